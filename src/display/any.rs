@@ -0,0 +1,35 @@
+use {
+    crate::parse::formats::any::*,
+    std::fmt::{Display, Formatter, Result}
+};
+
+impl Display for AnyFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        f.write_str(self.name())
+    }
+}
+
+impl Display for AnyMap {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            AnyMap::Standard(map) => map.fmt(f),
+            AnyMap::Valve(map) => map.fmt(f),
+            AnyMap::Quake2(map) => map.fmt(f),
+            AnyMap::Quake2Valve(map) => map.fmt(f),
+            AnyMap::Quake3(map) => map.fmt(f),
+            AnyMap::Doom3(map) => map.fmt(f)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parse::formats::AnyMap;
+
+    #[test]
+    fn roundtrip() {
+        let map = AnyMap::parse(include_str!("../../examples/example.map")).unwrap();
+
+        assert_eq!(AnyMap::parse(&map.to_string()).unwrap(), map)
+    }
+}
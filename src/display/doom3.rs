@@ -0,0 +1,80 @@
+use {
+    crate::parse::formats::doom3::*,
+    std::fmt::{Display, Formatter, Result}
+};
+
+impl Display for Version {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "Version {}", self.0)
+    }
+}
+
+impl Display for Plane {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let normal = &self.equation.normal;
+        let alignment = &self.texture.alignment;
+        write!(
+            f,
+            r#"( {} {} {} {} ) {} "{}" {} {} {}"#,
+            normal.x,
+            normal.y,
+            normal.z,
+            -self.equation.distance,
+            alignment.matrix,
+            self.texture.name,
+            alignment.content_flags,
+            alignment.surface_flags,
+            alignment.value
+        )
+    }
+}
+
+impl Display for Brush {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        writeln!(f, "{{")?;
+        writeln!(f, "brushDef3")?;
+        writeln!(f, "{{")?;
+        for plane in self.planes.iter() {
+            writeln!(f, "{}", plane)?
+        }
+        writeln!(f, "}}")?;
+        write!(f, "}}")
+    }
+}
+
+impl Display for Patch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        writeln!(f, "{{")?;
+        match self.subdivisions {
+            Some(_) => writeln!(f, "patchDef3")?,
+            None => writeln!(f, "patchDef2")?
+        }
+        writeln!(f, "{{")?;
+        writeln!(f, r#""{}""#, self.texture)?;
+        write!(f, "( {} {} ", self.width(), self.height())?;
+        if let Some(subdivisions) = self.subdivisions {
+            write!(f, "{} {} ", subdivisions.horizontal, subdivisions.vertical)?
+        }
+        writeln!(f, "{} {} {} )", self.content_flags, self.surface_flags, self.value)?;
+        writeln!(f, "(")?;
+        for column in self.control_points.iter() {
+            write!(f, "( ")?;
+            for point in column.iter() {
+                write!(f, "{} ", point)?
+            }
+            writeln!(f, ")")?
+        }
+        writeln!(f, ")")?;
+        writeln!(f, "}}")?;
+        write!(f, "}}")
+    }
+}
+
+impl Display for Primitive {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            Primitive::Brush(brush) => brush.fmt(f),
+            Primitive::Patch(patch) => patch.fmt(f)
+        }
+    }
+}
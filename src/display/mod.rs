@@ -1,3 +1,9 @@
 mod shared;
 mod standard;
-mod valve;
\ No newline at end of file
+mod valve;
+mod quake2;
+mod quake3;
+mod doom3;
+mod any;
+
+pub use shared::DisplayFields;
@@ -0,0 +1,28 @@
+use {
+    crate::parse::formats::quake2::*,
+    std::fmt::{Display, Formatter, Result}
+};
+
+impl Display for Contents {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{}", self.bits())
+    }
+}
+
+impl Display for SurfaceFlags {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{}", self.bits())
+    }
+}
+
+impl Display for SurfaceAttributes {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{} {} {}", self.contents, self.flags, self.value)
+    }
+}
+
+impl <TA: Display> Display for TextureAlignment<TA> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{} {}", self.base, self.attributes)
+    }
+}
@@ -0,0 +1,79 @@
+use {
+    crate::parse::formats::quake3::*,
+    std::fmt::{Display, Formatter, Result}
+};
+
+impl Display for TextureMatrix {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "( ( {} ) ( {} ) )", self.u, self.v)
+    }
+}
+
+impl Display for Brush {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        writeln!(f, "{{")?;
+        writeln!(f, "brushDef")?;
+        writeln!(f, "{{")?;
+        for plane in self.planes.iter() {
+            for point in plane.points.iter() {
+                write!(f, "( {} ) ", point)?
+            }
+            let alignment = &plane.texture.alignment;
+            writeln!(
+                f,
+                "{} {} {} {} {}",
+                alignment.matrix,
+                plane.texture.name,
+                alignment.content_flags,
+                alignment.surface_flags,
+                alignment.value
+            )?
+        }
+        writeln!(f, "}}")?;
+        write!(f, "}}")
+    }
+}
+
+impl Display for ControlPoint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "( {} {} {} {} {} )", self.position.x, self.position.y, self.position.z, self.uv.x, self.uv.y)
+    }
+}
+
+impl Display for Patch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        writeln!(f, "{{")?;
+        writeln!(f, "patchDef2")?;
+        writeln!(f, "{{")?;
+        writeln!(f, "{}", self.texture)?;
+        writeln!(
+            f,
+            "( {} {} {} {} {} )",
+            self.width(),
+            self.height(),
+            self.content_flags,
+            self.surface_flags,
+            self.value
+        )?;
+        writeln!(f, "(")?;
+        for column in self.control_points.iter() {
+            write!(f, "( ")?;
+            for point in column.iter() {
+                write!(f, "{} ", point)?
+            }
+            writeln!(f, ")")?
+        }
+        writeln!(f, ")")?;
+        writeln!(f, "}}")?;
+        write!(f, "}}")
+    }
+}
+
+impl Display for Primitive {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            Primitive::Brush(brush) => brush.fmt(f),
+            Primitive::Patch(patch) => patch.fmt(f)
+        }
+    }
+}
@@ -3,6 +3,8 @@ use {
         Map,
         parse::formats::{
             Format,
+            Header,
+            NoHeader,
             shared::*
         }
     },
@@ -16,13 +18,13 @@ impl Display for Vector3 {
 }
 
 
-impl <TA: Display> Display for Texture<TA> {
+impl <TA: Display, S: Display> Display for Texture<TA, S> {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
         write!(f, "{} {}", self.name, self.alignment)
     }
 }
 
-impl <TA: Display> Display for Plane<TA> {
+impl <TA: Display, S: Display> Display for Plane<TA, S> {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
         for point in self.points.iter() {
             write!(f, "( {} ) ", point)?
@@ -31,7 +33,7 @@ impl <TA: Display> Display for Plane<TA> {
     }
 }
 
-impl <TA: Display> Display for Brush<TA> {
+impl <TA: Display, S: Display> Display for Brush<TA, S> {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
         writeln!(f, "{{")?;
         for plane in self.planes.iter() {
@@ -41,16 +43,49 @@ impl <TA: Display> Display for Brush<TA> {
     }
 }
 
-impl Display for Fields {
+/// Writes [Fields](Fields) with their strings encoded with the [Escaping](Escaping)
+/// of choice. Returned by [Fields::display](Fields::display).
+pub struct DisplayFields<'a, S> {
+    fields: &'a Fields<S>,
+    escaping: Escaping
+}
+
+impl <S: AsRef<str>> Fields<S> {
+    /// Returns a value that writes the fields with their strings encoded
+    /// with `escaping`. The `Display` implementation of [Fields](Fields)
+    /// uses [Escaping::Raw](Escaping::Raw), which writes the strings as
+    /// they are. [Escaping::Verbatim](Escaping::Verbatim) writes quotes
+    /// as `'`, so only [Escaping::Backslash](Escaping::Backslash) can
+    /// write any string.
+    pub fn display(&self, escaping: Escaping) -> DisplayFields<'_, S> {
+        DisplayFields {
+            fields: self,
+            escaping
+        }
+    }
+}
+
+impl <S: AsRef<str>> Display for DisplayFields<'_, S> {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
-        for (key, value) in self.iter() {
-            writeln!(f, r#""{}" "{}""#, key, value)?
+        for (key, value) in self.fields.iter() {
+            writeln!(
+                f,
+                r#""{}" "{}""#,
+                self.escaping.encode(key.as_ref()),
+                self.escaping.encode(value.as_ref())
+            )?
         }
         Ok(())
     }
 }
 
-impl <B: Display> Display for Entity<B> {
+impl <S: AsRef<str>> Display for Fields<S> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        self.display(Escaping::Raw).fmt(f)
+    }
+}
+
+impl <B: Display, S: AsRef<str>> Display for Entity<B, S> {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
         writeln!(f, "{{")?;
         write!(f, "{}", self.fields)?;
@@ -61,12 +96,22 @@ impl <B: Display> Display for Entity<B> {
     }
 }
 
+impl Display for NoHeader {
+    fn fmt(&self, _f: &mut Formatter<'_>) -> Result {
+        Ok(())
+    }
+}
+
 impl <F> Display for Map<F>
 where
     F: Format,
+    F::Header: Display,
     F::Entity: Display
 {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        if !self.header.is_empty() {
+            writeln!(f, "{}", self.header)?
+        }
         for ent in self.entities.iter() {
             writeln!(f, "{}", ent)?
         }
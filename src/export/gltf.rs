@@ -0,0 +1,508 @@
+//! Export to glTF 2.0 as a single, self-contained JSON file, whose
+//! binary data is embedded as a base64 data URI.
+
+use {
+    std::{
+        io,
+        borrow::Borrow,
+        collections::HashSet,
+        fmt::Write as _
+    },
+    super::{Polygon, Polygons, TextureSizes},
+    crate::{
+        Map,
+        write::format_number,
+        parse::formats::{
+            Format,
+            shared::{Entity, Fields, Vector3}
+        }
+    }
+};
+
+/// The options of [write_gltf](write_gltf).
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct GltfOptions {
+    pub texture_sizes: TextureSizes
+}
+
+const FLOAT: u32 = 5126;
+const UNSIGNED_INT: u32 = 5125;
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+
+/// Writes the map as a glTF scene with a node for every entity, named after
+/// its `targetname`, or its `classname` if it has none. The fields of the
+/// entity are stored in the `extras` of its node, where only the last value
+/// of keys that appear more than once is kept. Point entities become empty
+/// nodes, which are placed at their `origin`, if it's valid. The brushes of
+/// an entity become a single mesh, with one primitive per texture, and
+/// every texture becomes a material named after it, which doesn't reference
+/// any images.
+///
+/// Like glTF requires, the coordinates are converted from the Z-up
+/// coordinates of maps to Y-up, but they aren't scaled to meters.
+pub fn write_gltf<F, B, S, W>(map: &Map<F>, options: &GltfOptions, mut out: W) -> io::Result<()>
+where
+    F: Format<Entity = Entity<B, S>>,
+    B: Polygons,
+    S: Borrow<str>,
+    W: io::Write
+{
+    let mut scene = Scene {
+        options,
+        buffer: Vec::new(),
+        views: Vec::new(),
+        accessors: Vec::new(),
+        materials: Vec::new(),
+        meshes: Vec::new()
+    };
+
+    let nodes: Vec<Node<'_, S>> = map.entities
+        .iter()
+        .map(|entity| {
+            let name = entity.fields
+                .get("targetname")
+                .or_else(|| entity.fields.get("classname"))
+                .map_or("", Borrow::borrow);
+
+            let polygons: Vec<Polygon<'_>> = entity.brushes
+                .iter()
+                .flat_map(Polygons::polygons)
+                .collect();
+            let mesh = if polygons.is_empty() {
+                None
+            } else {
+                Some(scene.mesh(&polygons))
+            };
+
+            // only point entities are placed, the brushes are already in place
+            let translation = if entity.brushes.is_empty() {
+                entity.origin()
+                    .ok()
+                    .flatten()
+                    .map(y_up)
+            } else {
+                None
+            };
+
+            Node { name, mesh, translation, fields: &entity.fields }
+        })
+        .collect();
+
+    let json = scene.json(&nodes)
+        .expect("formatting into a String can't fail");
+    out.write_all(json.as_bytes())
+}
+
+struct Node<'m, S> {
+    name: &'m str,
+    mesh: Option<usize>,
+    translation: Option<[f32; 3]>,
+    fields: &'m Fields<S>
+}
+
+struct Primitive {
+    material: usize,
+    positions: usize,
+    normals: usize,
+    uvs: usize,
+    indices: usize
+}
+
+struct Accessor {
+    view: usize,
+    component_type: u32,
+    count: usize,
+    kind: &'static str,
+    bounds: Option<([f32; 3], [f32; 3])>
+}
+
+struct View {
+    offset: usize,
+    length: usize,
+    target: u32
+}
+
+/// The vertices of the polygons of a mesh with the same texture.
+#[derive(Default)]
+struct Vertices {
+    positions: Vec<[f32; 3]>,
+    normals: Vec<[f32; 3]>,
+    uvs: Vec<[f32; 2]>,
+    indices: Vec<u32>
+}
+
+struct Scene<'o, 'm> {
+    options: &'o GltfOptions,
+    buffer: Vec<u8>,
+    views: Vec<View>,
+    accessors: Vec<Accessor>,
+    materials: Vec<&'m str>,
+    meshes: Vec<Vec<Primitive>>
+}
+
+impl <'m> Scene<'_, 'm> {
+    /// Adds a mesh consisting of the polygons, returning its index.
+    fn mesh(&mut self, polygons: &[Polygon<'m>]) -> usize {
+        let mut groups: Vec<(usize, Vertices)> = Vec::new();
+
+        for polygon in polygons {
+            let material = match self.materials.iter().position(|&name| name == polygon.texture) {
+                Some(material) => material,
+                None => {
+                    self.materials.push(polygon.texture);
+                    self.materials.len() - 1
+                }
+            };
+            let group = match groups.iter().position(|(index, _)| *index == material) {
+                Some(group) => group,
+                None => {
+                    groups.push((material, Vertices::default()));
+                    groups.len() - 1
+                }
+            };
+            let vertices = &mut groups[group].1;
+
+            // the polygons are convex, so they can be triangulated as a fan
+            let first = vertices.positions.len() as u32;
+            for index in 1..polygon.vertices.len().saturating_sub(1) as u32 {
+                vertices.indices.extend([first, first + index, first + index + 1])
+            }
+
+            let (width, height) = self.options.texture_sizes.get(polygon.texture);
+            for vertex in polygon.vertices.iter() {
+                vertices.positions.push(y_up(vertex.position));
+                vertices.normals.push(y_up(polygon.normal));
+                vertices.uvs.push([vertex.uv.x / width as f32, vertex.uv.y / height as f32])
+            }
+        }
+
+        let primitives = groups
+            .into_iter()
+            .map(|(material, vertices)| Primitive {
+                material,
+                positions: self.accessor(&vertices.positions, "VEC3", true),
+                normals: self.accessor(&vertices.normals, "VEC3", false),
+                uvs: self.accessor(&vertices.uvs, "VEC2", false),
+                indices: self.indices(&vertices.indices)
+            })
+            .collect();
+
+        self.meshes.push(primitives);
+        self.meshes.len() - 1
+    }
+
+    /// Adds an accessor of vertex attributes, returning its index. Positions need bounds.
+    fn accessor<const N: usize>(&mut self, values: &[[f32; N]], kind: &'static str, bounds: bool) -> usize {
+        let bounds = if bounds {
+            let mut min = [f32::INFINITY; 3];
+            let mut max = [f32::NEG_INFINITY; 3];
+            for value in values {
+                for (axis, &component) in value.iter().enumerate() {
+                    min[axis] = min[axis].min(component);
+                    max[axis] = max[axis].max(component)
+                }
+            }
+            Some((min, max))
+        } else {
+            None
+        };
+
+        let view = self.view(values.iter().flatten().map(|value| value.to_le_bytes()), ARRAY_BUFFER);
+        self.accessors.push(Accessor { view, component_type: FLOAT, count: values.len(), kind, bounds });
+        self.accessors.len() - 1
+    }
+
+    fn indices(&mut self, indices: &[u32]) -> usize {
+        let view = self.view(indices.iter().map(|index| index.to_le_bytes()), ELEMENT_ARRAY_BUFFER);
+        self.accessors.push(Accessor {
+            view,
+            component_type: UNSIGNED_INT,
+            count: indices.len(),
+            kind: "SCALAR",
+            bounds: None
+        });
+        self.accessors.len() - 1
+    }
+
+    /// Appends the values to the buffer as a new view, returning its index.
+    /// All values are 4 bytes long, so every view is aligned.
+    fn view<I: Iterator<Item = [u8; 4]>>(&mut self, values: I, target: u32) -> usize {
+        let offset = self.buffer.len();
+        self.buffer.extend(values.flatten());
+        self.views.push(View { offset, length: self.buffer.len() - offset, target });
+        self.views.len() - 1
+    }
+
+    fn json<S: Borrow<str>>(&self, nodes: &[Node<'_, S>]) -> Result<String, std::fmt::Error> {
+        let mut json = String::new();
+        let out = &mut json;
+
+        // glTF doesn't allow empty lists of nodes, so they're left out for empty maps
+        write!(out, r#"{{"asset":{{"version":"2.0","generator":"nomap"}},"scene":0,"scenes":[{{"#)?;
+        if !nodes.is_empty() {
+            write!(out, r#""nodes":["#)?;
+            list(out, 0..nodes.len(), |out, index| write!(out, "{}", index))?;
+            out.push(']')
+        }
+        write!(out, "}}],")?;
+
+        if !nodes.is_empty() {
+            write!(out, r#""nodes":["#)?;
+            list(out, nodes, |out, node| {
+                write!(out, r#"{{"name":"#)?;
+                string(out, node.name)?;
+                if let Some(mesh) = node.mesh {
+                    write!(out, r#","mesh":{}"#, mesh)?
+                }
+                if let Some(translation) = node.translation {
+                    write!(out, r#","translation":"#)?;
+                    numbers(out, &translation)?
+                }
+
+                // the last value of every key, in the order of their last occurrence
+                let mut keys = HashSet::new();
+                let mut fields: Vec<(&str, &str)> = node.fields
+                    .iter()
+                    .rev()
+                    .map(|(key, value)| (key.borrow(), value.borrow()))
+                    .filter(|&(key, _)| keys.insert(key))
+                    .collect();
+                fields.reverse();
+
+                write!(out, r#","extras":{{"#)?;
+                list(out, fields, |out, (key, value)| {
+                    string(out, key)?;
+                    out.push(':');
+                    string(out, value)
+                })?;
+                write!(out, "}}}}")
+            })?;
+            write!(out, "],")?;
+        }
+
+        if !self.meshes.is_empty() {
+            write!(out, r#""meshes":["#)?;
+            list(out, &self.meshes, |out, primitives| {
+                write!(out, r#"{{"primitives":["#)?;
+                list(out, primitives, |out, primitive| write!(
+                    out,
+                    r#"{{"attributes":{{"POSITION":{},"NORMAL":{},"TEXCOORD_0":{}}},"indices":{},"material":{}}}"#,
+                    primitive.positions, primitive.normals, primitive.uvs, primitive.indices, primitive.material
+                ))?;
+                write!(out, "]}}")
+            })?;
+            write!(out, "],")?;
+
+            write!(out, r#""materials":["#)?;
+            list(out, &self.materials, |out, name| {
+                write!(out, r#"{{"name":"#)?;
+                string(out, name)?;
+                write!(out, r#","pbrMetallicRoughness":{{"metallicFactor":0}}}}"#)
+            })?;
+            write!(out, "],")?;
+
+            write!(out, r#""accessors":["#)?;
+            list(out, &self.accessors, |out, accessor| {
+                write!(
+                    out,
+                    r#"{{"bufferView":{},"componentType":{},"count":{},"type":"{}""#,
+                    accessor.view, accessor.component_type, accessor.count, accessor.kind
+                )?;
+                if let Some((min, max)) = accessor.bounds {
+                    write!(out, r#","min":"#)?;
+                    numbers(out, &min)?;
+                    write!(out, r#","max":"#)?;
+                    numbers(out, &max)?
+                }
+                out.push('}');
+                Ok(())
+            })?;
+            write!(out, "],")?;
+
+            write!(out, r#""bufferViews":["#)?;
+            list(out, &self.views, |out, view| write!(
+                out,
+                r#"{{"buffer":0,"byteOffset":{},"byteLength":{},"target":{}}}"#,
+                view.offset, view.length, view.target
+            ))?;
+            write!(out, "],")?;
+
+            write!(
+                out,
+                r#""buffers":[{{"byteLength":{},"uri":"data:application/octet-stream;base64,"#,
+                self.buffer.len()
+            )?;
+            base64(out, &self.buffer);
+            write!(out, r#""}}],"#)?;
+        }
+
+        // every entry ends with a comma, which isn't allowed after the last one
+        json.pop();
+        json.push('}');
+        Ok(json)
+    }
+}
+
+/// Converts from the Z-up coordinates of maps to the Y-up coordinates of glTF.
+fn y_up(vector: Vector3) -> [f32; 3] {
+    [vector.x, vector.z, -vector.y]
+}
+
+/// Writes the items separated by commas.
+fn list<I, T>(out: &mut String, items: I, mut item: T) -> std::fmt::Result
+where
+    I: IntoIterator,
+    T: FnMut(&mut String, I::Item) -> std::fmt::Result
+{
+    for (index, value) in items.into_iter().enumerate() {
+        if index > 0 {
+            out.push(',')
+        }
+        item(out, value)?
+    }
+    Ok(())
+}
+
+fn numbers(out: &mut String, numbers: &[f32]) -> std::fmt::Result {
+    out.push('[');
+    list(out, numbers, |out, &number| format_number(out, number, None))?;
+    out.push(']');
+    Ok(())
+}
+
+/// Writes a JSON string, escaping quotes, backslashes and control characters.
+fn string(out: &mut String, value: &str) -> std::fmt::Result {
+    out.push('"');
+    for character in value.chars() {
+        match character {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            character if (character as u32) < 0x20 => write!(out, "\\u{:04x}", character as u32)?,
+            character => out.push(character)
+        }
+    }
+    out.push('"');
+    Ok(())
+}
+
+/// Writes the bytes in standard base64 with padding.
+fn base64(out: &mut String, bytes: &[u8]) {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    for chunk in bytes.chunks(3) {
+        let mut group = [0; 3];
+        group[..chunk.len()].copy_from_slice(chunk);
+        let bits = u32::from_be_bytes([0, group[0], group[1], group[2]]);
+
+        for index in 0..4 {
+            if index <= chunk.len() {
+                out.push(ALPHABET[(bits >> (18 - 6 * index) & 0x3f) as usize] as char)
+            } else {
+                out.push('=')
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        crate::parse::formats::{Standard, NoHeader},
+        serde_json::Value
+    };
+
+    fn export(map: &str) -> Value {
+        let map = crate::parse::<Standard>(map).unwrap();
+        let mut out = Vec::new();
+        write_gltf(&map, &GltfOptions::default(), &mut out).unwrap();
+
+        serde_json::from_slice(&out).unwrap()
+    }
+
+    #[test]
+    fn scene() {
+        let gltf = export(include_str!("../../examples/example.map"));
+
+        assert_eq!(gltf["asset"]["version"], "2.0");
+        assert_eq!(gltf["nodes"][0]["name"], "worldspawn");
+        assert_eq!(gltf["nodes"][0]["extras"]["wad"], "../wads/Q.wad");
+        assert_eq!(gltf["nodes"][0]["mesh"], 0);
+        assert_eq!(gltf["materials"][0]["name"], "sfloor4_6");
+
+        let buffer = &gltf["buffers"][0];
+        let uri = buffer["uri"].as_str().unwrap();
+        let data = uri.strip_prefix("data:application/octet-stream;base64,").unwrap();
+        let length = buffer["byteLength"].as_u64().unwrap() as usize;
+        assert_eq!(data.len(), length.div_ceil(3) * 4);
+
+        for primitive in gltf["meshes"].as_array().unwrap().iter().flat_map(|mesh| mesh["primitives"].as_array().unwrap()) {
+            let count = |attribute: &str| gltf["accessors"][primitive["attributes"][attribute].as_u64().unwrap() as usize]["count"].clone();
+            assert_eq!(count("POSITION"), count("NORMAL"));
+            assert_eq!(count("POSITION"), count("TEXCOORD_0"));
+            let indices = &gltf["accessors"][primitive["indices"].as_u64().unwrap() as usize];
+            assert_eq!(indices["componentType"], UNSIGNED_INT);
+            assert_eq!(indices["count"].as_u64().unwrap() % 3, 0)
+        }
+    }
+
+    #[test]
+    fn entities() {
+        let gltf = export(r#"
+{
+"classname" "worldspawn"
+"message" "say \"hi\"\\"
+}
+{
+"classname" "light"
+"targetname" "lamp"
+"origin" "8 16 32"
+"light" "200"
+"light" "300"
+}
+"#);
+
+        assert!(gltf.get("meshes").is_none());
+        assert!(gltf.get("buffers").is_none());
+        assert_eq!(gltf["nodes"][0]["extras"]["message"], r#"say \"hi\"\\"#);
+        assert_eq!(gltf["nodes"][1]["name"], "lamp");
+        assert!(gltf["nodes"][1].get("mesh").is_none());
+        assert_eq!(gltf["nodes"][1]["translation"], serde_json::json!([8, 32, -16]));
+        assert_eq!(gltf["nodes"][1]["extras"]["light"], "300");
+        assert_eq!(gltf["scenes"][0]["nodes"], serde_json::json!([0, 1]))
+    }
+
+    #[test]
+    fn empty() {
+        let map = Map::<Standard> { header: NoHeader, entities: Vec::new() };
+        let mut out = Vec::new();
+        write_gltf(&map, &GltfOptions::default(), &mut out).unwrap();
+        let gltf: Value = serde_json::from_slice(&out).unwrap();
+
+        assert_eq!(gltf["scenes"], serde_json::json!([{}]));
+        assert!(gltf.get("nodes").is_none());
+        assert!(gltf.get("meshes").is_none())
+    }
+
+    #[test]
+    fn encoding() {
+        let encode = |bytes: &[u8]| {
+            let mut out = String::new();
+            base64(&mut out, bytes);
+            out
+        };
+        assert_eq!(encode(b""), "");
+        assert_eq!(encode(b"f"), "Zg==");
+        assert_eq!(encode(b"fo"), "Zm8=");
+        assert_eq!(encode(b"foo"), "Zm9v");
+        assert_eq!(encode(b"foobar"), "Zm9vYmFy");
+
+        let mut out = String::new();
+        string(&mut out, "a\"b\\c\n\u{1}").unwrap();
+        assert_eq!(out, r#""a\"b\\c\n\u0001""#)
+    }
+}
@@ -0,0 +1,175 @@
+//! Export of the geometry of maps to formats understood by 3D software,
+//! for previewing maps without compiling them first. Every face of
+//! every brush becomes a polygon with the texture of its plane, whose
+//! texture coordinates are computed like by the compilers.
+//!
+//! Only maps of the formats whose brushes implement [Polygons](Polygons) can
+//! be exported, which are the Standard, Valve and both Quake 2 formats. Maps
+//! of the Quake 3 and Doom 3 formats can't be exported, since their texture
+//! matrices don't have a [Projection](crate::geometry::Projection) and their
+//! entities contain patches.
+//! ```
+//! use nomap::{formats::Standard, export::obj::{self, ObjOptions}};
+//!
+//! let map = nomap::parse::<Standard>(include_str!("../../examples/example.map")).unwrap();
+//! let options = ObjOptions {
+//!     material_library: Some(String::from("example.mtl")),
+//!     ..ObjOptions::default()
+//! };
+//!
+//! let (mut out, mut materials) = (Vec::new(), Vec::new());
+//! obj::write_obj(&map, &options, &mut out).unwrap();
+//! obj::write_mtl(&map, &options, &mut materials).unwrap();
+//!
+//! assert!(out.starts_with(b"mtllib example.mtl\n"));
+//! assert!(materials.starts_with(b"newmtl sfloor4_6\n"));
+//! ```
+
+pub mod obj;
+pub mod gltf;
+
+use {
+    std::{
+        borrow::Borrow,
+        collections::HashMap
+    },
+    crate::{
+        Map,
+        geometry::{Vertex, Projection},
+        parse::formats::{
+            Format,
+            shared::{Brush, Entity, Vector3}
+        }
+    }
+};
+
+/// A convex polygon on a face of a brush, whose vertices are in
+/// counter-clockwise order when looking at its front. The `uv`
+/// of the vertices are texel coordinates.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polygon<'a> {
+    pub texture: &'a str,
+    pub normal: Vector3,
+    pub vertices: Vec<Vertex>
+}
+
+/// Trait for brushes that can be exported, which
+/// is implemented for brushes of formats with a
+/// [Projection](crate::geometry::Projection).
+/// The brush primitives of the Quake 3 and Doom 3
+/// formats don't implement it.
+pub trait Polygons {
+    /// Returns a polygon for every face of the brush.
+    fn polygons(&self) -> Vec<Polygon<'_>>;
+
+    /// Returns the texture of every plane of the brush, without
+    /// computing its polygons. Planes without a face are included.
+    fn textures(&self) -> Vec<&str>;
+}
+
+impl <TA: Projection, S: Borrow<str>> Polygons for Brush<TA, S> {
+    /// Returns the faces of the brush's [polyhedron](Brush::polyhedron)
+    /// in the order of their planes.
+    fn polygons(&self) -> Vec<Polygon<'_>> {
+        let polyhedron = self.polyhedron();
+
+        polyhedron.faces
+            .iter()
+            .filter_map(|face| {
+                let plane = &self.planes[face.plane];
+                let normal = plane.equation()?.normal;
+                let projection = plane.projection()?;
+
+                Some(Polygon {
+                    texture: plane.texture.name.borrow(),
+                    normal,
+                    vertices: face.winding
+                        .iter()
+                        .map(|&index| {
+                            let position = polyhedron.vertices[index];
+                            Vertex { position, uv: projection.texel(position) }
+                        })
+                        .collect()
+                })
+            })
+            .collect()
+    }
+
+    fn textures(&self) -> Vec<&str> {
+        self.planes
+            .iter()
+            .map(|plane| plane.texture.name.borrow())
+            .collect()
+    }
+}
+
+/// The sizes of textures in pixels, which texture coordinates are divided by.
+/// Maps don't contain them, so they have to be looked up in the texture files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextureSizes {
+    /// The width and height of textures by name.
+    pub sizes: HashMap<String, (u32, u32)>,
+    /// The size of textures missing from `sizes`, 64x64 by default.
+    pub default: (u32, u32)
+}
+
+impl Default for TextureSizes {
+    fn default() -> Self {
+        TextureSizes {
+            sizes: HashMap::new(),
+            default: (64, 64)
+        }
+    }
+}
+
+impl TextureSizes {
+    pub fn get(&self, texture: &str) -> (u32, u32) {
+        self.sizes
+            .get(texture)
+            .copied()
+            .unwrap_or(self.default)
+    }
+}
+
+/// Returns the names of all textures used by the planes of
+/// brushes of the map, in the order they first appear in.
+pub(crate) fn textures<'m, F, B, S>(map: &'m Map<F>) -> Vec<&'m str>
+where
+    F: Format<Entity = Entity<B, S>>,
+    B: Polygons + 'm,
+    S: 'm
+{
+    let mut textures: Vec<&str> = Vec::new();
+
+    for brush in map.entities.iter().flat_map(|entity| entity.brushes.iter()) {
+        for texture in brush.textures() {
+            if !textures.contains(&texture) {
+                textures.push(texture)
+            }
+        }
+    }
+
+    textures
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        crate::parse::formats::Standard
+    };
+
+    #[test]
+    fn polygons() {
+        let map = crate::parse::<Standard>(include_str!("../../examples/example.map")).unwrap();
+        let brush = &map.entities[0].brushes[0];
+        let polygons = brush.polygons();
+
+        assert_eq!(polygons.len(), 6);
+        for polygon in polygons.iter() {
+            assert_eq!(polygon.texture, "sfloor4_6");
+            assert_eq!(polygon.vertices.len(), 4)
+        }
+        assert_eq!(textures(&map)[..2], ["sfloor4_6", "htek04_1"])
+    }
+}
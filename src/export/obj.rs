@@ -0,0 +1,304 @@
+//! Export to the Wavefront OBJ format, with the materials
+//! in a separate MTL file.
+
+use {
+    std::{
+        io,
+        borrow::Borrow
+    },
+    super::{Polygons, TextureSizes, textures},
+    crate::{
+        Map,
+        write::format_number,
+        parse::formats::{
+            Format,
+            shared::{Entity, Vector3}
+        }
+    }
+};
+
+/// The options of [write_obj](write_obj) and [write_mtl](write_mtl).
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ObjOptions {
+    pub grouping: Grouping,
+    pub texture_sizes: TextureSizes,
+    /// The name of the MTL file to reference with `mtllib`, if any.
+    pub material_library: Option<String>,
+    /// Appended to the names of textures to reference them as
+    /// the diffuse maps of the materials, like `.png`. If `None`,
+    /// the materials don't reference any textures.
+    pub texture_extension: Option<String>,
+    /// Whether to convert from the Z-up coordinates of maps to Y-up
+    /// coordinates, which most 3D software expects by default.
+    pub y_up: bool
+}
+
+/// How the faces are grouped into objects with `o` statements.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum Grouping {
+    /// All faces are part of the same object.
+    #[default]
+    None,
+    /// One object per entity, named `entity{N}_{classname}`, followed
+    /// by `_{targetname}` if the entity has one. Characters other than
+    /// ASCII letters, digits, `-`, `_` and `.` are replaced with `_`.
+    /// Entities without brushes are left out.
+    Entity,
+    /// One object per brush, named `entity{N}_brush{M}`.
+    Brush
+}
+
+/// Writes the faces of all brushes of the map as triangles. Every face gets
+/// its own vertices with texture coordinates, and its own normal. Faces are
+/// preceded by a `usemtl` statement whenever their texture differs from the
+/// previous face's, which names the material after the texture. The texture
+/// coordinates are flipped vertically, since they start at the bottom in OBJ.
+pub fn write_obj<F, B, S, W>(map: &Map<F>, options: &ObjOptions, mut out: W) -> io::Result<()>
+where
+    F: Format<Entity = Entity<B, S>>,
+    B: Polygons,
+    S: Borrow<str>,
+    W: io::Write
+{
+    if let Some(library) = options.material_library.as_ref() {
+        writeln!(out, "mtllib {}", library)?
+    }
+
+    let mut writer = ObjWriter {
+        out,
+        options,
+        buffer: String::new(),
+        vertices: 0,
+        normals: 0,
+        material: None
+    };
+
+    for (index, entity) in map.entities.iter().enumerate() {
+        if options.grouping == Grouping::Entity && !entity.brushes.is_empty() {
+            let classname = entity.fields.get("classname").map_or("", Borrow::borrow);
+            write!(writer.out, "o entity{}_{}", index, object_name(classname))?;
+            if let Some(targetname) = entity.fields.get("targetname") {
+                write!(writer.out, "_{}", object_name(targetname.borrow()))?
+            }
+            writeln!(writer.out)?
+        }
+
+        for (brush_index, brush) in entity.brushes.iter().enumerate() {
+            if options.grouping == Grouping::Brush {
+                writeln!(writer.out, "o entity{}_brush{}", index, brush_index)?
+            }
+            writer.brush(brush)?
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes a material for every texture used by [write_obj](write_obj),
+/// which are collected from the planes of the brushes.
+pub fn write_mtl<F, B, S, W>(map: &Map<F>, options: &ObjOptions, mut out: W) -> io::Result<()>
+where
+    F: Format<Entity = Entity<B, S>>,
+    B: Polygons,
+    W: io::Write
+{
+    for (index, texture) in textures(map).into_iter().enumerate() {
+        if index > 0 {
+            writeln!(out)?
+        }
+        writeln!(out, "newmtl {}", texture)?;
+        writeln!(out, "Kd 1 1 1")?;
+        if let Some(extension) = options.texture_extension.as_ref() {
+            writeln!(out, "map_Kd {}{}", texture, extension)?
+        }
+    }
+
+    Ok(())
+}
+
+/// Replaces the characters of `name` that can't be part of the name
+/// of an object, like the whitespace separating statements.
+fn object_name(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' | '.' => c,
+            _ => '_'
+        })
+        .collect()
+}
+
+struct ObjWriter<'o, W> {
+    out: W,
+    options: &'o ObjOptions,
+    /// Reused for formatting numbers.
+    buffer: String,
+    vertices: usize,
+    normals: usize,
+    material: Option<String>
+}
+
+impl <W: io::Write> ObjWriter<'_, W> {
+    fn brush<B: Polygons>(&mut self, brush: &B) -> io::Result<()> {
+        for polygon in brush.polygons() {
+            if self.material.as_deref() != Some(polygon.texture) {
+                writeln!(self.out, "usemtl {}", polygon.texture)?;
+                self.material = Some(polygon.texture.to_string())
+            }
+
+            let (width, height) = self.options.texture_sizes.get(polygon.texture);
+            for vertex in polygon.vertices.iter() {
+                self.line("v", &self.vector(vertex.position))?;
+                self.line("vt", &[vertex.uv.x / width as f32, -vertex.uv.y / height as f32])?
+            }
+            self.line("vn", &self.vector(polygon.normal))?;
+            self.normals += 1;
+
+            // the polygons are convex, so they can be triangulated as a fan
+            let first = self.vertices + 1;
+            for index in 1..polygon.vertices.len().saturating_sub(1) {
+                write!(self.out, "f")?;
+                for vertex in [first, first + index, first + index + 1] {
+                    write!(self.out, " {}/{}/{}", vertex, vertex, self.normals)?
+                }
+                writeln!(self.out)?
+            }
+            self.vertices += polygon.vertices.len()
+        }
+
+        Ok(())
+    }
+
+    fn vector(&self, vector: Vector3) -> [f32; 3] {
+        if self.options.y_up {
+            [vector.x, vector.z, -vector.y]
+        } else {
+            [vector.x, vector.y, vector.z]
+        }
+    }
+
+    fn line(&mut self, keyword: &str, numbers: &[f32]) -> io::Result<()> {
+        write!(self.out, "{}", keyword)?;
+        for &number in numbers {
+            self.buffer.clear();
+            format_number(&mut self.buffer, number, None)
+                .expect("formatting into a String can't fail");
+            write!(self.out, " {}", self.buffer)?
+        }
+        writeln!(self.out)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        crate::parse::formats::Standard
+    };
+
+    const CUBE: &str = r#"
+{
+"classname" "worldspawn"
+{
+( 0 0 0 ) ( 0 1 0 ) ( 0 0 1 ) wall 0 0 0 1 1
+( 64 0 0 ) ( 64 0 1 ) ( 64 1 0 ) wall 0 0 0 1 1
+( 0 0 0 ) ( 0 0 1 ) ( 1 0 0 ) wall 0 0 0 1 1
+( 0 64 0 ) ( 1 64 0 ) ( 0 64 1 ) wall 0 0 0 1 1
+( 0 0 0 ) ( 1 0 0 ) ( 0 1 0 ) floor 0 0 0 1 1
+( 0 0 64 ) ( 0 1 64 ) ( 1 0 64 ) floor 0 0 0 1 1
+}
+}
+{
+"classname" "func_door"
+"targetname" "door"
+{
+( 0 0 0 ) ( 0 1 0 ) ( 0 0 1 ) wall 0 0 0 1 1
+( 64 0 0 ) ( 64 0 1 ) ( 64 1 0 ) wall 0 0 0 1 1
+( 0 0 0 ) ( 0 0 1 ) ( 1 0 0 ) wall 0 0 0 1 1
+( 0 64 0 ) ( 1 64 0 ) ( 0 64 1 ) wall 0 0 0 1 1
+( 0 0 0 ) ( 1 0 0 ) ( 0 1 0 ) wall 0 0 0 1 1
+( 0 0 64 ) ( 0 1 64 ) ( 1 0 64 ) wall 0 0 0 1 1
+}
+}
+{
+"classname" "light"
+"origin" "32 32 32"
+}
+"#;
+
+    fn export(options: &ObjOptions) -> String {
+        let map = crate::parse::<Standard>(CUBE).unwrap();
+        let mut out = Vec::new();
+        write_obj(&map, options, &mut out).unwrap();
+
+        String::from_utf8(out).unwrap()
+    }
+
+    fn lines<'a>(obj: &'a str, keyword: &str) -> Vec<&'a str> {
+        obj.lines()
+            .filter(|line| line.split(' ').next() == Some(keyword))
+            .collect()
+    }
+
+    #[test]
+    fn geometry() {
+        let obj = export(&ObjOptions::default());
+
+        assert_eq!(lines(&obj, "v").len(), 48);
+        assert_eq!(lines(&obj, "vt").len(), 48);
+        assert_eq!(lines(&obj, "vn").len(), 12);
+        assert_eq!(lines(&obj, "f").len(), 24);
+        assert_eq!(lines(&obj, "usemtl"), ["usemtl wall", "usemtl floor", "usemtl wall"]);
+        assert!(lines(&obj, "o").is_empty());
+
+        // the first face lies on the west wall facing outwards
+        assert_eq!(lines(&obj, "vn")[0], "vn -1 0 0");
+        assert_eq!(lines(&obj, "f")[..2], ["f 1/1/1 2/2/1 3/3/1", "f 1/1/1 3/3/1 4/4/1"]);
+        assert!(lines(&obj, "vt").contains(&"vt 1 1"))
+    }
+
+    #[test]
+    fn options() {
+        let options = ObjOptions {
+            grouping: Grouping::Entity,
+            y_up: true,
+            material_library: Some(String::from("cube.mtl")),
+            ..ObjOptions::default()
+        };
+        let obj = export(&options);
+
+        assert!(obj.starts_with("mtllib cube.mtl\n"));
+        assert_eq!(lines(&obj, "o"), ["o entity0_worldspawn", "o entity1_func_door_door"]);
+        assert_eq!(lines(&obj, "vn")[4], "vn 0 -1 0");
+
+        let obj = export(&ObjOptions { grouping: Grouping::Brush, ..ObjOptions::default() });
+        assert_eq!(lines(&obj, "o"), ["o entity0_brush0", "o entity1_brush0"]);
+
+        let renamed = CUBE.replace(r#""door""#, r#""main door #2""#);
+        let map = crate::parse::<Standard>(&renamed).unwrap();
+        let mut out = Vec::new();
+        write_obj(&map, &ObjOptions { grouping: Grouping::Entity, ..ObjOptions::default() }, &mut out).unwrap();
+        assert_eq!(lines(&String::from_utf8(out).unwrap(), "o")[1], "o entity1_func_door_main_door__2")
+    }
+
+    #[test]
+    fn materials() {
+        let map = crate::parse::<Standard>(CUBE).unwrap();
+        let mut options = ObjOptions::default();
+        options.texture_sizes.sizes.insert(String::from("floor"), (128, 32));
+
+        let mut out = Vec::new();
+        write_mtl(&map, &options, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "newmtl wall\nKd 1 1 1\n\nnewmtl floor\nKd 1 1 1\n"
+        );
+
+        options.texture_extension = Some(String::from(".png"));
+        let mut out = Vec::new();
+        write_mtl(&map, &options, &mut out).unwrap();
+        assert!(String::from_utf8(out).unwrap().contains("map_Kd floor.png\n"));
+
+        let obj = export(&options);
+        assert!(lines(&obj, "vt").contains(&"vt 0.5 2"))
+    }
+}
@@ -0,0 +1,289 @@
+use {
+    std::borrow::Borrow,
+    super::Polyhedron,
+    crate::{
+        Map,
+        parse::formats::{
+            Format,
+            quake3,
+            doom3,
+            shared::{Brush, Entity, Vector3}
+        }
+    }
+};
+
+/// An axis-aligned bounding box.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Aabb {
+    pub min: Vector3,
+    pub max: Vector3
+}
+
+impl Aabb {
+    /// Creates a box spanning from `min` to `max`, which
+    /// are swapped per axis if they're the wrong way around.
+    pub fn new(min: Vector3, max: Vector3) -> Self {
+        Aabb::from_point(min).extended(max)
+    }
+
+    /// Creates a box of size zero containing only `point`.
+    pub fn from_point(point: Vector3) -> Self {
+        Aabb {
+            min: point,
+            max: point
+        }
+    }
+
+    /// Creates the smallest box containing all `points`,
+    /// or returns `None` if there are none.
+    pub fn from_points<I: IntoIterator<Item = Vector3>>(points: I) -> Option<Self> {
+        let mut points = points.into_iter();
+        let first = Aabb::from_point(points.next()?);
+
+        Some(points.fold(first, Aabb::extended))
+    }
+
+    /// Returns the box grown to contain `point`.
+    pub fn extended(self, point: Vector3) -> Self {
+        Aabb {
+            min: combine(self.min, point, f32::min),
+            max: combine(self.max, point, f32::max)
+        }
+    }
+
+    /// Returns the smallest box containing both boxes.
+    pub fn union(self, other: Self) -> Self {
+        Aabb {
+            min: combine(self.min, other.min, f32::min),
+            max: combine(self.max, other.max, f32::max)
+        }
+    }
+
+    /// Returns the box covered by both boxes, or `None` if they don't intersect.
+    /// Boxes that only touch intersect in a box of size zero along one axis.
+    pub fn intersection(self, other: Self) -> Option<Self> {
+        let intersection = Aabb {
+            min: combine(self.min, other.min, f32::max),
+            max: combine(self.max, other.max, f32::min)
+        };
+
+        if intersection.min.x <= intersection.max.x
+            && intersection.min.y <= intersection.max.y
+            && intersection.min.z <= intersection.max.z {
+            Some(intersection)
+        } else {
+            None
+        }
+    }
+
+    /// Returns `true` if the boxes intersect or touch.
+    pub fn intersects(self, other: Self) -> bool {
+        self.intersection(other).is_some()
+    }
+
+    /// Returns `true` if `point` lies inside the box or on its surface.
+    pub fn contains_point(self, point: Vector3) -> bool {
+        self.min.x <= point.x && point.x <= self.max.x
+            && self.min.y <= point.y && point.y <= self.max.y
+            && self.min.z <= point.z && point.z <= self.max.z
+    }
+
+    /// Returns `true` if `other` lies entirely inside the box.
+    pub fn contains(self, other: Self) -> bool {
+        self.contains_point(other.min) && self.contains_point(other.max)
+    }
+
+    pub fn size(self) -> Vector3 {
+        Vector3 {
+            x: self.max.x - self.min.x,
+            y: self.max.y - self.min.y,
+            z: self.max.z - self.min.z
+        }
+    }
+
+    pub fn center(self) -> Vector3 {
+        combine(self.min, self.max, |min, max| (min + max) / 2.)
+    }
+}
+
+fn combine(a: Vector3, b: Vector3, f: impl Fn(f32, f32) -> f32) -> Vector3 {
+    Vector3 {
+        x: f(a.x, b.x),
+        y: f(a.y, b.y),
+        z: f(a.z, b.z)
+    }
+}
+
+/// Trait for parts of a map that occupy space, which is
+/// implemented for brushes, patches, entities and maps.
+pub trait Bounded {
+    /// Returns the smallest box containing the geometry,
+    /// or `None` if there's none.
+    fn bounds(&self) -> Option<Aabb>;
+}
+
+impl <TA, S> Bounded for Brush<TA, S> {
+    /// Returns the bounds of the brush's [polyhedron](Brush::polyhedron).
+    /// Brushes that don't enclose a finite volume don't have bounds.
+    fn bounds(&self) -> Option<Aabb> {
+        polyhedron_bounds(self.polyhedron())
+    }
+}
+
+fn polyhedron_bounds(polyhedron: Polyhedron) -> Option<Aabb> {
+    if polyhedron.is_empty() || polyhedron.is_unbounded() {
+        None
+    } else {
+        Aabb::from_points(polyhedron.vertices)
+    }
+}
+
+impl Bounded for quake3::Brush {
+    fn bounds(&self) -> Option<Aabb> {
+        self.0.bounds()
+    }
+}
+
+impl Bounded for quake3::Patch {
+    /// Returns the bounds of the control points, which contain the whole patch.
+    fn bounds(&self) -> Option<Aabb> {
+        Aabb::from_points(
+            self.control_points
+                .iter()
+                .flatten()
+                .map(|point| point.position)
+        )
+    }
+}
+
+impl Bounded for quake3::Primitive {
+    fn bounds(&self) -> Option<Aabb> {
+        match self {
+            quake3::Primitive::Brush(brush) => brush.bounds(),
+            quake3::Primitive::Patch(patch) => patch.bounds()
+        }
+    }
+}
+
+impl Bounded for doom3::Brush {
+    fn bounds(&self) -> Option<Aabb> {
+        polyhedron_bounds(self.polyhedron())
+    }
+}
+
+impl Bounded for doom3::Primitive {
+    fn bounds(&self) -> Option<Aabb> {
+        match self {
+            doom3::Primitive::Brush(brush) => brush.bounds(),
+            doom3::Primitive::Patch(patch) => patch.patch.bounds()
+        }
+    }
+}
+
+impl <B: Bounded, S: Borrow<str>> Bounded for Entity<B, S> {
+    /// Returns the bounds of the entity's brushes, or a box of size zero
+    /// at its `origin` if it's a point entity. Invalid origins are ignored.
+    fn bounds(&self) -> Option<Aabb> {
+        if self.brushes.is_empty() {
+            return self.origin()
+                .ok()
+                .flatten()
+                .map(Aabb::from_point)
+        }
+
+        self.brushes
+            .iter()
+            .filter_map(Bounded::bounds)
+            .reduce(Aabb::union)
+    }
+}
+
+impl <F> Bounded for Map<F>
+where
+    F: Format,
+    F::Entity: Bounded
+{
+    fn bounds(&self) -> Option<Aabb> {
+        self.entities
+            .iter()
+            .filter_map(Bounded::bounds)
+            .reduce(Aabb::union)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        crate::{
+            geometry::brush::test::cuboid,
+            parse::formats::{Standard, shared::Fields}
+        }
+    };
+
+    fn vector(x: f32, y: f32, z: f32) -> Vector3 {
+        Vector3 { x, y, z }
+    }
+
+    #[test]
+    fn aabb() {
+        let a = Aabb::new(vector(0., 0., 0.), vector(64., 64., 64.));
+        let b = Aabb::new(vector(96., 32., 32.), vector(32., 128., 48.));
+
+        assert_eq!(b.min, vector(32., 32., 32.));
+        assert_eq!(a.union(b), Aabb::new(vector(0., 0., 0.), vector(96., 128., 64.)));
+        assert_eq!(a.intersection(b), Some(Aabb::new(vector(32., 32., 32.), vector(64., 64., 48.))));
+        assert!(a.union(b).contains(b));
+        assert!(!a.contains(b));
+        assert!(a.contains_point(vector(64., 0., 32.)));
+        assert_eq!(a.center(), vector(32., 32., 32.));
+        assert_eq!(b.size(), vector(64., 96., 16.));
+
+        let far = Aabb::from_point(vector(65., 0., 0.));
+        assert_eq!(a.intersection(far), None);
+        assert!(a.intersects(Aabb::from_point(vector(64., 0., 0.))));
+        assert_eq!(Aabb::from_points(Vec::new()), None)
+    }
+
+    #[test]
+    fn brush() {
+        let brush = cuboid([-16., -16., 0.], [16., 16., 72.]);
+
+        assert_eq!(brush.bounds(), Some(Aabb::new(vector(-16., -16., 0.), vector(16., 16., 72.))));
+
+        // without its top, the brush extends upwards indefinitely
+        let mut open = brush;
+        open.planes.pop();
+        assert_eq!(open.bounds(), None)
+    }
+
+    #[test]
+    fn entities() {
+        let mut entity = Entity {
+            fields: Fields::<String>::new(),
+            brushes: vec![
+                cuboid([0., 0., 0.], [32., 32., 32.]),
+                cuboid([64., 0., 0.], [128., 16., 16.])
+            ]
+        };
+        assert_eq!(entity.bounds(), Some(Aabb::new(vector(0., 0., 0.), vector(128., 32., 32.))));
+
+        entity.brushes.clear();
+        assert_eq!(entity.bounds(), None);
+        entity.set_origin(vector(8., -8., 24.));
+        assert_eq!(entity.bounds(), Some(Aabb::from_point(vector(8., -8., 24.))))
+    }
+
+    #[test]
+    fn map() {
+        let map = crate::parse::<Standard>(include_str!("../../examples/example.map")).unwrap();
+        let bounds = map.bounds().unwrap();
+        let limits = Aabb::new(vector(-4096., -4096., -4096.), vector(4096., 4096., 4096.));
+
+        assert!(limits.contains(bounds));
+        for entity in map.entities.iter() {
+            assert!(bounds.contains(entity.bounds().unwrap()))
+        }
+    }
+}
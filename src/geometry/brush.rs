@@ -0,0 +1,376 @@
+use {
+    super::math::{DVec3, DPlane},
+    crate::parse::formats::{
+        doom3,
+        shared::{Brush, Vector3}
+    }
+};
+
+/// The convex polyhedron enclosed by the half-spaces of a brush.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Polyhedron {
+    /// The distinct corners of the polyhedron.
+    pub vertices: Vec<Vector3>,
+    /// The faces of the polyhedron, in the order of the planes they lie on.
+    pub faces: Vec<Face>,
+    /// The indices of the planes that don't contribute a face, either
+    /// because they lie outside of the other planes' intersection, because
+    /// they're a duplicate of an earlier plane or because they're degenerate.
+    pub redundant: Vec<usize>
+}
+
+/// A face of a [Polyhedron](Polyhedron).
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Face {
+    /// The index of the plane of the brush the face lies on.
+    pub plane: usize,
+    /// The indices of the vertices of the face into
+    /// [Polyhedron::vertices](Polyhedron::vertices). They're in
+    /// counter-clockwise order when looking at the front of the face.
+    pub winding: Vec<usize>
+}
+
+impl Polyhedron {
+    /// Returns `true` if the polyhedron has fewer than four faces, meaning
+    /// that the brush doesn't enclose any volume. Brushes of zero thickness
+    /// still have faces on both sides.
+    pub fn is_empty(&self) -> bool {
+        self.faces.len() < 4
+    }
+
+    /// Returns `true` if the polyhedron reaches far beyond any map, which
+    /// is where faces end whose brush doesn't enclose a finite volume.
+    pub fn is_unbounded(&self) -> bool {
+        self.vertices
+            .iter()
+            .any(|vertex| [vertex.x, vertex.y, vertex.z]
+                .iter()
+                .any(|coordinate| coordinate.abs() as f64 >= BOUND / 2.)
+            )
+    }
+
+    /// Computes the polyhedron from the planes of a brush, where `None`
+    /// stands for a degenerate plane.
+    pub(crate) fn from_planes(planes: &[Option<DPlane>]) -> Self {
+        let mut polyhedron = Polyhedron::default();
+        let mut windings = Vec::with_capacity(planes.len());
+
+        for (index, plane) in planes.iter().enumerate() {
+            let winding = plane
+                .and_then(|plane| face_winding(plane, index, planes))
+                .filter(|winding| winding.len() >= 3);
+
+            match winding {
+                Some(winding) => windings.push((index, winding)),
+                None => polyhedron.redundant.push(index)
+            }
+        }
+
+        let mut vertices: Vec<DVec3> = Vec::new();
+        for (plane, points) in windings {
+            let mut winding = Vec::with_capacity(points.len());
+
+            for point in points {
+                let index = vertices
+                    .iter()
+                    .position(|vertex| (*vertex - point).length() < VERTEX_EPSILON)
+                    .unwrap_or_else(|| {
+                        vertices.push(point);
+                        vertices.len() - 1
+                    });
+
+                if winding.last() != Some(&index) && winding.first() != Some(&index) {
+                    winding.push(index)
+                }
+            }
+
+            if winding.len() >= 3 {
+                polyhedron.faces.push(Face { plane, winding })
+            } else {
+                polyhedron.redundant.push(plane)
+            }
+        }
+
+        polyhedron.redundant.sort_unstable();
+        polyhedron.vertices = vertices
+            .into_iter()
+            .map(Vector3::from)
+            .collect();
+        polyhedron
+    }
+}
+
+/// Points closer than this are considered to be on a plane.
+const PLANE_EPSILON: f64 = 1e-3;
+
+/// Vertices closer than this are merged.
+const VERTEX_EPSILON: f64 = 1e-2;
+
+/// Half the size of the winding each face starts out as, which is far
+/// larger than any map. Faces of brushes that don't enclose a finite
+/// volume extend this far.
+pub(super) const BOUND: f64 = (1 << 20) as f64;
+
+/// Clips a huge winding on `plane` by all other planes. Returns
+/// `None` if the face is clipped away entirely or is a duplicate.
+fn face_winding(plane: DPlane, index: usize, planes: &[Option<DPlane>]) -> Option<Vec<DVec3>> {
+    let mut winding = base_winding(plane);
+
+    for (other_index, other) in planes.iter().enumerate() {
+        let other = match other {
+            Some(other) if other_index != index => *other,
+            _ => continue
+        };
+
+        if is_same(plane, other) {
+            // only the first of two identical planes gets a face
+            if other_index < index {
+                return None
+            }
+            continue
+        }
+
+        winding = clip(&winding, other);
+        if winding.is_empty() {
+            return None
+        }
+    }
+
+    Some(winding)
+}
+
+pub(super) fn is_same(a: DPlane, b: DPlane) -> bool {
+    (a.normal - b.normal).length() < PLANE_EPSILON
+        && (a.distance - b.distance).abs() < PLANE_EPSILON
+}
+
+/// Returns a square on `plane` with a side length of twice the [BOUND](BOUND).
+fn base_winding(plane: DPlane) -> Vec<DVec3> {
+    let normal = plane.normal;
+
+    // the axis least aligned with the normal yields the most stable tangent
+    let axis = match normal.to_array().map(f64::abs) {
+        [x, y, z] if x <= y && x <= z => DVec3::new(1., 0., 0.),
+        [_, y, z] if y <= z => DVec3::new(0., 1., 0.),
+        _ => DVec3::new(0., 0., 1.)
+    };
+    let u = axis.cross(normal).normalize() * BOUND;
+    let v = normal.cross(u);
+    let origin = normal * plane.distance;
+
+    vec![
+        origin - u - v,
+        origin + u - v,
+        origin + u + v,
+        origin - u + v
+    ]
+}
+
+/// Removes the part of `winding` in front of `plane`.
+fn clip(winding: &[DVec3], plane: DPlane) -> Vec<DVec3> {
+    let distances = winding
+        .iter()
+        .map(|point| plane.distance_to(*point))
+        .collect::<Vec<_>>();
+
+    if distances.iter().all(|distance| *distance <= PLANE_EPSILON) {
+        return winding.to_vec()
+    }
+    if distances.iter().all(|distance| *distance >= -PLANE_EPSILON) {
+        return Vec::new()
+    }
+
+    let mut clipped = Vec::with_capacity(winding.len() + 1);
+    for (index, &point) in winding.iter().enumerate() {
+        let next_index = (index + 1) % winding.len();
+        let (distance, next_distance) = (distances[index], distances[next_index]);
+
+        if distance <= PLANE_EPSILON {
+            clipped.push(point)
+        }
+
+        let crosses = distance > PLANE_EPSILON && next_distance < -PLANE_EPSILON
+            || distance < -PLANE_EPSILON && next_distance > PLANE_EPSILON;
+        if crosses {
+            let next = winding[next_index];
+            clipped.push(point + (next - point) * (distance / (distance - next_distance)))
+        }
+    }
+    clipped
+}
+
+impl <TA, S> Brush<TA, S> {
+    /// Intersects the half-spaces of the brush's planes into a convex polyhedron.
+    /// The computations are carried out with `f64`s, so large coordinates
+    /// don't lose precision before the vertices are converted back.
+    pub fn polyhedron(&self) -> Polyhedron {
+        let planes = self.planes
+            .iter()
+            .map(|plane| DPlane::from_points(plane.points))
+            .collect::<Vec<_>>();
+
+        Polyhedron::from_planes(&planes)
+    }
+}
+
+impl doom3::Brush {
+    /// Intersects the half-spaces of the brush's planes into a convex polyhedron,
+    /// like [Brush::polyhedron](Brush::polyhedron).
+    pub fn polyhedron(&self) -> Polyhedron {
+        let planes = self.planes
+            .iter()
+            .map(|plane| DPlane::from_equation(
+                plane.equation.normal.into(),
+                plane.equation.distance as f64
+            ))
+            .collect::<Vec<_>>();
+
+        Polyhedron::from_planes(&planes)
+    }
+}
+
+#[cfg(test)]
+pub(super) mod test {
+    use {
+        super::*,
+        crate::parse::formats::{Standard, shared::Plane}
+    };
+
+    pub(crate) fn plane(points: [[f32; 3]; 3]) -> Plane<()> {
+        Plane {
+            points: points.map(|[x, y, z]| Vector3 { x, y, z }),
+            texture: Default::default()
+        }
+    }
+
+    /// A box from `min` to `max`, with the planes ordered -x, +x, -y, +y, -z, +z.
+    pub(crate) fn cuboid(min: [f32; 3], max: [f32; 3]) -> Brush<()> {
+        let ([x0, y0, z0], [x1, y1, z1]) = (min, max);
+
+        Brush {
+            planes: vec![
+                plane([[x0, y0, z0], [x0, y0 + 1., z0], [x0, y0, z0 + 1.]]),
+                plane([[x1, y0, z0], [x1, y0, z0 + 1.], [x1, y0 + 1., z0]]),
+                plane([[x0, y0, z0], [x0, y0, z0 + 1.], [x0 + 1., y0, z0]]),
+                plane([[x0, y1, z0], [x0 + 1., y1, z0], [x0, y1, z0 + 1.]]),
+                plane([[x0, y0, z0], [x0 + 1., y0, z0], [x0, y0 + 1., z0]]),
+                plane([[x0, y0, z1], [x0, y0 + 1., z1], [x0 + 1., y0, z1]])
+            ]
+        }
+    }
+
+    fn normal(polyhedron: &Polyhedron, face: &Face) -> DVec3 {
+        let [a, b, c] = [0, 1, 2].map(|i| DVec3::from(polyhedron.vertices[face.winding[i]]));
+        (b - a).cross(c - b).normalize()
+    }
+
+    #[test]
+    fn cube() {
+        let polyhedron = cuboid([-16., -16., 0.], [16., 16., 64.]).polyhedron();
+
+        assert_eq!(polyhedron.vertices.len(), 8);
+        assert_eq!(polyhedron.faces.len(), 6);
+        assert!(polyhedron.redundant.is_empty());
+        assert!(polyhedron.faces.iter().all(|face| face.winding.len() == 4));
+
+        for vertex in polyhedron.vertices.iter() {
+            assert!(vertex.x.abs() == 16. && vertex.y.abs() == 16.);
+            assert!(vertex.z == 0. || vertex.z == 64.)
+        }
+
+        // windings are counter-clockwise from the outside, so their normals point outwards
+        let expected = [
+            DVec3::new(-1., 0., 0.),
+            DVec3::new(1., 0., 0.),
+            DVec3::new(0., -1., 0.),
+            DVec3::new(0., 1., 0.),
+            DVec3::new(0., 0., -1.),
+            DVec3::new(0., 0., 1.)
+        ];
+        for (face, expected) in polyhedron.faces.iter().zip(expected.iter()) {
+            assert!((normal(&polyhedron, face) - *expected).length() < 1e-9)
+        }
+    }
+
+    #[test]
+    fn redundant() {
+        let mut brush = cuboid([0., 0., 0.], [32., 32., 32.]);
+        // outside of the box
+        brush.planes.push(plane([[64., 0., 0.], [64., 0., 1.], [64., 1., 0.]]));
+        // a duplicate of the first plane
+        brush.planes.push(brush.planes[0].clone());
+        // collinear points
+        brush.planes.push(plane([[0., 0., 0.], [1., 1., 1.], [2., 2., 2.]]));
+
+        let polyhedron = brush.polyhedron();
+
+        assert_eq!(polyhedron.faces.len(), 6);
+        assert_eq!(polyhedron.redundant, vec![6, 7, 8]);
+        assert_eq!(polyhedron.vertices.len(), 8)
+    }
+
+    #[test]
+    fn bevel() {
+        let mut brush = cuboid([0., 0., 0.], [64., 64., 64.]);
+        // cuts off the +x +y edge
+        brush.planes.push(plane([[64., 32., 0.], [64., 32., 1.], [32., 64., 0.]]));
+
+        let polyhedron = brush.polyhedron();
+
+        assert_eq!(polyhedron.faces.len(), 7);
+        assert_eq!(polyhedron.vertices.len(), 10);
+        assert_eq!(polyhedron.faces.last().unwrap().plane, 6);
+        assert_eq!(polyhedron.faces.last().unwrap().winding.len(), 4)
+    }
+
+    #[test]
+    fn large_coordinates() {
+        let polyhedron = cuboid([131072., -131072., 0.], [131072.5, -131071.5, 0.5]).polyhedron();
+
+        assert_eq!(polyhedron.faces.len(), 6);
+        assert!(polyhedron.vertices.iter().any(|vertex| vertex.x == 131072.5))
+    }
+
+    #[test]
+    fn empty() {
+        let mut brush = cuboid([0., 0., 0.], [32., 32., 32.]);
+        // facing the opposite way of the +x plane, but further out
+        brush.planes.push(plane([[40., 0., 0.], [40., 1., 0.], [40., 0., 1.]]));
+
+        assert!(brush.polyhedron().is_empty())
+    }
+
+    #[test]
+    fn parsed() {
+        let map = crate::parse::<Standard>(include_str!("../../examples/example.map")).unwrap();
+
+        for brush in map.entities.iter().flat_map(|entity| entity.brushes.iter()) {
+            let polyhedron = brush.polyhedron();
+            assert!(polyhedron.vertices.len() >= 4);
+            assert!(polyhedron.faces.len() >= 4);
+            assert!(polyhedron.vertices.iter().all(|vertex| vertex.x.abs() < 8192.))
+        }
+    }
+
+    #[test]
+    fn doom3() {
+        let brush = doom3::Brush {
+            planes: cuboid([0., 0., 0.], [32., 32., 32.])
+                .planes
+                .into_iter()
+                .map(|plane| {
+                    let plane = DPlane::from_points(plane.points).unwrap();
+                    doom3::Plane {
+                        equation: crate::geometry::PlaneEquation {
+                            normal: plane.normal.into(),
+                            distance: plane.distance as f32
+                        },
+                        texture: Default::default()
+                    }
+                })
+                .collect()
+        };
+
+        assert_eq!(brush.polyhedron().vertices.len(), 8)
+    }
+}
@@ -0,0 +1,336 @@
+use {
+    std::fmt::{self, Display, Formatter},
+    super::{
+        math::{DVec3, DPlane, snap},
+        texture::{Projection, base_axes, axis_index, rotated_axes, effective_scale}
+    },
+    crate::{
+        Map,
+        parse::formats::{
+            Standard,
+            Valve,
+            standard,
+            valve::{self, Axes, Axis, Scale},
+            shared::{Brush, Entity, Plane, Texture, Vector3}
+        }
+    }
+};
+
+/// The position of a face whose texture alignment couldn't be converted
+/// exactly, as reported by [Map::into_standard](Map::into_standard).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct InexactFace {
+    pub entity: usize,
+    pub brush: usize,
+    pub plane: usize
+}
+
+impl Display for InexactFace {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "entity {}, brush {}, plane {}", self.entity, self.brush, self.plane)
+    }
+}
+
+/// The largest difference in texels between two alignments of a face
+/// for them to be considered the same.
+const TEXEL_EPSILON: f64 = 1e-2;
+
+impl <S> Plane<standard::TextureAlignment, S> {
+    /// Converts the plane to the Valve format, with the texture axes
+    /// the standard alignment projects the texture along. This is
+    /// lossless, unless the plane's points are collinear or coincident,
+    /// in which case the axes of a floor are used.
+    pub fn into_valve(self) -> Plane<valve::TextureAlignment, S> {
+        let normal = DPlane::from_points(self.points)
+            .map_or(DVec3::default(), |plane| plane.normal);
+        let standard = self.texture.alignment;
+        let (u, v) = rotated_axes(normal, standard.rotation);
+
+        Plane {
+            points: self.points,
+            texture: Texture {
+                name: self.texture.name,
+                alignment: valve::TextureAlignment {
+                    axes: Axes {
+                        u: Axis { normal: u.into(), offset: standard.offset.x },
+                        v: Axis { normal: v.into(), offset: standard.offset.y }
+                    },
+                    rotation: standard.rotation,
+                    scale: Scale { u: standard.scale.x, v: standard.scale.y }
+                }
+            }
+        }
+    }
+}
+
+impl <S> Plane<valve::TextureAlignment, S> {
+    /// Converts the plane to the standard format, finding the standard alignment
+    /// that maps every point on the plane to the same texel. If there's none,
+    /// the closest one is returned as an error. That's the case when the axes
+    /// are skewed as seen along the world axis standard alignments project
+    /// along, or when the plane's points are collinear or coincident.
+    pub fn into_standard(self) -> Result<Plane<standard::TextureAlignment, S>, Plane<standard::TextureAlignment, S>> {
+        let valve = self.texture.alignment;
+        let (alignment, exact) = closest_standard(&valve, self.points)
+            .unwrap_or((
+                standard::TextureAlignment {
+                    offset: standard::Vector2 { x: valve.axes.u.offset, y: valve.axes.v.offset },
+                    rotation: valve.rotation,
+                    scale: standard::Vector2 { x: valve.scale.u, y: valve.scale.v }
+                },
+                false
+            ));
+        let plane = Plane {
+            points: self.points,
+            texture: Texture {
+                name: self.texture.name,
+                alignment
+            }
+        };
+
+        if exact {
+            Ok(plane)
+        } else {
+            Err(plane)
+        }
+    }
+}
+
+/// Computes the standard alignment closest to `valve` on the plane through `points`,
+/// and whether it maps the points to the same texels. Returns `None` if the points
+/// are degenerate or the u axis is parallel to the projection axis.
+pub(super) fn closest_standard(valve: &valve::TextureAlignment, points: [Vector3; 3]) -> Option<(standard::TextureAlignment, bool)> {
+    let plane = DPlane::from_points(points)?;
+    let (s, t) = base_axes(plane.normal);
+    let (sv, tv) = (axis_index(s), axis_index(t));
+    let projected = 3 - sv - tv;
+    let normal = plane.normal.to_array();
+
+    // points on the plane satisfy p[k] = (distance - n[sv] p[sv] - n[tv] p[tv]) / n[k],
+    // so the components of the axes along the projection axis k can be moved
+    // into the other two components and the offset
+    let flatten = |axis: &Axis, scale: f32| {
+        let axis_vector = (DVec3::from(axis.normal) / effective_scale(scale)).to_array();
+        let along = axis_vector[projected] / normal[projected];
+
+        (
+            axis_vector[sv] - along * normal[sv],
+            axis_vector[tv] - along * normal[tv],
+            axis.offset as f64 + along * plane.distance
+        )
+    };
+    let (ux, uy, s_offset) = flatten(&valve.axes.u, valve.scale.u);
+    let (vx, vy, t_offset) = flatten(&valve.axes.v, valve.scale.v);
+
+    // the rotated and scaled base axes are σ (cos, sin) / scale.x and τ (-sin, cos) / scale.y
+    // in those components, where σ and τ are the signs of the base axes
+    let (sigma, tau) = (s.to_array()[sv], t.to_array()[tv]);
+    let length = ux.hypot(uy);
+    if length == 0. {
+        return None
+    }
+
+    let rotation = (sigma * uy).atan2(sigma * ux);
+    let (sin, cos) = rotation.sin_cos();
+    let along_t = cos * vy - sin * vx;
+    let scale_t = if along_t == 0. { 1. } else { tau / along_t };
+
+    let rotation = snap(rotation.to_degrees().rem_euclid(360.)) % 360.;
+    let standard = standard::TextureAlignment {
+        offset: standard::Vector2 { x: snap(s_offset), y: snap(t_offset) },
+        rotation,
+        scale: standard::Vector2 { x: snap(1. / length), y: snap(scale_t) }
+    };
+
+    let normal = plane.normal.into();
+    let (converted, original) = (standard.projection(normal), valve.projection(normal));
+    let exact = points
+        .iter()
+        .all(|&point| {
+            let (a, b) = (converted.texel(point), original.texel(point));
+            (a.x as f64 - b.x as f64).abs() <= TEXEL_EPSILON
+                && (a.y as f64 - b.y as f64).abs() <= TEXEL_EPSILON
+        });
+
+    Some((standard, exact))
+}
+
+impl <S> Brush<standard::TextureAlignment, S> {
+    /// Converts the brush to the Valve format, like [Plane::into_valve](Plane::into_valve).
+    pub fn into_valve(self) -> Brush<valve::TextureAlignment, S> {
+        Brush {
+            planes: self.planes
+                .into_iter()
+                .map(Plane::into_valve)
+                .collect()
+        }
+    }
+}
+
+impl <S> Brush<valve::TextureAlignment, S> {
+    /// Converts the brush to the standard format, like [Plane::into_standard](Plane::into_standard),
+    /// returning the indices of the planes whose alignment couldn't be converted exactly.
+    pub fn into_standard(self) -> (Brush<standard::TextureAlignment, S>, Vec<usize>) {
+        let mut inexact = Vec::new();
+        let planes = self.planes
+            .into_iter()
+            .enumerate()
+            .map(|(index, plane)| plane.into_standard().unwrap_or_else(|closest| {
+                inexact.push(index);
+                closest
+            }))
+            .collect();
+
+        (Brush { planes }, inexact)
+    }
+}
+
+impl From<Map<Standard>> for Map<Valve> {
+    /// Converts the map to the Valve format, like [Plane::into_valve](Plane::into_valve).
+    fn from(map: Map<Standard>) -> Self {
+        Map {
+            header: map.header,
+            entities: map.entities
+                .into_iter()
+                .map(|entity| Entity {
+                    fields: entity.fields,
+                    brushes: entity.brushes
+                        .into_iter()
+                        .map(Brush::into_valve)
+                        .collect()
+                })
+                .collect()
+        }
+    }
+}
+
+impl Map<Valve> {
+    /// Converts the map to the standard format, like [Plane::into_standard](Plane::into_standard),
+    /// returning every face whose alignment couldn't be converted exactly, in order.
+    /// ```
+    /// use nomap::formats::{Standard, Valve};
+    ///
+    /// let map = nomap::parse::<Standard>(include_str!("../../examples/example.map")).unwrap();
+    /// let (converted, inexact) = nomap::Map::<Valve>::from(map.clone()).into_standard();
+    ///
+    /// assert!(inexact.is_empty());
+    /// assert_eq!(converted, map);
+    /// ```
+    pub fn into_standard(self) -> (Map<Standard>, Vec<InexactFace>) {
+        let mut faces = Vec::new();
+        let entities = self.entities
+            .into_iter()
+            .enumerate()
+            .map(|(entity, Entity { fields, brushes })| Entity {
+                fields,
+                brushes: brushes
+                    .into_iter()
+                    .enumerate()
+                    .map(|(brush, converted)| {
+                        let (converted, inexact) = converted.into_standard();
+                        faces.extend(
+                            inexact
+                                .into_iter()
+                                .map(|plane| InexactFace { entity, brush, plane })
+                        );
+                        converted
+                    })
+                    .collect()
+            })
+            .collect();
+
+        (Map { header: self.header, entities }, faces)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn vector(x: f32, y: f32, z: f32) -> Vector3 {
+        Vector3 { x, y, z }
+    }
+
+    fn floor(u: Vector3, v: Vector3, scale: [f32; 2]) -> Plane<valve::TextureAlignment> {
+        Plane {
+            points: [vector(0., 0., 16.), vector(0., 64., 16.), vector(64., 0., 16.)],
+            texture: Texture {
+                name: String::from("floor"),
+                alignment: valve::TextureAlignment {
+                    axes: Axes {
+                        u: Axis { normal: u, offset: 8. },
+                        v: Axis { normal: v, offset: -4. }
+                    },
+                    rotation: 0.,
+                    scale: Scale { u: scale[0], v: scale[1] }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn standard_to_valve() {
+        let plane = Plane {
+            points: [vector(0., 0., 0.), vector(0., 0., 64.), vector(0., 64., 0.)],
+            texture: Texture {
+                name: String::from("wall"),
+                alignment: standard::TextureAlignment {
+                    offset: standard::Vector2 { x: 16., y: 32. },
+                    rotation: 90.,
+                    scale: standard::Vector2 { x: 2., y: -1. }
+                }
+            }
+        };
+        let valve = plane.clone().into_valve().texture.alignment;
+
+        assert_eq!(valve.axes.u, Axis { normal: vector(0., 0., 1.), offset: 16. });
+        assert_eq!(valve.axes.v, Axis { normal: vector(0., 1., 0.), offset: 32. });
+        assert_eq!(valve.scale, Scale { u: 2., v: -1. });
+        assert_eq!(plane.clone().into_valve().into_standard(), Ok(plane))
+    }
+
+    #[test]
+    fn valve_to_standard() {
+        let rotated = floor(vector(0.6, 0.8, 0.), vector(0.8, -0.6, 0.), [0.5, 2.]);
+        let standard = rotated.into_standard().unwrap().texture.alignment;
+
+        assert_eq!(standard.offset, standard::Vector2 { x: 8., y: -4. });
+        assert!((standard.rotation - 53.130_1).abs() < 1e-3);
+        assert_eq!(standard.scale, standard::Vector2 { x: 0.5, y: 2. });
+
+        // the component along z is the same for all points on the floor
+        let tilted = floor(vector(1., 0., 1.), vector(0., -1., 0.), [1., 1.]);
+        let standard = tilted.into_standard().unwrap().texture.alignment;
+
+        assert_eq!(standard.offset, standard::Vector2 { x: 24., y: -4. });
+        assert_eq!(standard.rotation, 0.)
+    }
+
+    #[test]
+    fn inexact() {
+        let skewed = floor(vector(1., 0., 0.), vector(1., -1., 0.), [1., 1.]);
+        let closest = skewed.clone().into_standard().unwrap_err().texture;
+        assert_eq!(closest.name, "floor");
+        assert_eq!(closest.alignment.scale, standard::Vector2 { x: 1., y: 1. });
+
+        let mut map = Map::<Valve> {
+            header: Default::default(),
+            entities: vec![Entity {
+                fields: Default::default(),
+                brushes: vec![
+                    Brush { planes: vec![floor(vector(1., 0., 0.), vector(0., -1., 0.), [1., 1.])] },
+                    Brush { planes: vec![floor(vector(1., 0., 0.), vector(0., -1., 0.), [1., 1.]), skewed] }
+                ]
+            }]
+        };
+        map.entities.push(map.entities[0].clone());
+
+        let (converted, faces) = map.into_standard();
+        assert_eq!(converted.entities.len(), 2);
+        assert_eq!(faces, vec![
+            InexactFace { entity: 0, brush: 1, plane: 1 },
+            InexactFace { entity: 1, brush: 1, plane: 1 }
+        ]);
+        assert_eq!(faces[0].to_string(), "entity 0, brush 1, plane 1")
+    }
+}
@@ -0,0 +1,177 @@
+use {
+    std::ops::{Add, Sub, Mul, Div, Neg},
+    crate::parse::formats::shared::Vector3
+};
+
+/// A three-dimensional vector using `f64`s, which all
+/// geometric computations are carried out with internally.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub(crate) struct DVec3 {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64
+}
+
+impl DVec3 {
+    pub const fn new(x: f64, y: f64, z: f64) -> Self {
+        DVec3 { x, y, z }
+    }
+
+    pub fn dot(self, other: Self) -> f64 {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    pub fn cross(self, other: Self) -> Self {
+        DVec3 {
+            x: self.y * other.z - self.z * other.y,
+            y: self.z * other.x - self.x * other.z,
+            z: self.x * other.y - self.y * other.x
+        }
+    }
+
+    pub fn length(self) -> f64 {
+        self.dot(self).sqrt()
+    }
+
+    pub fn normalize(self) -> Self {
+        self / self.length()
+    }
+
+    pub fn to_array(self) -> [f64; 3] {
+        [self.x, self.y, self.z]
+    }
+}
+
+impl From<Vector3> for DVec3 {
+    fn from(vec: Vector3) -> Self {
+        DVec3 {
+            x: vec.x as f64,
+            y: vec.y as f64,
+            z: vec.z as f64
+        }
+    }
+}
+
+impl From<DVec3> for Vector3 {
+    fn from(vec: DVec3) -> Self {
+        Vector3 {
+            x: vec.x as f32,
+            y: vec.y as f32,
+            z: vec.z as f32
+        }
+    }
+}
+
+impl Add for DVec3 {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        DVec3::new(self.x + other.x, self.y + other.y, self.z + other.z)
+    }
+}
+
+impl Sub for DVec3 {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        DVec3::new(self.x - other.x, self.y - other.y, self.z - other.z)
+    }
+}
+
+impl Mul<f64> for DVec3 {
+    type Output = Self;
+
+    fn mul(self, scalar: f64) -> Self {
+        DVec3::new(self.x * scalar, self.y * scalar, self.z * scalar)
+    }
+}
+
+impl Div<f64> for DVec3 {
+    type Output = Self;
+
+    fn div(self, scalar: f64) -> Self {
+        DVec3::new(self.x / scalar, self.y / scalar, self.z / scalar)
+    }
+}
+
+impl Neg for DVec3 {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        DVec3::new(-self.x, -self.y, -self.z)
+    }
+}
+
+/// A plane using `f64`s with a normal of unit length,
+/// so that every point `p` on it satisfies `normal · p = distance`.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub(crate) struct DPlane {
+    pub normal: DVec3,
+    pub distance: f64
+}
+
+impl DPlane {
+    /// Computes the plane through three points like Quake does, with the normal pointing
+    /// away from the half-space when the points are clockwise as seen from the front.
+    /// Returns `None` if the points are collinear or coincident.
+    pub fn from_points(points: [Vector3; 3]) -> Option<Self> {
+        let [a, b, c] = points.map(DVec3::from);
+        let normal = (a - b).cross(c - b);
+        let length = normal.length();
+
+        // compared relative to the spanned area, so that tiny but valid planes are kept
+        if length <= f64::EPSILON * (a - b).length() * (c - b).length() {
+            return None
+        }
+
+        let normal = normal / length;
+        Some(DPlane {
+            normal,
+            distance: normal.dot(b)
+        })
+    }
+
+    /// Normalizes the equation of a plane. Returns `None`
+    /// if the normal has a length of zero.
+    pub fn from_equation(normal: DVec3, distance: f64) -> Option<Self> {
+        let length = normal.length();
+        if length == 0. || !length.is_finite() {
+            return None
+        }
+
+        Some(DPlane {
+            normal: normal / length,
+            distance: distance / length
+        })
+    }
+
+    /// Returns the signed distance of `point` from the plane,
+    /// which is positive in front of it.
+    pub fn distance_to(self, point: DVec3) -> f64 {
+        self.normal.dot(point) - self.distance
+    }
+}
+
+/// Returns the sine and cosine of an angle in degrees,
+/// which are exact for multiples of 90°.
+pub(crate) fn sin_cos_degrees(degrees: f64) -> (f64, f64) {
+    match degrees.rem_euclid(360.) {
+        0. => (0., 1.),
+        90. => (1., 0.),
+        180. => (0., -1.),
+        270. => (-1., 0.),
+        degrees => degrees.to_radians().sin_cos()
+    }
+}
+
+/// Rounds `value` to four decimal places if that
+/// only removes floating point noise.
+pub(crate) fn snap(value: f64) -> f32 {
+    let rounded = (value * 1e4).round() / 1e4;
+
+    if (value - rounded).abs() < 1e-6 {
+        rounded as f32
+    } else {
+        value as f32
+    }
+}
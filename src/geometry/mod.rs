@@ -0,0 +1,42 @@
+//! Geometric computations on the types produced by the parser.
+
+mod math;
+mod plane;
+mod patch;
+mod brush;
+mod validation;
+mod bounds;
+mod texture;
+mod convert;
+mod transform;
+
+pub use {
+    plane::{PlaneEquation, PlaneEpsilon},
+    brush::{Polyhedron, Face},
+    validation::BrushProblem,
+    bounds::{Aabb, Bounded},
+    texture::{TextureProjection, Projection},
+    convert::InexactFace,
+    transform::{Transform, TextureLock, Transformable}
+};
+
+use crate::parse::formats::{
+    shared::Vector3,
+    standard::Vector2
+};
+
+/// A vertex of a [Mesh](Mesh), consisting of its
+/// position and texture coordinates.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Vertex {
+    pub position: Vector3,
+    pub uv: Vector2
+}
+
+/// A triangle mesh, where each triangle is described
+/// by three indices into the list of vertices.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Mesh {
+    pub vertices: Vec<Vertex>,
+    pub triangles: Vec<[usize; 3]>
+}
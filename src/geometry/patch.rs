@@ -0,0 +1,196 @@
+use {
+    super::{Mesh, Vertex},
+    crate::parse::formats::{
+        shared::Vector3,
+        standard::Vector2,
+        quake3::{Patch, ControlPoint}
+    }
+};
+
+impl Patch {
+    /// Tessellates the patch into a triangle mesh with interpolated texture
+    /// coordinates. Every 3x3 block of control points forms a quadratic Bézier
+    /// patch, which is subdivided into `level` segments in both directions,
+    /// so a higher level results in a smoother mesh. A level of `0` is treated
+    /// like `1`. Control points that don't belong to a complete 3x3 block,
+    /// which can only occur in patches with an even width or height, are ignored.
+    /// If the columns differ in length, only as many rows as the shortest
+    /// column has are used.
+    pub fn tessellate(&self, level: usize) -> Mesh {
+        let level = level.max(1);
+        let height = self.control_points
+            .iter()
+            .map(Vec::len)
+            .min()
+            .unwrap_or(0);
+        let columns = self.width().saturating_sub(1) / 2;
+        let rows = height.saturating_sub(1) / 2;
+
+        if columns == 0 || rows == 0 {
+            return Mesh::default()
+        }
+
+        let grid_width = columns * level + 1;
+        let grid_height = rows * level + 1;
+
+        // finds the block a grid coordinate belongs to and the position within it
+        let locate = |coord: usize, blocks: usize| {
+            let block = (coord / level).min(blocks - 1);
+            (block * 2, (coord - block * level) as f32 / level as f32)
+        };
+
+        let mut vertices = Vec::with_capacity(grid_width * grid_height);
+        for x in 0..grid_width {
+            let (column, s) = locate(x, columns);
+            for y in 0..grid_height {
+                let (row, t) = locate(y, rows);
+                let along_height = |column: &Vec<ControlPoint>| bezier(
+                    &column[row],
+                    &column[row + 1],
+                    &column[row + 2],
+                    t
+                );
+                vertices.push(bezier(
+                    &along_height(&self.control_points[column]),
+                    &along_height(&self.control_points[column + 1]),
+                    &along_height(&self.control_points[column + 2]),
+                    s
+                ))
+            }
+        }
+
+        let index = |x, y| x * grid_height + y;
+        let mut triangles = Vec::with_capacity((grid_width - 1) * (grid_height - 1) * 2);
+        for x in 0..grid_width - 1 {
+            for y in 0..grid_height - 1 {
+                triangles.push([index(x, y), index(x + 1, y), index(x, y + 1)]);
+                triangles.push([index(x + 1, y), index(x + 1, y + 1), index(x, y + 1)]);
+            }
+        }
+
+        Mesh {
+            vertices: vertices
+                .into_iter()
+                .map(|point| Vertex {
+                    position: point.position,
+                    uv: point.uv
+                })
+                .collect(),
+            triangles
+        }
+    }
+}
+
+fn bezier(a: &ControlPoint, b: &ControlPoint, c: &ControlPoint, t: f32) -> ControlPoint {
+    let weights = [(1. - t) * (1. - t), 2. * t * (1. - t), t * t];
+    let blend = |get: fn(&ControlPoint) -> f32| {
+        weights[0] * get(a) + weights[1] * get(b) + weights[2] * get(c)
+    };
+
+    ControlPoint {
+        position: Vector3 {
+            x: blend(|p| p.position.x),
+            y: blend(|p| p.position.y),
+            z: blend(|p| p.position.z)
+        },
+        uv: Vector2 {
+            x: blend(|p| p.uv.x),
+            y: blend(|p| p.uv.y)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn patch() -> Patch {
+        let point = |x, y, z, u, v| ControlPoint {
+            position: Vector3 { x, y, z },
+            uv: Vector2 { x: u, y: v }
+        };
+
+        Patch {
+            texture: "base_wall/metal".into(),
+            control_points: vec![
+                vec![point(-64., -64., 0., 0., 0.), point(-64., 0., 32., 0., 0.5), point(-64., 64., 0., 0., 1.)],
+                vec![point(0., -64., 32., 0.5, 0.), point(0., 0., 64., 0.5, 0.5), point(0., 64., 32., 0.5, 1.)],
+                vec![point(64., -64., 0., 1., 0.), point(64., 0., 32., 1., 0.5), point(64., 64., 0., 1., 1.)]
+            ],
+            ..<_>::default()
+        }
+    }
+
+    #[test]
+    fn tessellate() {
+        let mesh = patch().tessellate(4);
+
+        assert_eq!(mesh.vertices.len(), 25);
+        assert_eq!(mesh.triangles.len(), 32);
+        assert_eq!(
+            mesh.vertices[0],
+            Vertex {
+                position: Vector3 { x: -64., y: -64., z: 0. },
+                uv: Vector2 { x: 0., y: 0. }
+            }
+        );
+        assert_eq!(
+            mesh.vertices[12],
+            Vertex {
+                position: Vector3 { x: 0., y: 0., z: 32. },
+                uv: Vector2 { x: 0.5, y: 0.5 }
+            }
+        );
+        assert_eq!(
+            mesh.vertices[24],
+            Vertex {
+                position: Vector3 { x: 64., y: 64., z: 0. },
+                uv: Vector2 { x: 1., y: 1. }
+            }
+        );
+    }
+
+    #[test]
+    fn tessellate_joined() {
+        let mut patch = patch();
+        let mut next = patch.control_points.clone();
+        for column in next.iter_mut() {
+            for point in column.iter_mut() {
+                point.position.x += 128.
+            }
+        }
+        patch.control_points.extend(next.into_iter().skip(1));
+
+        let mesh = patch.tessellate(4);
+
+        assert_eq!(mesh.vertices.len(), 9 * 5);
+        assert_eq!(mesh.triangles.len(), 8 * 4 * 2);
+        assert!(
+            mesh.triangles
+                .iter()
+                .flatten()
+                .all(|&index| index < mesh.vertices.len())
+        );
+    }
+
+    #[test]
+    fn tessellate_degenerate() {
+        let mut patch = patch();
+        patch.control_points.truncate(2);
+
+        assert_eq!(patch.tessellate(3), Mesh::default())
+    }
+
+    #[test]
+    fn tessellate_ragged() {
+        let mut short = patch();
+        short.control_points[1].truncate(2);
+        assert_eq!(short.tessellate(4), Mesh::default());
+
+        let mut long = patch();
+        for column in long.control_points.iter_mut().skip(1) {
+            column.extend(column.clone())
+        }
+        assert_eq!(long.tessellate(4), patch().tessellate(4))
+    }
+}
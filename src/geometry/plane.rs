@@ -0,0 +1,262 @@
+use {
+    super::math::{DVec3, DPlane},
+    crate::parse::formats::shared::{Plane, Texture, Vector3}
+};
+
+/// A plane described by its normal and its distance from the origin,
+/// so that every point `p` on it satisfies `normal · p = distance`.
+/// The normal points away from the half-space the plane describes.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PlaneEquation {
+    pub normal: Vector3,
+    pub distance: f32
+}
+
+impl PlaneEquation {
+    /// Computes the equation of the plane through three points, with the normal
+    /// pointing away from the half-space like Quake does, which is the case
+    /// when the points are clockwise as seen from the front. The normal has
+    /// a length of 1. Returns `None` if the points are collinear or coincident.
+    pub fn from_points(points: [Vector3; 3]) -> Option<Self> {
+        DPlane::from_points(points).map(PlaneEquation::from)
+    }
+
+    /// Returns the equation with its normal scaled to a length of 1.
+    /// Returns `None` if the normal has a length of zero.
+    pub fn normalized(&self) -> Option<Self> {
+        self.to_dplane().map(PlaneEquation::from)
+    }
+
+    /// Returns the equation of the same plane describing the opposite half-space.
+    pub fn flipped(&self) -> Self {
+        PlaneEquation {
+            normal: Vector3 {
+                x: -self.normal.x,
+                y: -self.normal.y,
+                z: -self.normal.z
+            },
+            distance: -self.distance
+        }
+    }
+
+    /// Returns the signed distance of `point` from the plane, which is positive
+    /// in front of it. Returns `None` if the normal has a length of zero.
+    pub fn distance_to(&self, point: Vector3) -> Option<f32> {
+        self.to_dplane().map(|plane| plane.distance_to(point.into()) as f32)
+    }
+
+    /// Returns `true` if both equations describe the same half-space within `epsilon`,
+    /// regardless of the lengths of their normals. Equations with a normal of length
+    /// zero are never equal.
+    pub fn approx_eq(&self, other: &Self, epsilon: PlaneEpsilon) -> bool {
+        match (self.to_dplane(), other.to_dplane()) {
+            (Some(a), Some(b)) =>
+                (a.normal - b.normal).to_array().iter().all(|d| d.abs() <= epsilon.normal as f64)
+                    && (a.distance - b.distance).abs() <= epsilon.distance as f64,
+            _ => false
+        }
+    }
+
+    /// Computes three points on the plane, ordered so that they describe
+    /// the same half-space as the equation when used in a [Plane](crate::formats::shared::Plane).
+    /// Two coordinates of every point lie on the integer grid, and so does the third
+    /// one where the plane allows it, which is always the case for axial planes at
+    /// integer distances. Returns `None` if the normal has a length of zero.
+    pub fn points(&self) -> Option<[Vector3; 3]> {
+        let plane = self.to_dplane()?;
+        let normal = plane.normal.to_array();
+        let origin = (plane.normal * plane.distance).to_array();
+
+        // the points are spread along the two axes least aligned with the normal
+        // and solved for the remaining one, which is the most stable choice
+        let k = (0..3)
+            .max_by(|a, b| normal[*a].abs().total_cmp(&normal[*b].abs()))
+            .unwrap_or(2);
+        let (i, j) = ((k + 1) % 3, (k + 2) % 3);
+
+        let point = |a: f64, b: f64| {
+            let c = (plane.distance - normal[i] * a - normal[j] * b) / normal[k];
+            let mut point = [0.; 3];
+            point[i] = a;
+            point[j] = b;
+            point[k] = if (c - c.round()).abs() < GRID_EPSILON { c.round() } else { c };
+            DVec3::new(point[0], point[1], point[2]).into()
+        };
+
+        let (a, b) = (origin[i].round(), origin[j].round());
+        let mut points = [
+            point(a + SPACING, b),
+            point(a, b),
+            point(a, b + SPACING)
+        ];
+
+        // the normal of the points as computed by Quake points along axis k,
+        // so their order is reversed if the plane's normal points the other way
+        if normal[k] < 0. {
+            points.swap(0, 2)
+        }
+
+        Some(points)
+    }
+
+    fn to_dplane(self) -> Option<DPlane> {
+        DPlane::from_equation(self.normal.into(), self.distance as f64)
+    }
+}
+
+impl From<DPlane> for PlaneEquation {
+    fn from(plane: DPlane) -> Self {
+        PlaneEquation {
+            normal: plane.normal.into(),
+            distance: plane.distance as f32
+        }
+    }
+}
+
+/// The tolerances used when [comparing](PlaneEquation::approx_eq) plane equations.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PlaneEpsilon {
+    /// The maximum difference of each component of the normals,
+    /// after scaling them to a length of 1.
+    pub normal: f32,
+    /// The maximum difference of the distances, after scaling the normals.
+    pub distance: f32
+}
+
+impl Default for PlaneEpsilon {
+    fn default() -> Self {
+        PlaneEpsilon {
+            normal: 1e-4,
+            distance: 1e-2
+        }
+    }
+}
+
+impl <TA, S> Plane<TA, S> {
+    /// Computes the equation of the plane through its three points.
+    /// See [PlaneEquation::from_points](PlaneEquation::from_points).
+    pub fn equation(&self) -> Option<PlaneEquation> {
+        PlaneEquation::from_points(self.points)
+    }
+
+    /// Creates a plane from an equation, using the [points](PlaneEquation::points)
+    /// it computes. Returns `None` if the normal has a length of zero.
+    pub fn from_equation(equation: &PlaneEquation, texture: Texture<TA, S>) -> Option<Self> {
+        equation
+            .points()
+            .map(|points| Plane { points, texture })
+    }
+}
+
+/// The distance between the points produced by [PlaneEquation::points](PlaneEquation::points).
+const SPACING: f64 = 64.;
+
+/// Coordinates closer than this to an integer are snapped to it.
+const GRID_EPSILON: f64 = 1e-4;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn normal_of([a, b, c]: [Vector3; 3]) -> DVec3 {
+        let [a, b, c] = [a, b, c].map(DVec3::from);
+        (a - b).cross(c - b).normalize()
+    }
+
+    #[test]
+    fn points() {
+        let equation = PlaneEquation {
+            normal: Vector3 { x: 0., y: 0., z: -1. },
+            distance: 48.
+        };
+        let points = equation.points().unwrap();
+
+        assert!(points.iter().all(|point| point.z == -48.));
+        assert_eq!(normal_of(points), DVec3::new(0., 0., -1.));
+    }
+
+    #[test]
+    fn points_oblique() {
+        let normal = DVec3::new(1., 2., -3.).normalize();
+        let equation = PlaneEquation {
+            normal: normal.into(),
+            distance: 100.
+        };
+        let points = equation.points().unwrap();
+
+        for point in points.iter() {
+            assert!((DVec3::from(*point).dot(normal) - 100.).abs() < 1e-3)
+        }
+        assert!((normal_of(points) - normal).length() < 1e-6);
+    }
+
+    #[test]
+    fn points_degenerate() {
+        assert_eq!(PlaneEquation::default().points(), None)
+    }
+
+    #[test]
+    fn points_grid() {
+        let diagonal = 0.5f32.sqrt();
+        let equation = PlaneEquation {
+            normal: Vector3 { x: diagonal, y: diagonal, z: 0. },
+            distance: 64. * diagonal
+        };
+        let points = equation.points().unwrap();
+
+        for point in points.iter() {
+            assert_eq!(point.x + point.y, 64.);
+            assert!([point.x, point.y, point.z].iter().all(|c| c.fract() == 0.))
+        }
+        assert!(PlaneEquation::from_points(points).unwrap().approx_eq(&equation, PlaneEpsilon::default()))
+    }
+
+    #[test]
+    fn from_points() {
+        let points = [
+            Vector3 { x: 0., y: 64., z: 64. },
+            Vector3 { x: 64., y: 0., z: 64. },
+            Vector3 { x: 0., y: 0., z: 64. }
+        ];
+        let equation = PlaneEquation::from_points(points).unwrap();
+
+        assert_eq!(equation, PlaneEquation { normal: Vector3 { x: 0., y: 0., z: 1. }, distance: 64. });
+        assert_eq!(equation.distance_to(Vector3 { x: 5., y: 5., z: 0. }), Some(-64.));
+        assert_eq!(PlaneEquation::from_points([points[0], points[1], points[0]]), None)
+    }
+
+    #[test]
+    fn roundtrip() {
+        let map = crate::parse::<crate::formats::Standard>(include_str!("../../examples/example.map")).unwrap();
+        let planes = map.entities
+            .iter()
+            .flat_map(|entity| entity.brushes.iter())
+            .flat_map(|brush| brush.planes.iter());
+
+        for plane in planes {
+            let equation = plane.equation().unwrap();
+            let rebuilt = Plane::from_equation(&equation, plane.texture.clone()).unwrap();
+
+            assert!(rebuilt.equation().unwrap().approx_eq(&equation, PlaneEpsilon::default()))
+        }
+    }
+
+    #[test]
+    fn approx_eq() {
+        let equation = PlaneEquation {
+            normal: Vector3 { x: 0., y: 2., z: 0. },
+            distance: 64.
+        };
+        let close = PlaneEquation {
+            normal: Vector3 { x: 0.00001, y: 1., z: 0. },
+            distance: 32.001
+        };
+        let strict = PlaneEpsilon { normal: 1e-6, distance: 1e-6 };
+
+        assert!(equation.approx_eq(&close, PlaneEpsilon::default()));
+        assert!(!equation.approx_eq(&close, strict));
+        assert!(!equation.approx_eq(&equation.flipped(), PlaneEpsilon::default()));
+        assert!(!PlaneEquation::default().approx_eq(&PlaneEquation::default(), PlaneEpsilon::default()))
+    }
+}
@@ -0,0 +1,312 @@
+use {
+    super::math::{DVec3, DPlane, sin_cos_degrees},
+    crate::parse::formats::{
+        standard,
+        valve,
+        quake2,
+        shared::{Plane, Vector3}
+    }
+};
+
+/// The projection of a texture onto a plane as a 2x4 matrix, whose rows map
+/// a point `p` to the texel coordinates `s = s[0..3] · p + s[3]` and
+/// `t = t[0..3] · p + t[3]`, like in the compiled BSP file.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TextureProjection {
+    pub s: [f32; 4],
+    pub t: [f32; 4]
+}
+
+impl TextureProjection {
+    /// Returns the texel coordinates of `point`.
+    pub fn texel(&self, point: Vector3) -> standard::Vector2 {
+        let point = DVec3::from(point);
+        let row = |row: &[f32; 4]| {
+            let [x, y, z, offset] = row.map(f64::from);
+            (point.dot(DVec3::new(x, y, z)) + offset) as f32
+        };
+
+        standard::Vector2 {
+            x: row(&self.s),
+            y: row(&self.t)
+        }
+    }
+
+    /// Returns the texture coordinates of `point` for a texture of the given
+    /// size in pixels, where a distance of 1 corresponds to the whole texture.
+    pub fn uv(&self, point: Vector3, width: u32, height: u32) -> standard::Vector2 {
+        let texel = self.texel(point);
+
+        standard::Vector2 {
+            x: texel.x / width as f32,
+            y: texel.y / height as f32
+        }
+    }
+
+    /// Returns the texel coordinates of each point of a winding.
+    pub fn texels<I: IntoIterator<Item = Vector3>>(&self, points: I) -> Vec<standard::Vector2> {
+        points
+            .into_iter()
+            .map(|point| self.texel(point))
+            .collect()
+    }
+
+    /// Returns the texture coordinates of each point of a winding,
+    /// like [uv](TextureProjection::uv).
+    pub fn uvs<I: IntoIterator<Item = Vector3>>(&self, points: I, width: u32, height: u32) -> Vec<standard::Vector2> {
+        points
+            .into_iter()
+            .map(|point| self.uv(point, width, height))
+            .collect()
+    }
+
+    fn from_axes(s: DVec3, t: DVec3, offset: standard::Vector2) -> Self {
+        TextureProjection {
+            s: [s.x as f32, s.y as f32, s.z as f32, offset.x],
+            t: [t.x as f32, t.y as f32, t.z as f32, offset.y]
+        }
+    }
+}
+
+/// Trait for texture alignments that can be turned into a [TextureProjection](TextureProjection).
+pub trait Projection {
+    /// Computes the projection of the texture onto a plane with the given
+    /// normal, which has to have a length of 1.
+    fn projection(&self, normal: Vector3) -> TextureProjection;
+}
+
+/// The normals and texture axes of the six planes Quake
+/// projects textures from: floor, ceiling, and the walls
+/// facing west, east, south and north.
+const BASE_AXES: [[[f64; 3]; 3]; 6] = [
+    [[0., 0., 1.], [1., 0., 0.], [0., -1., 0.]],
+    [[0., 0., -1.], [1., 0., 0.], [0., -1., 0.]],
+    [[1., 0., 0.], [0., 1., 0.], [0., 0., -1.]],
+    [[-1., 0., 0.], [0., 1., 0.], [0., 0., -1.]],
+    [[0., 1., 0.], [1., 0., 0.], [0., 0., -1.]],
+    [[0., -1., 0.], [1., 0., 0.], [0., 0., -1.]]
+];
+
+/// Returns the texture axes Quake uses for a plane with the given normal,
+/// which are those of the base plane closest to it. Ties go to the first.
+pub(crate) fn base_axes(normal: DVec3) -> (DVec3, DVec3) {
+    let vector = |[x, y, z]: [f64; 3]| DVec3::new(x, y, z);
+    let mut best = 0;
+    let mut best_dot = 0.;
+
+    for (index, [base, ..]) in BASE_AXES.iter().enumerate() {
+        let dot = normal.dot(vector(*base));
+        if dot > best_dot {
+            best = index;
+            best_dot = dot
+        }
+    }
+
+    let [_, s, t] = BASE_AXES[best];
+    (vector(s), vector(t))
+}
+
+/// Returns the index of the first non-zero component of a base axis,
+/// which is the only one.
+pub(crate) fn axis_index(axis: DVec3) -> usize {
+    match axis.to_array() {
+        [x, ..] if x != 0. => 0,
+        [_, y, _] if y != 0. => 1,
+        _ => 2
+    }
+}
+
+/// Returns the base axes for a plane with the given normal, rotated
+/// by `rotation` degrees around the axis the texture is projected along.
+pub(crate) fn rotated_axes(normal: DVec3, rotation: f32) -> (DVec3, DVec3) {
+    let (s, t) = base_axes(normal);
+
+    let (sin, cos) = sin_cos_degrees(rotation as f64);
+
+    // the components the axes lie along, which they're rotated in
+    let (sv, tv) = (axis_index(s), axis_index(t));
+    let rotate = |axis: DVec3| {
+        let mut axis = axis.to_array();
+        let (a, b) = (axis[sv], axis[tv]);
+        axis[sv] = cos * a - sin * b;
+        axis[tv] = sin * a + cos * b;
+        DVec3::new(axis[0], axis[1], axis[2])
+    };
+
+    (rotate(s), rotate(t))
+}
+
+/// Returns `scale`, or 1 if it's 0, which is how compilers treat it.
+pub(crate) fn effective_scale(scale: f32) -> f64 {
+    if scale == 0. { 1. } else { scale as f64 }
+}
+
+impl Projection for standard::TextureAlignment {
+    /// Projects the texture along the closest axis, rotates it around that
+    /// axis and scales it, exactly like Quake's compilers do. A scale of 0
+    /// is treated as 1, and angles that are multiples of 90° are exact.
+    fn projection(&self, normal: Vector3) -> TextureProjection {
+        let (s, t) = rotated_axes(normal.into(), self.rotation);
+
+        TextureProjection::from_axes(
+            s / effective_scale(self.scale.x),
+            t / effective_scale(self.scale.y),
+            self.offset.clone()
+        )
+    }
+}
+
+impl Projection for valve::TextureAlignment {
+    /// Uses the explicit axes, which are divided by the scale. The normal
+    /// is ignored, as is the rotation, which is already part of the axes.
+    fn projection(&self, _normal: Vector3) -> TextureProjection {
+        let (u, v) = (&self.axes.u, &self.axes.v);
+
+        TextureProjection::from_axes(
+            DVec3::from(u.normal) / effective_scale(self.scale.u),
+            DVec3::from(v.normal) / effective_scale(self.scale.v),
+            standard::Vector2 { x: u.offset, y: v.offset }
+        )
+    }
+}
+
+impl <TA: Projection> Projection for quake2::TextureAlignment<TA> {
+    fn projection(&self, normal: Vector3) -> TextureProjection {
+        self.base.projection(normal)
+    }
+}
+
+impl <TA: Projection, S> Plane<TA, S> {
+    /// Computes the projection of the plane's texture onto it. Returns
+    /// `None` if the plane's points are collinear or coincident.
+    pub fn projection(&self) -> Option<TextureProjection> {
+        DPlane::from_points(self.points)
+            .map(|plane| self.texture.alignment.projection(plane.normal.into()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        crate::parse::formats::{
+            shared::Texture,
+            standard::Vector2,
+            valve::{Axes, Axis, Scale}
+        }
+    };
+
+    fn vector(x: f32, y: f32, z: f32) -> Vector3 {
+        Vector3 { x, y, z }
+    }
+
+    fn standard(offset: [f32; 2], rotation: f32, scale: [f32; 2]) -> standard::TextureAlignment {
+        standard::TextureAlignment {
+            offset: Vector2 { x: offset[0], y: offset[1] },
+            rotation,
+            scale: Vector2 { x: scale[0], y: scale[1] }
+        }
+    }
+
+    fn assert_close(a: Vector2, b: Vector2) {
+        assert!((a.x - b.x).abs() < 1e-4 && (a.y - b.y).abs() < 1e-4, "{:?} != {:?}", a, b)
+    }
+
+    const UP: Vector3 = Vector3 { x: 0., y: 0., z: 1. };
+
+    #[test]
+    fn base_axes() {
+        let point = vector(16., 32., 8.);
+        let projection = standard([0., 0.], 0., [1., 1.]);
+
+        assert_eq!(projection.projection(UP).texel(point), Vector2 { x: 16., y: -32. });
+        assert_eq!(projection.projection(vector(-1., 0., 0.)).texel(point), Vector2 { x: 32., y: -8. });
+        assert_eq!(projection.projection(vector(0., -1., 0.)).texel(point), Vector2 { x: 16., y: -8. });
+
+        // a 45° slope between floor and east wall counts as floor
+        let diagonal = 0.5f32.sqrt();
+        assert_eq!(projection.projection(vector(diagonal, 0., diagonal)).s, [1., 0., 0., 0.])
+    }
+
+    #[test]
+    fn standard_alignment() {
+        let point = vector(16., 32., 0.);
+
+        assert_eq!(
+            standard([8., -4.], 0., [2., 0.5]).projection(UP).texel(point),
+            Vector2 { x: 16., y: -68. }
+        );
+        assert_eq!(
+            standard([0., 0.], 90., [1., 1.]).projection(UP),
+            TextureProjection { s: [0., 1., 0., 0.], t: [1., 0., 0., 0.] }
+        );
+        assert_close(
+            standard([0., 0.], 45., [1., 1.]).projection(UP).texel(point),
+            Vector2 { x: 48. * 0.5f32.sqrt(), y: -16. * 0.5f32.sqrt() }
+        );
+        assert_eq!(
+            standard([0., 0.], 0., [0., 0.]).projection(UP),
+            standard([0., 0.], 0., [1., 1.]).projection(UP)
+        )
+    }
+
+    #[test]
+    fn valve_alignment() {
+        let alignment = valve::TextureAlignment {
+            axes: Axes {
+                u: Axis { normal: vector(1., 0., 0.), offset: 8. },
+                v: Axis { normal: vector(0., -1., 0.), offset: -4. }
+            },
+            rotation: 0.,
+            scale: Scale { u: 2., v: 0.5 }
+        };
+        let point = vector(16., 32., 0.);
+
+        assert_eq!(
+            alignment.projection(UP).texel(point),
+            standard([8., -4.], 0., [2., 0.5]).projection(UP).texel(point)
+        );
+        assert_eq!(alignment.projection(UP).uv(point, 64, 16), Vector2 { x: 0.25, y: -4.25 })
+    }
+
+    #[test]
+    fn plane() {
+        let plane = Plane {
+            points: [vector(0., 0., 0.), vector(0., 64., 0.), vector(64., 0., 0.)],
+            texture: Texture {
+                name: String::from("floor"),
+                alignment: standard([0., 0.], 0., [1., 1.])
+            }
+        };
+
+        assert_eq!(plane.projection().unwrap().s, [1., 0., 0., 0.]);
+        let degenerate = Plane { points: [vector(0., 0., 0.); 3], ..plane };
+        assert_eq!(degenerate.projection(), None)
+    }
+
+    #[test]
+    fn winding() {
+        let brush = crate::geometry::brush::test::cuboid([0., 0., 0.], [64., 32., 16.]);
+        let polyhedron = brush.polyhedron();
+        let top = polyhedron.faces
+            .iter()
+            .find(|face| face.plane == 5)
+            .unwrap();
+        let points = top.winding
+            .iter()
+            .map(|&index| polyhedron.vertices[index]);
+
+        let mut uvs = standard([0., 0.], 0., [1., 1.])
+            .projection(UP)
+            .uvs(points, 64, 32);
+        uvs.sort_by(|a, b| (a.x, a.y).partial_cmp(&(b.x, b.y)).unwrap());
+
+        assert_eq!(uvs, vec![
+            Vector2 { x: 0., y: -1. },
+            Vector2 { x: 0., y: 0. },
+            Vector2 { x: 1., y: -1. },
+            Vector2 { x: 1., y: 0. }
+        ])
+    }
+}
@@ -0,0 +1,704 @@
+use {
+    std::borrow::Borrow,
+    super::{
+        math::{DVec3, DPlane, sin_cos_degrees, snap},
+        plane::PlaneEquation,
+        texture::{Projection, TextureProjection},
+        convert::closest_standard
+    },
+    crate::{
+        Map,
+        parse::formats::{
+            Format,
+            standard,
+            valve::{self, Axes, Axis, Scale},
+            quake2,
+            quake3,
+            doom3,
+            keys::Orientation,
+            shared::{Brush, Entity, Plane, Vector3}
+        }
+    }
+};
+
+/// An affine transformation of space, like a combination
+/// of translations, rotations, scalings and mirrorings.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Transform {
+    /// The rows of the 3x4 matrix, whose last column is the translation.
+    matrix: [[f64; 4]; 3]
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Transform::IDENTITY
+    }
+}
+
+impl Transform {
+    /// The transformation that leaves everything in place.
+    pub const IDENTITY: Self = Transform {
+        matrix: [
+            [1., 0., 0., 0.],
+            [0., 1., 0., 0.],
+            [0., 0., 1., 0.]
+        ]
+    };
+
+    fn from_linear(linear: [[f64; 3]; 3]) -> Self {
+        let row = |[x, y, z]: [f64; 3]| [x, y, z, 0.];
+
+        Transform {
+            matrix: linear.map(row)
+        }
+    }
+
+    /// Moves everything by `offset`.
+    pub fn translation(offset: Vector3) -> Self {
+        let mut transform = Transform::IDENTITY;
+        for (row, offset) in transform.matrix.iter_mut().zip(DVec3::from(offset).to_array()) {
+            row[3] = offset
+        }
+
+        transform
+    }
+
+    /// Rotates everything counter-clockwise by `angle` degrees around `axis`,
+    /// which goes through the origin. Rotations by multiples of 90° are exact.
+    /// Returns the identity if the axis has a length of zero.
+    pub fn rotation(axis: Vector3, angle: f32) -> Self {
+        let axis = DVec3::from(axis);
+        if axis.length() == 0. {
+            return Transform::IDENTITY
+        }
+
+        let [x, y, z] = axis.normalize().to_array();
+        let (sin, cos) = sin_cos_degrees(angle as f64);
+        let rest = 1. - cos;
+
+        Transform::from_linear([
+            [cos + x * x * rest, x * y * rest - z * sin, x * z * rest + y * sin],
+            [y * x * rest + z * sin, cos + y * y * rest, y * z * rest - x * sin],
+            [z * x * rest - y * sin, z * y * rest + x * sin, cos + z * z * rest]
+        ])
+    }
+
+    /// Scales everything along each axis by the respective factor, away from the origin.
+    pub fn scaling(factors: Vector3) -> Self {
+        let [x, y, z] = DVec3::from(factors).to_array();
+
+        Transform::from_linear([
+            [x, 0., 0.],
+            [0., y, 0.],
+            [0., 0., z]
+        ])
+    }
+
+    /// Mirrors everything on the plane through the origin with the given normal.
+    /// Returns the identity if the normal has a length of zero.
+    pub fn mirror(normal: Vector3) -> Self {
+        let normal = DVec3::from(normal);
+        if normal.length() == 0. {
+            return Transform::IDENTITY
+        }
+
+        let normal = normal.normalize().to_array();
+        let mut linear = [[0.; 3]; 3];
+        for (i, row) in linear.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                let identity = if i == j { 1. } else { 0. };
+                *value = identity - 2. * normal[i] * normal[j]
+            }
+        }
+
+        Transform::from_linear(linear)
+    }
+
+    /// Returns the transformation applying `self` first and then `next`.
+    pub fn then(self, next: Self) -> Self {
+        let mut matrix = [[0.; 4]; 3];
+
+        for (i, row) in matrix.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = (0..3)
+                    .map(|k| next.matrix[i][k] * self.matrix[k][j])
+                    .sum::<f64>();
+            }
+            row[3] += next.matrix[i][3]
+        }
+
+        Transform { matrix }
+    }
+
+    /// Returns the transformation that does the same around `center`
+    /// instead of the origin, like rotating around a point.
+    pub fn around(self, center: Vector3) -> Self {
+        let center = DVec3::from(center);
+
+        Transform::translation((-center).into())
+            .then(self)
+            .then(Transform::translation(center.into()))
+    }
+
+    /// Returns the transformation undoing this one, or `None` if it
+    /// doesn't have one because it flattens space, like a scaling by 0.
+    pub fn inverse(self) -> Option<Self> {
+        let determinant = self.determinant();
+        if determinant == 0. || !determinant.is_finite() {
+            return None
+        }
+
+        let m = &self.matrix;
+        let cofactor = |i: usize, j: usize| {
+            let (i1, i2) = ((i + 1) % 3, (i + 2) % 3);
+            let (j1, j2) = ((j + 1) % 3, (j + 2) % 3);
+            m[i1][j1] * m[i2][j2] - m[i1][j2] * m[i2][j1]
+        };
+
+        let mut inverse = [[0.; 3]; 3];
+        for (i, row) in inverse.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = cofactor(j, i) / determinant
+            }
+        }
+
+        let linear = Transform::from_linear(inverse);
+        let translation = linear.apply_vector(DVec3::new(m[0][3], m[1][3], m[2][3]));
+        Some(linear.then(Transform::translation((-translation).into())))
+    }
+
+    /// Returns the factor by which volumes are scaled,
+    /// which is negative if the transformation mirrors.
+    pub fn determinant(&self) -> f64 {
+        let m = &self.matrix;
+
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    }
+
+    /// Returns `true` if the transformation mirrors, turning
+    /// clockwise windings into counter-clockwise ones.
+    pub fn is_mirroring(&self) -> bool {
+        self.determinant() < 0.
+    }
+
+    /// Transforms a point.
+    pub fn apply(&self, point: Vector3) -> Vector3 {
+        self.apply_point(point.into()).into()
+    }
+
+    pub(crate) fn apply_point(&self, point: DVec3) -> DVec3 {
+        let [x, y, z] = self.matrix.map(|[a, b, c, d]| a * point.x + b * point.y + c * point.z + d);
+        DVec3::new(x, y, z)
+    }
+
+    /// Transforms a direction, which ignores the translation.
+    pub(crate) fn apply_vector(&self, vector: DVec3) -> DVec3 {
+        let [x, y, z] = self.matrix.map(|[a, b, c, _]| a * vector.x + b * vector.y + c * vector.z);
+        DVec3::new(x, y, z)
+    }
+
+    /// Returns the projection mapping every transformed point to the
+    /// texel `projection` mapped it to before, if there's an inverse.
+    fn locked_projection(&self, projection: &TextureProjection) -> Option<TextureProjection> {
+        let inverse = self.inverse()?;
+        let row = |[x, y, z, offset]: [f32; 4]| {
+            let [a, b, c] = [0, 1, 2].map(|column| (0..3)
+                .map(|k| [x, y, z][k] as f64 * inverse.matrix[k][column])
+                .sum::<f64>()
+            );
+            let translation = DVec3::new(x as f64, y as f64, z as f64)
+                .dot(inverse.apply_point(DVec3::default()));
+
+            [a as f32, b as f32, c as f32, snap(offset as f64 + translation)]
+        };
+
+        Some(TextureProjection {
+            s: row(projection.s),
+            t: row(projection.t)
+        })
+    }
+}
+
+/// Trait for texture alignments that can keep a texture in place on the
+/// world surface while the plane it's on is transformed, which is known
+/// as texture lock.
+pub trait TextureLock: Sized {
+    /// Returns the alignment of the texture of the plane through `before`
+    /// after it was moved to `after` by `transform`, such that every point
+    /// keeps its texel. Alignments that can't express that are approximated.
+    fn locked(&self, transform: &Transform, before: [Vector3; 3], after: [Vector3; 3]) -> Self;
+}
+
+impl TextureLock for standard::TextureAlignment {
+    /// Finds the standard alignment closest to the locked projection. That's exact
+    /// for translations, and for rotations around the axis the texture is projected
+    /// along. The alignment is left unchanged if either plane is degenerate.
+    fn locked(&self, transform: &Transform, before: [Vector3; 3], after: [Vector3; 3]) -> Self {
+        let locked = DPlane::from_points(before)
+            .and_then(|plane| transform.locked_projection(&self.projection(plane.normal.into())));
+        let projection = match locked {
+            Some(projection) => projection,
+            None => return self.clone()
+        };
+
+        let axis = |[x, y, z, offset]: [f32; 4]| Axis { normal: Vector3 { x, y, z }, offset };
+        let valve = valve::TextureAlignment {
+            axes: Axes {
+                u: axis(projection.s),
+                v: axis(projection.t)
+            },
+            rotation: 0.,
+            scale: Scale { u: 1., v: 1. }
+        };
+
+        closest_standard(&valve, after)
+            .map_or_else(|| self.clone(), |(alignment, _exact)| alignment)
+    }
+}
+
+impl TextureLock for valve::TextureAlignment {
+    /// Transforms the axes, which is always exact. Their scale keeps its
+    /// sign, and the rotation is left as it is, since it's only informative.
+    fn locked(&self, transform: &Transform, _before: [Vector3; 3], _after: [Vector3; 3]) -> Self {
+        let projection = match transform.locked_projection(&self.projection(Vector3::default())) {
+            Some(projection) => projection,
+            None => return *self
+        };
+
+        let axis = |[x, y, z, offset]: [f32; 4], scale: f32| {
+            let row = DVec3::new(x as f64, y as f64, z as f64);
+            let scale = if row.length() == 0. {
+                scale
+            } else {
+                snap(scale.signum() as f64 / row.length())
+            };
+
+            (Axis { normal: (row * scale as f64).into(), offset }, scale)
+        };
+        let (u, scale_u) = axis(projection.s, self.scale.u);
+        let (v, scale_v) = axis(projection.t, self.scale.v);
+
+        valve::TextureAlignment {
+            axes: Axes { u, v },
+            rotation: self.rotation,
+            scale: Scale { u: scale_u, v: scale_v }
+        }
+    }
+}
+
+impl <TA: TextureLock> TextureLock for quake2::TextureAlignment<TA> {
+    fn locked(&self, transform: &Transform, before: [Vector3; 3], after: [Vector3; 3]) -> Self {
+        quake2::TextureAlignment {
+            base: self.base.locked(transform, before, after),
+            attributes: self.attributes
+        }
+    }
+}
+
+/// Trait for parts of a map that can be moved around, which is
+/// implemented for brushes, entities and maps. With `texture_lock`,
+/// textures stay in place on the world surface, as far as the
+/// alignment can express it, instead of moving with the planes.
+/// The Quake 3 and Doom 3 formats don't support texture lock yet,
+/// so their textures always move with the planes and patches.
+pub trait Transformable {
+    /// Applies an arbitrary [Transform](Transform).
+    fn transform(&mut self, transform: &Transform, texture_lock: bool);
+
+    /// Moves by `offset`.
+    fn translate(&mut self, offset: Vector3, texture_lock: bool) {
+        self.transform(&Transform::translation(offset), texture_lock)
+    }
+
+    /// Rotates by `angle` degrees around `axis` through the origin,
+    /// like [Transform::rotation](Transform::rotation).
+    fn rotate(&mut self, axis: Vector3, angle: f32, texture_lock: bool) {
+        self.transform(&Transform::rotation(axis, angle), texture_lock)
+    }
+
+    /// Scales along each axis by the respective factor, away from the origin.
+    fn scale(&mut self, factors: Vector3, texture_lock: bool) {
+        self.transform(&Transform::scaling(factors), texture_lock)
+    }
+
+    /// Mirrors on the plane through the origin with the given normal.
+    fn mirror(&mut self, normal: Vector3, texture_lock: bool) {
+        self.transform(&Transform::mirror(normal), texture_lock)
+    }
+}
+
+impl <TA: TextureLock, S> Transformable for Plane<TA, S> {
+    /// Transforms the points of the plane, swapping two of them
+    /// when mirroring so that the plane keeps facing outwards.
+    fn transform(&mut self, transform: &Transform, texture_lock: bool) {
+        let before = self.points;
+        let after = transform_points(before, transform);
+        if texture_lock {
+            self.texture.alignment = self.texture.alignment.locked(transform, before, after)
+        }
+        self.points = after
+    }
+}
+
+impl <TA: TextureLock, S> Transformable for Brush<TA, S> {
+    fn transform(&mut self, transform: &Transform, texture_lock: bool) {
+        for plane in self.planes.iter_mut() {
+            plane.transform(transform, texture_lock)
+        }
+    }
+}
+
+impl Transformable for quake3::Brush {
+    /// Transforms the planes without texture lock.
+    fn transform(&mut self, transform: &Transform, _texture_lock: bool) {
+        for plane in self.0.planes.iter_mut() {
+            plane.points = transform_points(plane.points, transform)
+        }
+    }
+}
+
+impl Transformable for quake3::Patch {
+    /// Transforms the positions of the control points, keeping their
+    /// texture coordinates, so the texture always moves with the patch.
+    fn transform(&mut self, transform: &Transform, _texture_lock: bool) {
+        for point in self.control_points.iter_mut().flatten() {
+            point.position = transform.apply(point.position)
+        }
+    }
+}
+
+impl Transformable for quake3::Primitive {
+    fn transform(&mut self, transform: &Transform, texture_lock: bool) {
+        match self {
+            quake3::Primitive::Brush(brush) => brush.transform(transform, texture_lock),
+            quake3::Primitive::Patch(patch) => patch.transform(transform, texture_lock)
+        }
+    }
+}
+
+impl Transformable for doom3::Plane {
+    /// Transforms the plane equation without texture lock. Planes whose
+    /// normal has a length of zero are left unchanged.
+    fn transform(&mut self, transform: &Transform, _texture_lock: bool) {
+        let equation = self.equation
+            .points()
+            .and_then(|points| PlaneEquation::from_points(transform_points(points, transform)));
+        if let Some(equation) = equation {
+            self.equation = equation
+        }
+    }
+}
+
+impl Transformable for doom3::Brush {
+    fn transform(&mut self, transform: &Transform, texture_lock: bool) {
+        for plane in self.planes.iter_mut() {
+            plane.transform(transform, texture_lock)
+        }
+    }
+}
+
+impl Transformable for doom3::Patch {
+    fn transform(&mut self, transform: &Transform, texture_lock: bool) {
+        self.patch.transform(transform, texture_lock)
+    }
+}
+
+impl Transformable for doom3::Primitive {
+    fn transform(&mut self, transform: &Transform, texture_lock: bool) {
+        match self {
+            doom3::Primitive::Brush(brush) => brush.transform(transform, texture_lock),
+            doom3::Primitive::Patch(patch) => patch.transform(transform, texture_lock)
+        }
+    }
+}
+
+impl <B, S> Transformable for Entity<B, S>
+where
+    B: Transformable,
+    S: Borrow<str> + From<String>
+{
+    /// Transforms the brushes of the entity, as well as its `origin`
+    /// and the orientation stored in `angles`, `mangle` or `angle`,
+    /// if those keys are present and valid.
+    fn transform(&mut self, transform: &Transform, texture_lock: bool) {
+        for brush in self.brushes.iter_mut() {
+            brush.transform(transform, texture_lock)
+        }
+
+        if let Ok(Some(origin)) = self.origin() {
+            self.set_origin(transform.apply(origin))
+        }
+        if let Ok(Some(orientation)) = self.orientation() {
+            self.set_orientation(transform_orientation(orientation, transform))
+        }
+    }
+}
+
+impl <F> Transformable for Map<F>
+where
+    F: Format,
+    F::Entity: Transformable
+{
+    fn transform(&mut self, transform: &Transform, texture_lock: bool) {
+        for entity in self.entities.iter_mut() {
+            entity.transform(transform, texture_lock)
+        }
+    }
+}
+
+/// Transforms the points of a plane, swapping two of them
+/// when mirroring so that the plane keeps facing outwards.
+fn transform_points(points: [Vector3; 3], transform: &Transform) -> [Vector3; 3] {
+    let mut points = points.map(|point| transform.apply(point));
+    if transform.is_mirroring() {
+        points.swap(0, 2)
+    }
+
+    points
+}
+
+/// Rotates an orientation along with the directions it
+/// points in, with a positive pitch pointing downwards.
+fn transform_orientation(orientation: Orientation, transform: &Transform) -> Orientation {
+    let angles = [orientation.pitch, orientation.yaw, orientation.roll].map(|angle| sin_cos_degrees(angle as f64));
+    let [(pitch_sin, pitch_cos), (yaw_sin, yaw_cos), (roll_sin, roll_cos)] = angles;
+
+    // the forward and up vectors, like Quake's AngleVectors
+    let forward = DVec3::new(pitch_cos * yaw_cos, pitch_cos * yaw_sin, -pitch_sin);
+    let up = DVec3::new(
+        roll_cos * pitch_sin * yaw_cos + roll_sin * yaw_sin,
+        roll_cos * pitch_sin * yaw_sin - roll_sin * yaw_cos,
+        roll_cos * pitch_cos
+    );
+
+    let forward = transform.apply_vector(forward);
+    let up = transform.apply_vector(up);
+    if forward.length() == 0. {
+        return orientation
+    }
+
+    let forward = forward.normalize();
+    let pitch = (-forward.z).clamp(-1., 1.).asin();
+    let (pitch_sin, pitch_cos) = pitch.sin_cos();
+
+    let (yaw, roll) = if pitch_cos.abs() < 1e-9 {
+        // pointing straight up or down, so the up vector determines the yaw
+        ((up.y * pitch_sin).atan2(up.x * pitch_sin), 0.)
+    } else {
+        let yaw = forward.y.atan2(forward.x);
+        let (yaw_sin, yaw_cos) = yaw.sin_cos();
+        let unrolled_up = DVec3::new(pitch_sin * yaw_cos, pitch_sin * yaw_sin, pitch_cos);
+        let unrolled_right = DVec3::new(yaw_sin, -yaw_cos, 0.);
+
+        (yaw, up.dot(unrolled_right).atan2(up.dot(unrolled_up)))
+    };
+
+    // adding 0 turns -0 into 0, which would otherwise be written as "-0"
+    let degrees = |angle: f64| snap(angle.to_degrees()) + 0.;
+    Orientation {
+        pitch: degrees(pitch),
+        yaw: degrees(yaw).rem_euclid(360.),
+        roll: degrees(roll)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        crate::parse::formats::{
+            Standard,
+            shared::{Fields, Texture}
+        }
+    };
+
+    fn vector(x: f32, y: f32, z: f32) -> Vector3 {
+        Vector3 { x, y, z }
+    }
+
+    const Z: Vector3 = Vector3 { x: 0., y: 0., z: 1. };
+
+    fn floor(alignment: standard::TextureAlignment) -> Plane<standard::TextureAlignment> {
+        Plane {
+            points: [vector(0., 0., 16.), vector(0., 64., 16.), vector(64., 0., 16.)],
+            texture: Texture {
+                name: String::from("floor"),
+                alignment
+            }
+        }
+    }
+
+    fn texels<TA: Projection>(plane: &Plane<TA>, points: [Vector3; 3]) -> Vec<standard::Vector2> {
+        plane.projection().unwrap().texels(points)
+    }
+
+    fn assert_texels_eq(actual: Vec<standard::Vector2>, expected: Vec<standard::Vector2>, transform: &Transform) {
+        for (actual, expected) in actual.iter().zip(&expected) {
+            assert!(
+                (actual.x - expected.x).abs() < 1e-3 && (actual.y - expected.y).abs() < 1e-3,
+                "{:?} != {:?} for {:?}", actual, expected, transform
+            )
+        }
+    }
+
+    #[test]
+    fn transforms() {
+        let point = vector(1., 2., 3.);
+
+        assert_eq!(Transform::rotation(Z, 90.).apply(point), vector(-2., 1., 3.));
+        assert_eq!(Transform::mirror(vector(1., 0., 0.)).apply(point), vector(-1., 2., 3.));
+        assert_eq!(Transform::scaling(vector(2., 1., 0.5)).apply(point), vector(2., 2., 1.5));
+        assert_eq!(
+            Transform::rotation(Z, 180.).around(vector(8., 8., 0.)).apply(point),
+            vector(15., 14., 3.)
+        );
+
+        let transform = Transform::rotation(vector(1., 1., 0.), 30.)
+            .then(Transform::translation(vector(16., 0., -8.)))
+            .then(Transform::scaling(vector(2., 2., 1.)));
+        let back = transform.inverse().unwrap().apply(transform.apply(point));
+        assert!((DVec3::from(back) - DVec3::from(point)).length() < 1e-5);
+
+        assert!(Transform::mirror(Z).is_mirroring());
+        assert_eq!(Transform::scaling(vector(1., 0., 1.)).inverse(), None)
+    }
+
+    #[test]
+    fn brush() {
+        let mut brush = standard::Brush {
+            planes: crate::geometry::brush::test::cuboid([0., 0., 0.], [64., 32., 16.]).planes
+                .into_iter()
+                .map(|plane| Plane { points: plane.points, ..floor(Default::default()) })
+                .collect()
+        };
+        let mut mirrored = brush.clone();
+
+        brush.rotate(Z, 90., false);
+        brush.translate(vector(32., 0., 0.), false);
+        assert!(brush.is_valid());
+        assert_eq!(brush.polyhedron().vertices.len(), 8);
+        assert!(brush.polyhedron().vertices.iter().all(|vertex| (0. ..=32.).contains(&vertex.x) && (0. ..=64.).contains(&vertex.y)));
+
+        mirrored.mirror(vector(1., 0., 0.), false);
+        assert!(mirrored.is_valid())
+    }
+
+    #[test]
+    fn texture_lock() {
+        let alignment = standard::TextureAlignment {
+            offset: standard::Vector2 { x: 8., y: 4. },
+            rotation: 0.,
+            scale: standard::Vector2 { x: 1., y: 2. }
+        };
+        let original = floor(alignment.clone());
+        let points = original.points;
+
+        for transform in [
+            Transform::translation(vector(24., -8., 32.)),
+            Transform::rotation(Z, 90.),
+            Transform::rotation(Z, 30.).around(vector(16., 16., 0.)),
+            Transform::mirror(vector(0., 1., 0.))
+        ] {
+            let mut plane = original.clone();
+            plane.transform(&transform, true);
+            let moved = points.map(|point| transform.apply(point));
+            assert_texels_eq(texels(&plane, moved), texels(&original, points), &transform);
+
+            let mut valve = original.clone().into_valve();
+            valve.transform(&transform, true);
+            assert_texels_eq(texels(&valve, moved), texels(&original, points), &transform)
+        }
+
+        let mut unlocked = original.clone();
+        unlocked.translate(vector(24., -8., 0.), false);
+        assert_eq!(unlocked.texture.alignment, alignment)
+    }
+
+    #[test]
+    fn quake3_and_doom3() {
+        let plane = floor(standard::TextureAlignment::default());
+        let alignment = quake3::TextureAlignment::default();
+        let mut brush = quake3::Brush(Brush {
+            planes: vec![Plane {
+                points: plane.points,
+                texture: Texture { name: plane.texture.name, alignment }
+            }]
+        });
+        let mut patch = quake3::Patch {
+            control_points: vec![vec![quake3::ControlPoint {
+                position: vector(16., 0., 0.),
+                uv: standard::Vector2 { x: 1., y: 0. }
+            }]],
+            ..<_>::default()
+        };
+
+        brush.mirror(vector(1., 0., 0.), true);
+        assert_eq!(brush.0.planes[0].points, [vector(-64., 0., 16.), vector(0., 64., 16.), vector(0., 0., 16.)]);
+        assert_eq!(brush.0.planes[0].texture.alignment, alignment);
+
+        patch.rotate(Z, 90., true);
+        assert_eq!(patch.control_points[0][0].position, vector(0., 16., 0.));
+        assert_eq!(patch.control_points[0][0].uv, standard::Vector2 { x: 1., y: 0. });
+
+        let mut plane = doom3::Plane {
+            equation: PlaneEquation { normal: Z, distance: 16. },
+            texture: <_>::default()
+        };
+        plane.translate(vector(0., 0., 8.), false);
+        assert_eq!(plane.equation, PlaneEquation { normal: Z, distance: 24. });
+
+        plane.mirror(Z, false);
+        assert_eq!(plane.equation, PlaneEquation { normal: vector(0., 0., -1.), distance: 24. });
+
+        let mut degenerate = doom3::Plane::default();
+        degenerate.translate(vector(0., 0., 8.), false);
+        assert_eq!(degenerate, doom3::Plane::default())
+    }
+
+    #[test]
+    fn entity() {
+        let mut fields = Fields::<String>::new();
+        fields.insert("classname".into(), "info_player_start".into());
+        fields.insert("origin".into(), "16 0 24".into());
+        fields.insert("angle".into(), "90".into());
+        let mut entity = Entity::<standard::Brush> { fields, brushes: Vec::new() };
+
+        entity.rotate(Z, 90., false);
+        assert_eq!(entity.fields["origin"], "0 16 24");
+        assert_eq!(entity.fields["angle"], "180");
+
+        entity.mirror(vector(1., 0., 0.), false);
+        assert_eq!(entity.fields["origin"], "0 16 24");
+        assert_eq!(entity.fields["angle"], "0");
+
+        entity.rotate(vector(0., 1., 0.), -90., false);
+        assert_eq!(entity.orientation(), Ok(Some(Orientation::UP)));
+        assert_eq!(entity.fields["angle"], "-1");
+
+        entity.fields.insert("angle".into(), "bad".into());
+        entity.translate(vector(0., 0., 8.), false);
+        assert_eq!(entity.fields["angle"], "bad");
+        assert_eq!(entity.fields["origin"], "-24 16 8");
+
+        // a spotlight pointing east and 30° downwards, turned to point north
+        let mut fields = Fields::<String>::new();
+        fields.insert("classname".into(), "light".into());
+        fields.insert("mangle".into(), "0 -30 0".into());
+        let mut light = Entity::<standard::Brush> { fields, brushes: Vec::new() };
+
+        light.rotate(Z, 90., false);
+        assert_eq!(light.fields["mangle"], "90 -30 0")
+    }
+
+    #[test]
+    fn map() {
+        let mut map = crate::parse::<Standard>(include_str!("../../examples/example.map")).unwrap();
+        let original = map.clone();
+
+        map.transform(&Transform::rotation(Z, 90.).then(Transform::translation(vector(64., 0., 0.))), true);
+        map.transform(&Transform::translation(vector(-64., 0., 0.)).then(Transform::rotation(Z, -90.)), true);
+        assert_eq!(map, original)
+    }
+}
@@ -0,0 +1,241 @@
+use {
+    std::fmt::{self, Display, Formatter},
+    super::{
+        math::DPlane,
+        brush::is_same
+    },
+    crate::parse::formats::shared::Brush
+};
+
+/// A problem with the geometry of a brush that makes it unusable for compilers,
+/// as reported by [Brush::validate](Brush::validate). Plane indices start at 0.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum BrushProblem {
+    /// The points of the plane are collinear or coincident,
+    /// so they don't describe a plane.
+    DegeneratePlane { plane: usize },
+    /// The plane is the same as an earlier one.
+    DuplicatePlane { plane: usize, original: usize },
+    /// The plane is the same as an earlier one, but facing the opposite way,
+    /// so the brush has no thickness.
+    OppositePlane { plane: usize, original: usize },
+    /// The plane lies outside of the volume enclosed by the other planes,
+    /// so it doesn't contribute a face.
+    RedundantPlane { plane: usize },
+    /// The brush has fewer than four valid planes, which is
+    /// the minimum needed to enclose a volume.
+    TooFewPlanes { count: usize },
+    /// The half-spaces of the brush don't enclose a finite volume.
+    Unbounded,
+    /// The half-spaces of the brush don't intersect, or only in a flat shape.
+    Empty
+}
+
+impl BrushProblem {
+    /// Returns the index of the plane the problem concerns,
+    /// if it isn't one of the whole brush.
+    pub fn plane(self) -> Option<usize> {
+        match self {
+            BrushProblem::DegeneratePlane { plane }
+            | BrushProblem::DuplicatePlane { plane, .. }
+            | BrushProblem::OppositePlane { plane, .. }
+            | BrushProblem::RedundantPlane { plane } => Some(plane),
+            BrushProblem::TooFewPlanes { .. }
+            | BrushProblem::Unbounded
+            | BrushProblem::Empty => None
+        }
+    }
+}
+
+impl Display for BrushProblem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            BrushProblem::DegeneratePlane { plane } =>
+                write!(f, "plane {} has collinear or coincident points", plane),
+            BrushProblem::DuplicatePlane { plane, original } =>
+                write!(f, "plane {} is a duplicate of plane {}", plane, original),
+            BrushProblem::OppositePlane { plane, original } =>
+                write!(f, "plane {} is the opposite of plane {}", plane, original),
+            BrushProblem::RedundantPlane { plane } =>
+                write!(f, "plane {} doesn't contribute a face", plane),
+            BrushProblem::TooFewPlanes { count } =>
+                write!(f, "brush has only {} valid plane{}", count, if *count == 1 { "" } else { "s" }),
+            BrushProblem::Unbounded =>
+                write!(f, "brush doesn't enclose a finite volume"),
+            BrushProblem::Empty =>
+                write!(f, "brush doesn't enclose any volume")
+        }
+    }
+}
+
+impl std::error::Error for BrushProblem {}
+
+impl <TA, S> Brush<TA, S> {
+    /// Checks the brush for problems, returning all of them with the problems
+    /// of individual planes first, in the order of the planes. Degenerate and
+    /// duplicate planes are left out of the other checks. If there are too few
+    /// valid planes, whether they enclose a volume isn't checked, and planes
+    /// are only reported as redundant if the brush encloses a finite volume.
+    /// ```
+    /// use nomap::{formats::Standard, geometry::BrushProblem};
+    ///
+    /// let map = nomap::parse::<Standard>(r#"
+    /// {
+    /// "classname" "worldspawn"
+    /// {
+    /// ( 0 0 0 ) ( 0 1 0 ) ( 0 0 1 ) floor 0 0 0 1 1
+    /// ( 0 0 0 ) ( 1 1 1 ) ( 2 2 2 ) floor 0 0 0 1 1
+    /// }
+    /// }
+    /// "#).unwrap();
+    ///
+    /// assert_eq!(
+    ///     map.entities[0].brushes[0].validate(),
+    ///     vec![
+    ///         BrushProblem::DegeneratePlane { plane: 1 },
+    ///         BrushProblem::TooFewPlanes { count: 1 }
+    ///     ]
+    /// );
+    /// ```
+    pub fn validate(&self) -> Vec<BrushProblem> {
+        let mut problems = Vec::new();
+        let mut planes: Vec<Option<DPlane>> = Vec::with_capacity(self.planes.len());
+
+        for (index, plane) in self.planes.iter().enumerate() {
+            let equation = match DPlane::from_points(plane.points) {
+                Some(equation) => equation,
+                None => {
+                    problems.push(BrushProblem::DegeneratePlane { plane: index });
+                    planes.push(None);
+                    continue
+                }
+            };
+
+            let opposite = DPlane {
+                normal: -equation.normal,
+                distance: -equation.distance
+            };
+            let earlier = |matches: &dyn Fn(DPlane) -> bool| planes
+                .iter()
+                .position(|earlier| earlier.is_some_and(matches));
+
+            if let Some(original) = earlier(&|earlier| is_same(earlier, equation)) {
+                problems.push(BrushProblem::DuplicatePlane { plane: index, original });
+                planes.push(None);
+                continue
+            }
+            if let Some(original) = earlier(&|earlier| is_same(earlier, opposite)) {
+                problems.push(BrushProblem::OppositePlane { plane: index, original })
+            }
+            planes.push(Some(equation))
+        }
+
+        let count = planes.iter().flatten().count();
+        if count < 4 {
+            problems.push(BrushProblem::TooFewPlanes { count });
+            return problems
+        }
+
+        let polyhedron = super::Polyhedron::from_planes(&planes);
+
+        if polyhedron.is_empty() {
+            problems.push(BrushProblem::Empty)
+        } else if polyhedron.is_unbounded() {
+            problems.push(BrushProblem::Unbounded)
+        } else {
+            let redundant = polyhedron.redundant
+                .iter()
+                .filter(|index| planes[**index].is_some())
+                .map(|&plane| BrushProblem::RedundantPlane { plane });
+            problems.extend(redundant);
+            problems.sort_by_key(|problem| problem.plane().unwrap_or(usize::MAX))
+        }
+
+        problems
+    }
+
+    /// Returns `true` if [validate](Brush::validate) doesn't find any problems.
+    pub fn is_valid(&self) -> bool {
+        self.validate().is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        crate::{
+            geometry::brush::test::{cuboid, plane},
+            parse::formats::Standard
+        }
+    };
+
+    #[test]
+    fn valid() {
+        assert!(cuboid([0., 0., 0.], [64., 64., 64.]).is_valid());
+
+        let map = crate::parse::<Standard>(include_str!("../../examples/example.map")).unwrap();
+        for brush in map.entities.iter().flat_map(|entity| entity.brushes.iter()) {
+            assert_eq!(brush.validate(), vec![])
+        }
+    }
+
+    #[test]
+    fn planes() {
+        let mut brush = cuboid([0., 0., 0.], [32., 32., 32.]);
+        brush.planes.insert(1, plane([[0., 0., 0.], [0., 0., 0.], [0., 0., 1.]]));
+        brush.planes.push(brush.planes[0].clone());
+        brush.planes.push(plane([[64., 0., 0.], [64., 0., 1.], [64., 1., 0.]]));
+
+        assert_eq!(
+            brush.validate(),
+            vec![
+                BrushProblem::DegeneratePlane { plane: 1 },
+                BrushProblem::DuplicatePlane { plane: 7, original: 0 },
+                BrushProblem::RedundantPlane { plane: 8 }
+            ]
+        )
+    }
+
+    #[test]
+    fn opposite() {
+        let mut brush = cuboid([0., 0., 0.], [32., 32., 32.]);
+        // a zero thickness slab at x = 0
+        brush.planes[1] = plane([[0., 0., 0.], [0., 0., 1.], [0., 1., 0.]]);
+
+        assert_eq!(
+            brush.validate(),
+            vec![
+                BrushProblem::OppositePlane { plane: 1, original: 0 },
+                BrushProblem::Empty
+            ]
+        )
+    }
+
+    #[test]
+    fn volume() {
+        let mut brush = cuboid([0., 0., 0.], [32., 32., 32.]);
+        brush.planes.truncate(3);
+        assert_eq!(brush.validate(), vec![BrushProblem::TooFewPlanes { count: 3 }]);
+
+        let mut brush = cuboid([0., 0., 0.], [32., 32., 32.]);
+        brush.planes.remove(5);
+        assert_eq!(brush.validate(), vec![BrushProblem::Unbounded]);
+
+        let mut brush = cuboid([0., 0., 0.], [32., 32., 32.]);
+        brush.planes.push(plane([[40., 0., 0.], [40., 1., 0.], [40., 0., 1.]]));
+        assert_eq!(brush.validate(), vec![BrushProblem::Empty])
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(
+            BrushProblem::DuplicatePlane { plane: 4, original: 2 }.to_string(),
+            "plane 4 is a duplicate of plane 2"
+        );
+        assert_eq!(
+            BrushProblem::TooFewPlanes { count: 1 }.to_string(),
+            "brush has only 1 valid plane"
+        )
+    }
+}
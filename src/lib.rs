@@ -6,6 +6,9 @@
 //! `nomap` is whitespace agnostic and ignores comments.
 //! It also optionally provides `Display` implementations for all its types (through
 //! the "display" feature), so you can serialise a parsed map back into a string.
+//! For control over the style of the output, or to write it directly to a file,
+//! use a [MapWriter](write::MapWriter). For previews in 3D software, the geometry
+//! can be [exported](export) to other formats.
 //!
 //! ## Example
 //! ```
@@ -24,8 +27,26 @@
 //!     )
 //! }
 //! ```
+//!
+//! ## Serde
+//! With the "serde" feature, all map types implement `Serialize` and `Deserialize`,
+//! so maps can be exchanged as JSON, MessagePack and the like. The representation
+//! is derived from the types and is considered part of the public API:
+//! - structs are maps of their field names, like `{"x":0.0,"y":64.0,"z":0.0}`
+//!   for a [Vector3](formats::shared::Vector3) and `{"header":null,"entities":[...]}`
+//!   for a [Map](Map), whose header is `null` if the format doesn't have one
+//! - [Fields](formats::shared::Fields) are a list of `[key, value]` pairs in
+//!   their stored order, since keys may appear more than once
+//! - newtypes like [Version](formats::doom3::Version) and the Quake 2 flags
+//!   are their inner value
+//! - enums are tagged with the name of their variant, like `{"Brush":{...}}` for a
+//!   [Primitive](formats::quake3::Primitive), except [AnyMap](formats::AnyMap),
+//!   which is `{"format":"Valve","map":{...}}`
 
 pub mod parse;
+pub mod geometry;
+pub mod write;
+pub mod export;
 #[cfg(feature = "display")]
 pub mod display;
 
@@ -52,8 +73,39 @@ pub use parse::{
 pub fn parse<'i, F>(input: Input<'i>) -> Result<Map<F>, nom::Err<Error<'i>>>
 where
     F: formats::Format,
+    F::Header: Parse<'i, Error<'i>>,
     F::Entity: Parse<'i, Error<'i>>
 {
     all_consuming(Map::parse)(input)
         .map(|(_rest, map)| map)
 }
+
+/// Like [parse](function@parse), but decodes the strings of entity fields with the
+/// given [Escaping](formats::shared::Escaping) instead of keeping them as they're
+/// written, like [Escaping::Raw](formats::shared::Escaping::Raw) does.
+/// ```
+/// use nomap::formats::{Valve, shared::Escaping};
+///
+/// let input = r#"{ "classname" "worldspawn" "wad" "c:\half-life\valve\" }"#;
+/// let map = nomap::parse_with::<Valve>(input, Escaping::Verbatim).unwrap();
+/// assert_eq!(map.entities[0].fields["wad"], r"c:\half-life\valve\");
+///
+/// let input = r#"{ "classname" "worldspawn" "message" "say \"hi\"" }"#;
+/// let map = nomap::parse_with::<Valve>(input, Escaping::Backslash).unwrap();
+/// assert_eq!(map.entities[0].fields["message"], r#"say "hi""#);
+/// ```
+pub fn parse_with<'i, F>(input: Input<'i>, escaping: formats::shared::Escaping) -> Result<Map<F>, nom::Err<Error<'i>>>
+where
+    F: formats::Format,
+    F::Header: Parse<'i, Error<'i>>,
+    F::Entity: formats::ParseEscaped<'i, Error<'i>>
+{
+    all_consuming(Map::parser(escaping))(input)
+        .map(|(_rest, map)| map)
+}
+
+/// Convenience function to parse a map whose format isn't known in advance.
+/// See [AnyMap::parse](parse::formats::AnyMap::parse) for how the format is determined.
+pub fn parse_any(input: Input) -> Result<formats::AnyMap, formats::any::AnyError> {
+    formats::AnyMap::parse(input)
+}
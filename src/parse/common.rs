@@ -7,7 +7,9 @@ use {
             ParseResult,
             nom::{
                 error::ErrorKind,
-                combinator::iterator
+                sequence::pair,
+                character::{digit1, one_of},
+                combinator::{iterator, map_res, opt, recognize}
             }
         },
         nom::{
@@ -20,7 +22,109 @@ use {
 
 pub use nom_fields::fields;
 
-pub fn parse<'i, T, E>(input: Input<'i>) -> ParseResult<T, E>
+/// Defines a newtype around an integer that represents a set of bit flags,
+/// with an associated constant for every named flag.
+macro_rules! bitflags {
+    (
+        $(#[$meta:meta])*
+        pub struct $name:ident($int:ty) {
+            $(
+                $(#[$flag_meta:meta])*
+                const $flag:ident = $value:expr;
+            )*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+        #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(transparent))]
+        pub struct $name(pub $int);
+
+        impl $name {
+            $(
+                $(#[$flag_meta])*
+                pub const $flag: Self = $name($value);
+            )*
+
+            /// Returns a set without any flags.
+            pub const fn empty() -> Self {
+                $name(0)
+            }
+
+            /// Returns the underlying integer.
+            pub const fn bits(self) -> $int {
+                self.0
+            }
+
+            /// Returns `true` if no flags are set.
+            pub const fn is_empty(self) -> bool {
+                self.0 == 0
+            }
+
+            /// Returns `true` if all flags in `other` are set.
+            pub const fn contains(self, other: Self) -> bool {
+                self.0 & other.0 == other.0
+            }
+
+            /// Returns `true` if any flag in `other` is set.
+            pub const fn intersects(self, other: Self) -> bool {
+                self.0 & other.0 != 0
+            }
+
+            /// Sets all flags in `other`.
+            pub fn insert(&mut self, other: Self) {
+                self.0 |= other.0
+            }
+
+            /// Clears all flags in `other`.
+            pub fn remove(&mut self, other: Self) {
+                self.0 &= !other.0
+            }
+
+            /// Sets or clears all flags in `other`, depending on `value`.
+            pub fn set(&mut self, other: Self, value: bool) {
+                if value {
+                    self.insert(other)
+                } else {
+                    self.remove(other)
+                }
+            }
+        }
+
+        impl std::ops::BitOr for $name {
+            type Output = Self;
+
+            fn bitor(self, other: Self) -> Self {
+                $name(self.0 | other.0)
+            }
+        }
+
+        impl std::ops::BitOrAssign for $name {
+            fn bitor_assign(&mut self, other: Self) {
+                self.insert(other)
+            }
+        }
+
+        impl std::ops::BitAnd for $name {
+            type Output = Self;
+
+            fn bitand(self, other: Self) -> Self {
+                $name(self.0 & other.0)
+            }
+        }
+
+        impl std::ops::BitAndAssign for $name {
+            fn bitand_assign(&mut self, other: Self) {
+                self.0 &= other.0
+            }
+        }
+    };
+}
+
+pub(crate) use bitflags;
+
+use std::str::FromStr;
+
+pub fn parse<'i, T, E>(input: Input<'i>) -> ParseResult<'i, T, E>
 where
     E: ParseError<Input<'i>>,
     T: Parse<'i, E>
@@ -49,35 +153,60 @@ where
     }
 }
 
+pub fn integer<'i, T, E>(input: Input<'i>) -> ParseResult<'i, T, E>
+where
+    T: FromStr,
+    E: ParseError<Input<'i>>
+{
+    map_res(
+        recognize(pair(opt(one_of("+-")), digit1)),
+        str::parse
+    )(input)
+}
+
+/// Parses a quoted string in which `\"` doesn't end the string, and returns
+/// its contents as they are. Once the opening quote has been parsed, a missing
+/// closing quote is a [Failure](nom::Err::Failure), since nothing else can
+/// start with a quote.
 pub fn quoted_string<'i, E>(input: &'i str) -> IResult<&'i str, &'i str, E>
+where E: ParseError<&'i str> {
+    quoted(input, true)
+}
+
+/// Parses a quoted string that ends at the next quote, in which
+/// backslashes have no special meaning, like [quoted_string](quoted_string).
+pub fn verbatim_string<'i, E>(input: &'i str) -> IResult<&'i str, &'i str, E>
+where E: ParseError<&'i str> {
+    quoted(input, false)
+}
+
+fn quoted<'i, E>(input: &'i str, escapes: bool) -> IResult<&'i str, &'i str, E>
 where E: ParseError<&'i str> {
     let mut escaped = false;
     let mut iter = input.chars();
 
-    let error = |ctx| Err(nom::Err::Error(
-        E::add_context(input, ctx, E::from_error_kind(input, ErrorKind::Verify))
-    ));
+    let error = |ctx| E::add_context(input, ctx, E::from_error_kind(input, ErrorKind::Verify));
 
     if let Some('"') = iter.next() {
         let end = iter.take_while(|c| match c {
             '"' if !escaped => false,
-            '\\' => {
-                escaped = true;
+            '\\' if escapes => {
+                escaped = !escaped;
                 true
             },
             _ => {
                 escaped = false;
                 true
             }
-        }).count() + 1;
+        }).map(char::len_utf8).sum::<usize>() + 1;
 
         if input[end..].starts_with('"') {
             Ok((&input[end + 1..], &input[1..end]))
         } else {
-            error("no closing quote")
+            Err(nom::Err::Failure(error("no closing quote")))
         }
     } else {
-        error("no opening quote")
+        Err(nom::Err::Error(error("no opening quote")))
     }
 }
 
@@ -98,6 +227,14 @@ pub(super) mod test {
         )
     }
 
+    #[test]
+    fn quoted_string_backslash() {
+        assert_eq!(
+            super::quoted_string::<Error>(r#""trailing\\" "next""#),
+            Ok((r#" "next""#, r#"trailing\\"#))
+        )
+    }
+
     #[test]
     fn quoted_string_unopened() {
         assert!(super::quoted_string::<Error>(r#"test\"string""#).is_err())
@@ -105,6 +242,22 @@ pub(super) mod test {
 
     #[test]
     fn quoted_string_unclosed() {
-        assert!(super::quoted_string::<Error>(r#""test\"string"#).is_err())
+        assert_eq!(
+            super::quoted_string::<Error>(r#""test\"string"#),
+            Err(nom::Err::Failure(Error {
+                input: r#""test\"string"#,
+                kind: ErrorKind::Verify,
+                context: "no closing quote"
+            }))
+        )
+    }
+
+    #[test]
+    fn verbatim_string() {
+        assert_eq!(
+            super::verbatim_string::<Error>(r#""c:\wads\" "next""#),
+            Ok((r#" "next""#, r#"c:\wads\"#))
+        );
+        assert!(matches!(super::verbatim_string::<Error>(r#""open"#), Err(nom::Err::Failure(_))))
     }
 }
\ No newline at end of file
@@ -11,10 +11,8 @@ pub(crate) mod nom {
         error,
         multi,
         branch,
-        methods,
         sequence,
         combinator,
-        bits::complete as bits,
         bytes::complete as bytes,
         number::complete as number,
         character::complete as character,
@@ -70,5 +68,5 @@ where
     E: ParseError<Input<'i>>,
     Self: Sized
 {
-    fn parse(input: Input<'i>) -> ParseResult<Self, E>;
+    fn parse(input: Input<'i>) -> ParseResult<'i, Self, E>;
 }
\ No newline at end of file
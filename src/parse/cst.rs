@@ -0,0 +1,649 @@
+//! A lossless syntax tree of a map, which keeps comments, whitespace and the
+//! original spelling of every number. Writing a [Document](Document) back out
+//! reproduces its source byte for byte, except for the parts that were edited.
+//!
+//! Only entities and their fields are broken down. Brushes are kept as their
+//! source text and can be [parsed](Brush::parse) into a format's brush type
+//! on demand. The keys and values of fields are read and written with the
+//! [Escaping](Escaping) the document was [parsed with](Document::parse_with).
+//! ```
+//! use nomap::cst::Document;
+//!
+//! let input = include_str!("../../examples/example.map");
+//! let mut document = Document::parse(input).unwrap();
+//!
+//! document.entities_mut().next().unwrap().set("wad", "gfx.wad");
+//! assert_eq!(document.to_string(), input.replacen("../wads/Q.wad", "gfx.wad", 1));
+//! ```
+
+use {
+    std::{
+        borrow::Cow,
+        fmt::{self, Display, Formatter}
+    },
+    crate::parse::{
+        common::{quoted_string, verbatim_string},
+        formats::shared::{Escaping, comment},
+        core::{
+            Parse,
+            Input,
+            Error,
+            ParseResult,
+            nom::{
+                self,
+                branch::alt,
+                multi::{many0, many1},
+                error::{ErrorKind, ParseError},
+                bytes::take_till1,
+                sequence::{tuple, preceded, terminated},
+                character::{char, multispace1},
+                combinator::{map, opt, recognize, all_consuming}
+            }
+        }
+    }
+};
+
+/// A map file as a sequence of [Item](Item)s.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Document<'i> {
+    pub items: Vec<Item<'i>>
+}
+
+/// A top-level part of a [Document](Document).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Item<'i> {
+    /// Whitespace and comments.
+    Trivia(Cow<'i, str>),
+    /// A word outside of any entity, like the parts of a Doom 3 `Version` header.
+    Token(Cow<'i, str>),
+    Entity(Entity<'i>)
+}
+
+/// An entity as a sequence of [EntityItem](EntityItem)s between its braces.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Entity<'i> {
+    pub items: Vec<EntityItem<'i>>,
+    /// How the keys and values of the entity's fields are
+    /// decoded and encoded when they're accessed by key.
+    pub escaping: Escaping
+}
+
+/// A part of an [Entity](Entity).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EntityItem<'i> {
+    /// Whitespace and comments.
+    Trivia(Cow<'i, str>),
+    Field(Field<'i>),
+    Brush(Brush<'i>)
+}
+
+/// A key/value pair of an entity. The key and value are stored without
+/// their quotes, but with any escape sequences as they appear in the source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field<'i> {
+    pub key: Cow<'i, str>,
+    /// The whitespace and comments between the key and the value.
+    pub separator: Cow<'i, str>,
+    pub value: Cow<'i, str>
+}
+
+/// The source text of a brush or patch, including its braces.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Brush<'i> {
+    pub text: Cow<'i, str>
+}
+
+impl <'i> Document<'i> {
+    /// Parses a document from the whole input, keeping the strings of fields
+    /// as they're written like [Escaping::Raw](Escaping::Raw).
+    pub fn parse(input: Input<'i>) -> Result<Self, nom::Err<Error<'i>>> {
+        Document::parse_with(input, Escaping::Raw)
+    }
+
+    /// Parses a document from the whole input, whose entities read and write
+    /// the strings of their fields with the given [Escaping](Escaping).
+    pub fn parse_with(input: Input<'i>, escaping: Escaping) -> Result<Self, nom::Err<Error<'i>>> {
+        all_consuming(map(
+            many0(alt((
+                map(trivia, Item::Trivia),
+                map(entity(escaping), Item::Entity),
+                map(token, Item::Token)
+            ))),
+            |items| Document { items }
+        ))(input)
+        .map(|(_rest, document)| document)
+    }
+
+    /// Returns an iterator over the document's entities.
+    pub fn entities(&self) -> impl Iterator<Item = &Entity<'i>> {
+        self.items
+            .iter()
+            .filter_map(|item| match item {
+                Item::Entity(ent) => Some(ent),
+                _ => None
+            })
+    }
+
+    /// Returns a mutable iterator over the document's entities.
+    pub fn entities_mut(&mut self) -> impl Iterator<Item = &mut Entity<'i>> {
+        self.items
+            .iter_mut()
+            .filter_map(|item| match item {
+                Item::Entity(ent) => Some(ent),
+                _ => None
+            })
+    }
+
+    /// Appends an entity on a new line.
+    pub fn push_entity(&mut self, entity: Entity<'i>) {
+        if !self.items.is_empty() {
+            self.items.push(Item::Trivia("\n".into()))
+        }
+        self.items.push(Item::Entity(entity))
+    }
+
+    /// Removes the entity at `index` along with the whitespace preceding it.
+    pub fn remove_entity(&mut self, index: usize) -> Option<Entity<'i>> {
+        let position = nth_position(&self.items, index, |item| matches!(item, Item::Entity(_)))?;
+        let entity = match self.items.remove(position) {
+            Item::Entity(ent) => ent,
+            _ => unreachable!()
+        };
+        remove_whitespace_before(&mut self.items, position, |item| match item {
+            Item::Trivia(text) => Some(text),
+            _ => None
+        });
+        Some(entity)
+    }
+
+    /// Converts the document into one that doesn't borrow from the input.
+    pub fn into_owned(self) -> Document<'static> {
+        Document {
+            items: self.items
+                .into_iter()
+                .map(|item| match item {
+                    Item::Trivia(text) => Item::Trivia(owned(text)),
+                    Item::Token(text) => Item::Token(owned(text)),
+                    Item::Entity(ent) => Item::Entity(ent.into_owned())
+                })
+                .collect()
+        }
+    }
+}
+
+impl <'i> Entity<'i> {
+    /// Returns an iterator over the entity's fields.
+    pub fn fields(&self) -> impl Iterator<Item = &Field<'i>> {
+        self.items
+            .iter()
+            .filter_map(|item| match item {
+                EntityItem::Field(field) => Some(field),
+                _ => None
+            })
+    }
+
+    /// Returns an iterator over the entity's brushes.
+    pub fn brushes(&self) -> impl Iterator<Item = &Brush<'i>> {
+        self.items
+            .iter()
+            .filter_map(|item| match item {
+                EntityItem::Brush(brush) => Some(brush),
+                _ => None
+            })
+    }
+
+    /// Returns the value of the last field with the given key, with its
+    /// escape sequences [decoded](Escaping::decode) like by the parser.
+    /// The keys of the fields are decoded before comparing them.
+    pub fn get(&self, key: &str) -> Option<Cow<'_, str>> {
+        let escaping = self.escaping;
+        self.fields()
+            .filter(|field| field.has_key(key, escaping))
+            .last()
+            .map(|field| escaping.decode(&field.value))
+    }
+
+    /// Sets the value of the last field with the given key, leaving the rest of
+    /// the entity untouched. If there's no such field, it's added on a new line
+    /// after the last field. The key and value are [encoded](Escaping::encode)
+    /// with the entity's [Escaping](Escaping).
+    pub fn set(&mut self, key: impl Into<Cow<'i, str>>, value: impl Into<Cow<'i, str>>) {
+        let (key, escaping) = (key.into(), self.escaping);
+        let value = encode(escaping, value.into());
+        let existing = self.items
+            .iter_mut()
+            .rev()
+            .find_map(|item| match item {
+                EntityItem::Field(field) if field.has_key(&key, escaping) => Some(field),
+                _ => None
+            });
+
+        match existing {
+            Some(field) => field.value = value,
+            None => {
+                let key = encode(escaping, key);
+                let field = EntityItem::Field(Field {
+                    key,
+                    separator: " ".into(),
+                    value
+                });
+                let position = self.items
+                    .iter()
+                    .rposition(|item| matches!(item, EntityItem::Field(_)))
+                    .map_or(0, |index| index + 1);
+                self.items.insert(position, field);
+                self.items.insert(position, EntityItem::Trivia("\n".into()))
+            }
+        }
+    }
+
+    /// Removes all fields with the given key along with the whitespace
+    /// preceding them, returning the decoded value of the last one.
+    pub fn remove(&mut self, key: &str) -> Option<Cow<'i, str>> {
+        let (mut removed, escaping) = (None, self.escaping);
+
+        while let Some(position) = self.items
+            .iter()
+            .position(|item| matches!(item, EntityItem::Field(field) if field.has_key(key, escaping)))
+        {
+            if let EntityItem::Field(field) = self.items.remove(position) {
+                removed = Some(decode(escaping, field.value))
+            }
+            remove_whitespace_before(&mut self.items, position, trivia_text);
+        }
+
+        removed
+    }
+
+    /// Appends a brush on a new line after the last item.
+    pub fn push_brush(&mut self, brush: Brush<'i>) {
+        let position = self.items
+            .iter()
+            .rposition(|item| !matches!(item, EntityItem::Trivia(_)))
+            .map_or(0, |index| index + 1);
+        self.items.insert(position, EntityItem::Brush(brush));
+        self.items.insert(position, EntityItem::Trivia("\n".into()))
+    }
+
+    /// Removes the brush at `index` along with the whitespace preceding it.
+    pub fn remove_brush(&mut self, index: usize) -> Option<Brush<'i>> {
+        let position = nth_position(&self.items, index, |item| matches!(item, EntityItem::Brush(_)))?;
+        let brush = match self.items.remove(position) {
+            EntityItem::Brush(brush) => brush,
+            _ => unreachable!()
+        };
+        remove_whitespace_before(&mut self.items, position, trivia_text);
+        Some(brush)
+    }
+
+    /// Converts the entity into one that doesn't borrow from the input.
+    pub fn into_owned(self) -> Entity<'static> {
+        Entity {
+            items: self.items
+                .into_iter()
+                .map(|item| match item {
+                    EntityItem::Trivia(text) => EntityItem::Trivia(owned(text)),
+                    EntityItem::Field(field) => EntityItem::Field(Field {
+                        key: owned(field.key),
+                        separator: owned(field.separator),
+                        value: owned(field.value)
+                    }),
+                    EntityItem::Brush(brush) => EntityItem::Brush(Brush {
+                        text: owned(brush.text)
+                    })
+                })
+                .collect(),
+            escaping: self.escaping
+        }
+    }
+}
+
+impl Field<'_> {
+    fn has_key(&self, key: &str, escaping: Escaping) -> bool {
+        escaping.decode(&self.key) == key
+    }
+}
+
+impl <'i> Brush<'i> {
+    /// Creates a brush from its source text, which should include the braces.
+    pub fn new(text: impl Into<Cow<'i, str>>) -> Self {
+        Brush { text: text.into() }
+    }
+
+    /// Parses the brush's text as the brush type `B` of a format.
+    pub fn parse<'a, B>(&'a self) -> Result<B, nom::Err<Error<'a>>>
+    where B: Parse<'a, Error<'a>> {
+        all_consuming(B::parse)(&self.text)
+            .map(|(_rest, brush)| brush)
+    }
+}
+
+impl Display for Document<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for item in self.items.iter() {
+            match item {
+                Item::Trivia(text) | Item::Token(text) => f.write_str(text)?,
+                Item::Entity(ent) => write!(f, "{}", ent)?
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Display for Entity<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("{")?;
+        for item in self.items.iter() {
+            match item {
+                EntityItem::Trivia(text) => f.write_str(text)?,
+                EntityItem::Field(field) => write!(f, "{}", field)?,
+                EntityItem::Brush(brush) => f.write_str(&brush.text)?
+            }
+        }
+        f.write_str("}")
+    }
+}
+
+impl Display for Field<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, r#""{}"{}"{}""#, self.key, self.separator, self.value)
+    }
+}
+
+/// Encodes a key or value of a [Field](Field), keeping
+/// `text` as it is if there's nothing to escape.
+fn encode(escaping: Escaping, text: Cow<str>) -> Cow<str> {
+    match escaping.encode(&text) {
+        Cow::Borrowed(_) => text,
+        Cow::Owned(encoded) => Cow::Owned(encoded)
+    }
+}
+
+/// Decodes a key or value of a [Field](Field), keeping
+/// `text` as it is if there's nothing to unescape.
+fn decode(escaping: Escaping, text: Cow<str>) -> Cow<str> {
+    match escaping.decode(&text) {
+        Cow::Borrowed(_) => text,
+        Cow::Owned(decoded) => Cow::Owned(decoded)
+    }
+}
+
+fn owned(text: Cow<str>) -> Cow<'static, str> {
+    Cow::Owned(text.into_owned())
+}
+
+fn trivia_text<'a>(item: &'a EntityItem) -> Option<&'a Cow<'a, str>> {
+    match item {
+        EntityItem::Trivia(text) => Some(text),
+        _ => None
+    }
+}
+
+/// Returns the position of the `index`th item matching `predicate`.
+fn nth_position<T>(items: &[T], index: usize, predicate: impl Fn(&T) -> bool) -> Option<usize> {
+    items
+        .iter()
+        .enumerate()
+        .filter(|(_, item)| predicate(item))
+        .nth(index)
+        .map(|(position, _)| position)
+}
+
+/// Removes the trivia before `position` if it's only whitespace,
+/// so that removing an item doesn't leave an empty line behind.
+fn remove_whitespace_before<T>(
+    items: &mut Vec<T>,
+    position: usize,
+    text: impl Fn(&T) -> Option<&Cow<str>>
+) {
+    let whitespace = position
+        .checked_sub(1)
+        .and_then(|previous| text(&items[previous]))
+        .is_some_and(|text| text.trim().is_empty());
+
+    if whitespace {
+        items.remove(position - 1);
+    }
+}
+
+fn trivia<'i, E>(input: Input<'i>) -> ParseResult<'i, Cow<'i, str>, E>
+where E: ParseError<Input<'i>> + Clone {
+    map(
+        recognize(many1(alt((multispace1, comment)))),
+        Cow::Borrowed
+    )(input)
+}
+
+fn token<'i, E>(input: Input<'i>) -> ParseResult<'i, Cow<'i, str>, E>
+where E: ParseError<Input<'i>> + Clone {
+    map(
+        take_till1(|c: char| c.is_whitespace() || c == '{' || c == '}' || c == '"'),
+        Cow::Borrowed
+    )(input)
+}
+
+fn entity<'i, E>(escaping: Escaping) -> impl Fn(Input<'i>) -> ParseResult<'i, Entity<'i>, E>
+where E: ParseError<Input<'i>> + Clone {
+    map(
+        preceded(
+            char('{'),
+            terminated(
+                many0(alt((
+                    map(trivia, EntityItem::Trivia),
+                    map(field(escaping), EntityItem::Field),
+                    map(block, |text| EntityItem::Brush(Brush::new(text)))
+                ))),
+                char('}')
+            )
+        ),
+        move |items| Entity { items, escaping }
+    )
+}
+
+fn field<'i, E>(escaping: Escaping) -> impl Fn(Input<'i>) -> ParseResult<'i, Field<'i>, E>
+where E: ParseError<Input<'i>> + Clone {
+    // the strings are kept as they're written, but
+    // where they end depends on the escaping
+    let string = move |input| match escaping {
+        Escaping::Verbatim => verbatim_string(input),
+        Escaping::Raw | Escaping::Backslash => quoted_string(input)
+    };
+
+    map(
+        tuple((
+            string,
+            recognize(opt(trivia::<E>)),
+            string
+        )),
+        |(key, separator, value)| Field {
+            key: key.into(),
+            separator: separator.into(),
+            value: value.into()
+        }
+    )
+}
+
+/// Recognizes a block delimited by matching braces, skipping
+/// over braces in comments and quoted strings.
+fn block<'i, E>(input: Input<'i>) -> ParseResult<'i, Input<'i>, E>
+where E: ParseError<Input<'i>> {
+    if !input.starts_with('{') {
+        return Err(nom::Err::Error(E::from_error_kind(input, ErrorKind::Char)))
+    }
+
+    let mut depth = 0;
+    let mut chars = input.char_indices().peekable();
+
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Ok((&input[index + 1..], &input[..=index]))
+                }
+            },
+            '/' if matches!(chars.peek(), Some((_, '/'))) => {
+                while !matches!(chars.peek(), Some((_, '\n')) | None) {
+                    chars.next();
+                }
+            },
+            '"' => {
+                let mut escaped = false;
+                for (_, c) in chars.by_ref() {
+                    match c {
+                        '"' if !escaped => break,
+                        '\\' => escaped = !escaped,
+                        _ => escaped = false
+                    }
+                }
+            },
+            _ => ()
+        }
+    }
+
+    Err(nom::Err::Error(E::add_context(
+        input,
+        "no closing brace",
+        E::from_error_kind(input, ErrorKind::Char)
+    )))
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        crate::parse::formats::{standard, quake3}
+    };
+
+    const WEIRD: &str = r#"// Game: Quake
+Version 2
+// entity 0
+{ "classname" // comment between key and value
+"worldspawn"
+"wad"    "../wads/Q.wad" // trailing
+// brush 0
+{
+( 32 -112 -48 ) ( 32 -111 -48 ) ( 32 -112 -47 ) sfloor4_6 0 0 0 1 1
+( 352 -16 -32 ) ( 352 -16 -31.000 ) ( 352 -15 -32 ) sfloor4_6 -0 -0 -0 1 1 // } brace in comment
+}
+}
+
+// entity 1
+{"classname""light""message" "a \"quoted\" {brace}"}
+// no trailing newline"#;
+
+    #[test]
+    fn roundtrip() {
+        for input in [WEIRD, include_str!("../../examples/example.map")].iter() {
+            assert_eq!(Document::parse(input).unwrap().to_string(), *input)
+        }
+    }
+
+    #[test]
+    fn structure() {
+        let document = Document::parse(WEIRD).unwrap();
+        let entities = document.entities().collect::<Vec<_>>();
+
+        assert_eq!(entities.len(), 2);
+        assert_eq!(entities[0].get("wad").as_deref(), Some("../wads/Q.wad"));
+        assert_eq!(entities[1].get("message").as_deref(), Some(r#"a \"quoted\" {brace}"#));
+        assert_eq!(entities[0].brushes().count(), 1);
+        assert_eq!(
+            document.items[1..4],
+            [
+                Item::Token("Version".into()),
+                Item::Trivia(" ".into()),
+                Item::Token("2".into())
+            ]
+        )
+    }
+
+    #[test]
+    fn edit() {
+        let mut document = Document::parse_with(WEIRD, Escaping::Backslash).unwrap();
+        let worldspawn = document.entities_mut().next().unwrap();
+
+        worldspawn.set("wad", "gfx.wad");
+        worldspawn.set("sounds", "4");
+        worldspawn.remove("classname");
+        worldspawn.set("message", r#"say "hi" from c:\wads\"#);
+        assert_eq!(worldspawn.get("message").as_deref(), Some(r#"say "hi" from c:\wads\"#));
+        worldspawn.remove("message");
+
+        assert_eq!(
+            document.to_string(),
+            WEIRD
+                .replacen("../wads/Q.wad", "gfx.wad", 1)
+                .replacen(r#" "classname" // comment between key and value
+"worldspawn""#, "", 1)
+                .replacen(r#""gfx.wad""#, "\"gfx.wad\"\n\"sounds\" \"4\"", 1)
+        )
+    }
+
+    #[test]
+    fn escaping() {
+        let input = r#"{
+"classname" "worldspawn"
+"wad" "c:\half-life\valve\"
+}"#;
+        let mut document = Document::parse_with(input, Escaping::Verbatim).unwrap();
+        let worldspawn = document.entities_mut().next().unwrap();
+
+        assert_eq!(worldspawn.get("wad").as_deref(), Some(r"c:\half-life\valve\"));
+        worldspawn.set("wad", r"c:\half-life\valve\halflife.wad");
+        assert_eq!(document.to_string(), input.replacen(r"valve\", r"valve\halflife.wad", 1));
+
+        let mut document = Document::parse_with(r#"{ "classname" "worldspawn" }"#, Escaping::Backslash).unwrap();
+        let worldspawn = document.entities_mut().next().unwrap();
+        worldspawn.set(r#"odd "key" \"#, "1");
+        assert_eq!(worldspawn.get(r#"odd "key" \"#).as_deref(), Some("1"));
+        worldspawn.set(r#"odd "key" \"#, "2");
+        assert_eq!(worldspawn.fields().count(), 2);
+        assert_eq!(worldspawn.remove(r#"odd "key" \"#).as_deref(), Some("2"));
+        assert_eq!(worldspawn.get(r#"odd "key" \"#), None)
+    }
+
+    #[test]
+    fn edit_structure() {
+        let mut document = Document::parse(WEIRD).unwrap();
+
+        let light = document.remove_entity(1).unwrap();
+        assert_eq!(light.get("classname").as_deref(), Some("light"));
+
+        let worldspawn = document.entities_mut().next().unwrap();
+        let brush = worldspawn.remove_brush(0).unwrap();
+        worldspawn.push_brush(brush.clone());
+        worldspawn.push_brush(brush);
+        assert_eq!(worldspawn.brushes().count(), 2);
+
+        let output = document.to_string();
+        let reparsed = Document::parse(&output).unwrap();
+        assert_eq!(reparsed.entities().count(), 1);
+        assert_eq!(reparsed.entities().next().unwrap().brushes().count(), 2);
+        assert_eq!(reparsed.clone().into_owned(), reparsed)
+    }
+
+    #[test]
+    fn brush() {
+        let document = Document::parse(WEIRD).unwrap();
+        let brush = document
+            .entities()
+            .next()
+            .unwrap()
+            .brushes()
+            .next()
+            .unwrap()
+            .parse::<standard::Brush>()
+            .unwrap();
+
+        assert_eq!(brush.planes.len(), 2);
+        assert_eq!(brush.planes[1].points[1].z, -31.);
+        assert!(Brush::new("{ brushDef { } }").parse::<quake3::Brush>().is_ok())
+    }
+
+    #[test]
+    fn unclosed() {
+        assert!(Document::parse(r#"{ "classname" "worldspawn" { ( 0 0 0 )"#).is_err())
+    }
+}
@@ -0,0 +1,408 @@
+//! Resolution of [Error](Error)s to positions in the parsed source,
+//! for reporting them in a human-readable way.
+
+use {
+    std::fmt::{self, Display, Formatter},
+    crate::parse::core::{Input, Error}
+};
+
+/// The position of an [Error](Error) in the source it was produced from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location<'i> {
+    /// The line of the error, starting at 1.
+    pub line: usize,
+    /// The column of the error in characters, starting at 1.
+    pub column: usize,
+    /// The offset of the error in bytes.
+    pub offset: usize,
+    /// The part of the map the error occurred in.
+    pub path: Path<'i>
+}
+
+/// The structural position of an [Error](Error) inside a map. The indices are
+/// those of the innermost entity, brush and plane open at the error's position,
+/// starting at 0. Patches are counted as brushes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Path<'i> {
+    pub entity: Option<usize>,
+    pub brush: Option<usize>,
+    pub plane: Option<usize>,
+    /// The key of the entity field the error occurred in.
+    pub field: Option<&'i str>
+}
+
+impl <'i> Error<'i> {
+    /// Resolves the error to its position in `source`, which has to be
+    /// the input the error was produced from. Returns `None` otherwise.
+    pub fn locate(&self, source: Input<'i>) -> Option<Location<'i>> {
+        let start = source.as_ptr() as usize;
+        let offset = (self.input.as_ptr() as usize).checked_sub(start)?;
+
+        if offset > source.len() || !source.is_char_boundary(offset) {
+            return None
+        }
+
+        let before = &source[..offset];
+        let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+
+        Some(Location {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+            offset,
+            path: Path::scan(source, offset)
+        })
+    }
+
+    /// Returns a report of the error that renders the offending line
+    /// of `source` with a caret, or `None` if the error can't be
+    /// [located](Error::locate) in `source`.
+    pub fn report<'e>(&'e self, source: Input<'i>) -> Option<Report<'e, 'i>> {
+        self.locate(source)
+            .map(|location| Report {
+                error: self,
+                source,
+                location
+            })
+    }
+}
+
+impl Display for Error<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.context.is_empty() {
+            write!(f, "unexpected input ({})", self.kind.description())?
+        } else {
+            write!(f, "{}", self.context)?
+        }
+
+        match self.input.lines().next() {
+            Some(line) if !line.trim().is_empty() => {
+                let excerpt = line.trim_end();
+                match excerpt.char_indices().nth(32) {
+                    Some((end, _)) => write!(f, " at `{}...`", &excerpt[..end]),
+                    None => write!(f, " at `{}`", excerpt)
+                }
+            },
+            _ => write!(f, " at end of input")
+        }
+    }
+}
+
+impl std::error::Error for Error<'_> {}
+
+/// A located [Error](Error) that renders itself along with the
+/// line it occurred on, like this:
+/// ```plain
+/// error: no closing quote at `"unclosed`
+///  --> 3:11 (entity 0, field "message")
+///   |
+/// 3 | "message" "unclosed
+///   |           ^
+/// ```
+#[derive(Debug, Clone)]
+pub struct Report<'e, 'i> {
+    pub error: &'e Error<'i>,
+    pub source: Input<'i>,
+    pub location: Location<'i>
+}
+
+impl Display for Report<'_, '_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let Location { line, column, offset, path } = &self.location;
+        let gutter = line.to_string().len();
+
+        writeln!(f, "error: {}", self.error)?;
+        write!(f, "{:gutter$}--> {}:{}", "", line, column, gutter = gutter)?;
+        if *path != Path::default() {
+            write!(f, " ({})", path)?
+        }
+        writeln!(f)?;
+
+        let line_start = self.source[..*offset].rfind('\n').map_or(0, |index| index + 1);
+        let text = self.source[line_start..]
+            .lines()
+            .next()
+            .unwrap_or("");
+        // keep tabs so that the caret lines up with the source
+        let indent = self.source[line_start..*offset]
+            .chars()
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect::<String>();
+
+        writeln!(f, "{:gutter$} |", "", gutter = gutter)?;
+        writeln!(f, "{} | {}", line, text)?;
+        write!(f, "{:gutter$} | {}^", "", indent, gutter = gutter)
+    }
+}
+
+impl Display for Path<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let parts = [
+            self.entity.map(|index| format!("entity {}", index)),
+            self.brush.map(|index| format!("brush {}", index)),
+            self.plane.map(|index| format!("plane {}", index)),
+            self.field.map(|key| format!("field \"{}\"", key))
+        ];
+
+        let mut first = true;
+        for part in parts.iter().flatten() {
+            if !first {
+                write!(f, ", ")?
+            }
+            write!(f, "{}", part)?;
+            first = false
+        }
+        Ok(())
+    }
+}
+
+impl <'i> Path<'i> {
+    /// Determines the path at `offset` by scanning the braces, parentheses
+    /// and strings that precede it, skipping comments. A block or string
+    /// starting right at `offset` is counted as well, since that's where
+    /// errors about invalid blocks point to.
+    fn scan(source: Input<'i>, offset: usize) -> Self {
+        let mut path = Path::default();
+        let mut entities = 0;
+        let mut brushes = 0;
+        let mut planes = 0;
+        let mut depth = 0;
+        let mut parens = 0;
+        let mut patch = false;
+        // the last significant character, to tell plane points apart
+        let mut previous = ' ';
+        // the key of the field whose value hasn't been read yet
+        let mut key = None;
+
+        let mut chars = source.char_indices().peekable();
+
+        while let Some((index, c)) = chars.next() {
+            let at_error = index == offset;
+            if index > offset || at_error && !matches!(c, '{' | '(' | '"') {
+                break
+            }
+
+            match c {
+                '/' if matches!(chars.peek(), Some((_, '/'))) => {
+                    while !matches!(chars.peek(), Some((_, '\n')) | None) {
+                        chars.next();
+                    }
+                    continue
+                },
+                '"' => {
+                    let end = string_end(source, index);
+                    if depth == 1 {
+                        match key {
+                            Some(_) if !at_error => key = None,
+                            Some(_) => (),
+                            None => key = Some(&source[index + 1..end.min(source.len())])
+                        }
+                    }
+                    if end >= offset {
+                        break
+                    }
+                    while matches!(chars.peek(), Some(&(next, _)) if next <= end) {
+                        chars.next();
+                    }
+                },
+                '{' => {
+                    match depth {
+                        0 => {
+                            path.entity = Some(entities);
+                            entities += 1;
+                            brushes = 0
+                        },
+                        1 => {
+                            path.brush = Some(brushes);
+                            brushes += 1;
+                            planes = 0;
+                            key = None
+                        },
+                        _ => ()
+                    }
+                    depth += 1
+                },
+                '}' => {
+                    depth -= 1;
+                    match depth {
+                        0 => {
+                            path = Path::default();
+                            key = None
+                        },
+                        1 => {
+                            path.brush = None;
+                            path.plane = None;
+                            patch = false
+                        },
+                        _ => ()
+                    }
+                },
+                '(' => {
+                    if depth >= 2 && parens == 0 && previous != ')' && !patch {
+                        path.plane = Some(planes);
+                        planes += 1
+                    }
+                    parens += 1
+                },
+                ')' => parens -= 1,
+                'p' if depth >= 2 && source[index..].starts_with("patchDef") => patch = true,
+                _ => ()
+            }
+
+            if at_error {
+                break
+            }
+            if !c.is_whitespace() {
+                previous = c
+            }
+        }
+
+        if depth == 1 {
+            path.field = key
+        }
+        path
+    }
+}
+
+/// Returns the index of the closing quote of the string starting at `start`,
+/// or the length of `source` if it's unclosed.
+fn string_end(source: Input, start: usize) -> usize {
+    let mut escaped = false;
+
+    source[start + 1..]
+        .char_indices()
+        .find(|&(_, c)| match c {
+            '"' if !escaped => true,
+            '\\' => {
+                escaped = !escaped;
+                false
+            },
+            _ => {
+                escaped = false;
+                false
+            }
+        })
+        .map_or(source.len(), |(index, _)| start + 1 + index)
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        crate::parse::formats::{Standard, Quake3}
+    };
+
+    fn error(input: &str) -> Error<'_> {
+        match crate::parse::<Standard>(input) {
+            Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) => e,
+            other => panic!("expected an error, got {:?}", other)
+        }
+    }
+
+    const UNCLOSED: &str = r#"{
+"classname" "worldspawn"
+"message" "unclosed
+}"#;
+
+    const PLANE: &str = r#"{
+"classname" "worldspawn"
+{
+( 0 0 0 ) ( 0 64 0 ) ( 64 0 0 ) floor 0 0 0 1 1
+( 0 0 64 ) ( 64 0 64 ) ( 0 64 64 ) ceiling 0 0 zero 1 1
+}
+}"#;
+
+    #[test]
+    fn locate_field() {
+        assert_eq!(
+            error(UNCLOSED).locate(UNCLOSED),
+            Some(Location {
+                line: 3,
+                column: 11,
+                offset: 37,
+                path: Path {
+                    entity: Some(0),
+                    field: Some("message"),
+                    ..Path::default()
+                }
+            })
+        )
+    }
+
+    #[test]
+    fn locate_plane() {
+        let location = error(PLANE).locate(PLANE).unwrap();
+
+        assert_eq!((location.line, location.column), (5, 1));
+        assert_eq!(
+            location.path,
+            Path {
+                entity: Some(0),
+                brush: Some(0),
+                plane: Some(1),
+                field: None
+            }
+        )
+    }
+
+    #[test]
+    fn locate_brush_def() {
+        let input = r#"{
+"classname" "worldspawn"
+{
+brushDef
+{
+( 0 0 0 ) ( 0 64 0 ) ( 64 0 0 ) ( ( 0.03125 0 0 ) ( 0 0.03125 0 ) ) floor 0 0 0
+( 0 0 64 ) ( 64 0 64 ) ( 0 64 64 ) ( ( 0.03125 0 0 ) ( 0 0.03125 0 ) ) ceiling 0 0 0
+}
+}
+{
+patchDef2
+{
+nope
+}
+}
+}"#;
+        let path = Path::scan(input, input.find("nope").unwrap());
+
+        assert_eq!(
+            path,
+            Path {
+                entity: Some(0),
+                brush: Some(1),
+                plane: None,
+                field: None
+            }
+        );
+        assert!(crate::parse::<Quake3>(input).is_err())
+    }
+
+    #[test]
+    fn locate_foreign() {
+        assert_eq!(error(UNCLOSED).locate("another string"), None)
+    }
+
+    #[test]
+    fn report() {
+        let error = error(UNCLOSED);
+
+        assert_eq!(
+            error.report(UNCLOSED).unwrap().to_string(),
+            r#"error: no closing quote at `"unclosed`
+ --> 3:11 (entity 0, field "message")
+  |
+3 | "message" "unclosed
+  |           ^"#
+        )
+    }
+
+    #[test]
+    fn display() {
+        let error = Error {
+            input: "",
+            kind: nom::error::ErrorKind::Verify,
+            context: "no closing quote"
+        };
+
+        assert_eq!(error.to_string(), "no closing quote at end of input")
+    }
+}
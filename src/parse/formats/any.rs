@@ -0,0 +1,815 @@
+use {
+    std::{
+        slice,
+        fmt::{self, Display, Formatter}
+    },
+    crate::parse::{
+        common::{parse, integer},
+        formats::{
+            Map,
+            Format,
+            NoHeader,
+            doom3,
+            quake2,
+            quake3,
+            valve,
+            standard,
+            shared::{self, Escaping, Fields, Vector3, separator},
+        },
+        core::{
+            Parse,
+            Input,
+            Error,
+            ParseResult,
+            nom::{
+                self,
+                branch::alt,
+                error::ParseError,
+                sequence::{pair, preceded},
+                combinator::{map, opt, peek}
+            }
+        }
+    }
+};
+
+/// One of the formats supported by this crate, for when the
+/// format of a map is only known at runtime.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AnyFormat {
+    Standard,
+    Valve,
+    Quake2,
+    Quake2Valve,
+    Quake3,
+    Doom3
+}
+
+impl AnyFormat {
+    /// Returns a human-readable name of the format.
+    pub fn name(self) -> &'static str {
+        match self {
+            AnyFormat::Standard => "Standard",
+            AnyFormat::Valve => "Valve",
+            AnyFormat::Quake2 => "Quake 2",
+            AnyFormat::Quake2Valve => "Quake 2 (Valve)",
+            AnyFormat::Quake3 => "Quake 3",
+            AnyFormat::Doom3 => "Doom 3"
+        }
+    }
+
+    /// Guesses the format of a map without parsing all of it. A `Version` header
+    /// indicates the Doom 3 format, while `brushDef` or `patchDef2` blocks indicate
+    /// the Quake 3 format. Otherwise, the format is determined by the first plane
+    /// of the map, falling back to the format declared in an editor's header
+    /// comment (like TrenchBroom's `// Format: Valve`) or to the Valve format if
+    /// the worldspawn entity's `mapversion` is `220`. Returns `None` if
+    /// none of these are present. Quake 2 maps whose first plane leaves out
+    /// the surface attributes are detected as standard or Valve maps, which
+    /// [AnyMap::parse](AnyMap::parse) corrects by looking at all planes.
+    pub fn detect(input: Input) -> Option<AnyFormat> {
+        let (content, _) = opt(separator::<Error>)(input)
+            .unwrap_or((input, None));
+
+        if content.starts_with("Version") {
+            return Some(AnyFormat::Doom3)
+        }
+
+        if let Some(brush) = open_brace(input, 2) {
+            let brush = skip_separator(brush);
+            if brush.starts_with("brushDef3") || brush.starts_with("patchDef3") {
+                return Some(AnyFormat::Doom3)
+            }
+            if brush.starts_with("brushDef") || brush.starts_with("patchDef2") {
+                return Some(AnyFormat::Quake3)
+            }
+            if let Ok((_, plane)) = <shared::Plane<AnyAlignment> as Parse<Error>>::parse(brush) {
+                return Some(plane.texture.alignment.format())
+            }
+        }
+
+        declared_format(input)
+            .or_else(|| match open_brace(input, 1) {
+                Some(entity) if mapversion(&<Fields as Parse<Error>>::parse(skip_separator(entity)).ok()?.1) =>
+                    Some(AnyFormat::Valve),
+                _ => None
+            })
+    }
+
+    fn is_valve(self) -> bool {
+        matches!(self, AnyFormat::Valve | AnyFormat::Quake2Valve)
+    }
+}
+
+/// A map in any of the [formats](AnyFormat) supported by this crate.
+/// The entities can be accessed through the variants, or through the
+/// [AnyEntity](AnyEntity) type, which works the same for every format.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "format", content = "map"))]
+pub enum AnyMap {
+    Standard(Map<super::Standard>),
+    Valve(Map<super::Valve>),
+    Quake2(Map<super::Quake2>),
+    Quake2Valve(Map<super::Quake2Valve>),
+    Quake3(Map<super::Quake3>),
+    Doom3(Map<super::Doom3>)
+}
+
+impl AnyMap {
+    /// Parses a map of unknown format. The format is [detected](AnyFormat::detect)
+    /// first, then it is verified that every plane of the map is written in it and
+    /// that it matches the format declared by an editor's header comment or the
+    /// `mapversion` key. Maps without any brushes are parsed with the standard format
+    /// unless another one is declared.
+    pub fn parse(input: Input) -> Result<Self, AnyError> {
+        AnyMap::parse_with(input, Escaping::Raw)
+    }
+
+    /// Like [parse](AnyMap::parse), but decodes the strings of entity
+    /// fields with the given [Escaping](Escaping).
+    pub fn parse_with(input: Input, escaping: Escaping) -> Result<Self, AnyError> {
+        let format = AnyFormat::detect(input)
+            .unwrap_or(AnyFormat::Standard);
+        let parse_error = |error| AnyError::Parse { format, error };
+
+        match format {
+            AnyFormat::Quake3 => crate::parse_with(input, escaping)
+                .map(AnyMap::Quake3)
+                .map_err(parse_error),
+            AnyFormat::Doom3 => crate::parse_with(input, escaping)
+                .map(AnyMap::Doom3)
+                .map_err(parse_error),
+            _ => {
+                let probe = crate::parse_with::<Probe>(input, escaping)
+                    .map_err(parse_error)?;
+                let found = probed_format(&probe)
+                    .unwrap_or(format);
+
+                let declared = declared_format(input);
+                match declared {
+                    Some(declared) if declared != found => return Err(AnyError::Mismatch { declared, found }),
+                    _ => ()
+                }
+                let valve_declared = probe.entities
+                    .first()
+                    .is_some_and(|worldspawn| mapversion(&worldspawn.fields));
+                if declared.is_none() && valve_declared && !found.is_valve() {
+                    return Err(AnyError::Mismatch { declared: AnyFormat::Valve, found })
+                }
+
+                Ok(match found {
+                    AnyFormat::Valve => AnyMap::Valve(narrow(probe, found, AnyAlignment::valve)?),
+                    AnyFormat::Quake2 => AnyMap::Quake2(narrow(probe, found, AnyAlignment::quake2)?),
+                    AnyFormat::Quake2Valve => AnyMap::Quake2Valve(narrow(probe, found, AnyAlignment::quake2_valve)?),
+                    _ => AnyMap::Standard(narrow(probe, found, AnyAlignment::standard)?)
+                })
+            }
+        }
+    }
+
+    /// Returns the format of the map.
+    pub fn format(&self) -> AnyFormat {
+        match self {
+            AnyMap::Standard(_) => AnyFormat::Standard,
+            AnyMap::Valve(_) => AnyFormat::Valve,
+            AnyMap::Quake2(_) => AnyFormat::Quake2,
+            AnyMap::Quake2Valve(_) => AnyFormat::Quake2Valve,
+            AnyMap::Quake3(_) => AnyFormat::Quake3,
+            AnyMap::Doom3(_) => AnyFormat::Doom3
+        }
+    }
+
+    /// Returns the number of entities in the map.
+    pub fn len(&self) -> usize {
+        match self {
+            AnyMap::Standard(map) => map.entities.len(),
+            AnyMap::Valve(map) => map.entities.len(),
+            AnyMap::Quake2(map) => map.entities.len(),
+            AnyMap::Quake2Valve(map) => map.entities.len(),
+            AnyMap::Quake3(map) => map.entities.len(),
+            AnyMap::Doom3(map) => map.entities.len()
+        }
+    }
+
+    /// Returns `true` if the map has no entities.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the entity at `index`, if there is one.
+    pub fn entity(&self, index: usize) -> Option<AnyEntity<'_>> {
+        Some(match self {
+            AnyMap::Standard(map) => AnyEntity::Standard(map.entities.get(index)?),
+            AnyMap::Valve(map) => AnyEntity::Valve(map.entities.get(index)?),
+            AnyMap::Quake2(map) => AnyEntity::Quake2(map.entities.get(index)?),
+            AnyMap::Quake2Valve(map) => AnyEntity::Quake2Valve(map.entities.get(index)?),
+            AnyMap::Quake3(map) => AnyEntity::Quake3(map.entities.get(index)?),
+            AnyMap::Doom3(map) => AnyEntity::Doom3(map.entities.get(index)?)
+        })
+    }
+
+    /// Returns an iterator over the map's entities.
+    pub fn entities(&self) -> impl Iterator<Item = AnyEntity<'_>> {
+        (0..self.len()).filter_map(move |index| self.entity(index))
+    }
+}
+
+/// A reference to an entity of an [AnyMap](AnyMap).
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum AnyEntity<'a> {
+    Standard(&'a standard::Entity),
+    Valve(&'a valve::Entity),
+    Quake2(&'a quake2::standard::Entity),
+    Quake2Valve(&'a quake2::valve::Entity),
+    Quake3(&'a quake3::Entity),
+    Doom3(&'a doom3::Entity)
+}
+
+impl <'a> AnyEntity<'a> {
+    /// Returns the entity's key/value pairs.
+    pub fn fields(&self) -> &'a Fields {
+        match self {
+            AnyEntity::Standard(ent) => &ent.fields,
+            AnyEntity::Valve(ent) => &ent.fields,
+            AnyEntity::Quake2(ent) => &ent.fields,
+            AnyEntity::Quake2Valve(ent) => &ent.fields,
+            AnyEntity::Quake3(ent) => &ent.fields,
+            AnyEntity::Doom3(ent) => &ent.fields
+        }
+    }
+
+    /// Returns an iterator over the entity's brushes, leaving out any patches.
+    pub fn brushes(&self) -> impl Iterator<Item = AnyBrush<'a>> {
+        match *self {
+            AnyEntity::Standard(ent) => Brushes::Standard(ent.brushes.iter()),
+            AnyEntity::Valve(ent) => Brushes::Valve(ent.brushes.iter()),
+            AnyEntity::Quake2(ent) => Brushes::Quake2(ent.brushes.iter()),
+            AnyEntity::Quake2Valve(ent) => Brushes::Quake2Valve(ent.brushes.iter()),
+            AnyEntity::Quake3(ent) => Brushes::Quake3(ent.brushes.iter()),
+            AnyEntity::Doom3(ent) => Brushes::Doom3(ent.brushes.iter())
+        }
+    }
+
+    /// Returns the entity's patches, which only the Quake 3
+    /// and Doom 3 formats have.
+    pub fn patches(&self) -> Vec<&'a quake3::Patch> {
+        match self {
+            AnyEntity::Quake3(ent) => ent.brushes
+                .iter()
+                .filter_map(|primitive| match primitive {
+                    quake3::Primitive::Patch(patch) => Some(patch),
+                    quake3::Primitive::Brush(_) => None
+                })
+                .collect(),
+            AnyEntity::Doom3(ent) => ent.brushes
+                .iter()
+                .filter_map(|primitive| match primitive {
+                    doom3::Primitive::Patch(patch) => Some(&patch.patch),
+                    doom3::Primitive::Brush(_) => None
+                })
+                .collect(),
+            _ => Vec::new()
+        }
+    }
+}
+
+/// A format-independent view of a brush of an [AnyEntity](AnyEntity).
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnyBrush<'a> {
+    pub planes: Vec<AnyPlane<'a>>
+}
+
+impl <'a> AnyBrush<'a> {
+    fn from_shared<TA>(brush: &'a shared::Brush<TA>) -> Self {
+        AnyBrush {
+            planes: brush.planes
+                .iter()
+                .map(|plane| AnyPlane {
+                    points: Some(plane.points),
+                    texture: &plane.texture.name
+                })
+                .collect()
+        }
+    }
+
+    fn from_doom3(brush: &'a doom3::Brush) -> Self {
+        AnyBrush {
+            planes: brush.planes
+                .iter()
+                .map(|plane| AnyPlane {
+                    points: plane.equation.points(),
+                    texture: &plane.texture.name
+                })
+                .collect()
+        }
+    }
+}
+
+/// A format-independent view of a plane of an [AnyBrush](AnyBrush). Planes of the
+/// Doom 3 format are converted to [points](crate::geometry::PlaneEquation::points),
+/// which are `None` for planes with a normal of length zero.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnyPlane<'a> {
+    pub points: Option<[Vector3; 3]>,
+    pub texture: &'a str
+}
+
+/// The iterator returned by [AnyEntity::brushes](AnyEntity::brushes).
+enum Brushes<'a> {
+    Standard(slice::Iter<'a, standard::Brush>),
+    Valve(slice::Iter<'a, valve::Brush>),
+    Quake2(slice::Iter<'a, quake2::standard::Brush>),
+    Quake2Valve(slice::Iter<'a, quake2::valve::Brush>),
+    Quake3(slice::Iter<'a, quake3::Primitive>),
+    Doom3(slice::Iter<'a, doom3::Primitive>)
+}
+
+impl <'a> Iterator for Brushes<'a> {
+    type Item = AnyBrush<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Brushes::Standard(brushes) => brushes.next().map(AnyBrush::from_shared),
+            Brushes::Valve(brushes) => brushes.next().map(AnyBrush::from_shared),
+            Brushes::Quake2(brushes) => brushes.next().map(AnyBrush::from_shared),
+            Brushes::Quake2Valve(brushes) => brushes.next().map(AnyBrush::from_shared),
+            Brushes::Quake3(primitives) => primitives.find_map(|primitive| match primitive {
+                quake3::Primitive::Brush(brush) => Some(AnyBrush::from_shared(brush)),
+                quake3::Primitive::Patch(_) => None
+            }),
+            Brushes::Doom3(primitives) => primitives.find_map(|primitive| match primitive {
+                doom3::Primitive::Brush(brush) => Some(AnyBrush::from_doom3(brush)),
+                doom3::Primitive::Patch(_) => None
+            })
+        }
+    }
+}
+
+/// The error type returned by [AnyMap::parse](AnyMap::parse).
+#[derive(Debug, Clone, PartialEq)]
+pub enum AnyError<'i> {
+    /// The map couldn't be parsed in the detected format.
+    Parse {
+        format: AnyFormat,
+        error: nom::Err<Error<'i>>
+    },
+    /// The map declares a different format than the one it's written in.
+    Mismatch {
+        declared: AnyFormat,
+        found: AnyFormat
+    },
+    /// A plane of the map is written in a different format than the first one.
+    Mixed {
+        expected: AnyFormat,
+        found: AnyFormat,
+        entity: usize,
+        brush: usize,
+        plane: usize
+    }
+}
+
+impl Display for AnyError<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            AnyError::Parse { format, .. } => write!(
+                f,
+                "failed to parse map in the {} format",
+                format.name()
+            ),
+            AnyError::Mismatch { declared, found } => write!(
+                f,
+                "map declares the {} format, but is written in the {} format",
+                declared.name(),
+                found.name()
+            ),
+            AnyError::Mixed { expected, found, entity, brush, plane } => write!(
+                f,
+                "plane {} of brush {} of entity {} is written in the {} format, \
+                but the map is written in the {} format",
+                plane,
+                brush,
+                entity,
+                found.name(),
+                expected.name()
+            )
+        }
+    }
+}
+
+impl std::error::Error for AnyError<'_> {}
+
+/// The format used to parse maps whose planes may be written in any of
+/// the plane-based formats, so that mixed styles can be reported.
+struct Probe;
+
+impl Format for Probe {
+    type Header = NoHeader;
+    type Entity = shared::Entity<shared::Brush<AnyAlignment>>;
+}
+
+enum AnyAlignment {
+    Standard(standard::TextureAlignment),
+    Valve(valve::TextureAlignment),
+    Quake2(quake2::standard::TextureAlignment),
+    Quake2Valve(quake2::valve::TextureAlignment)
+}
+
+impl AnyAlignment {
+    fn format(&self) -> AnyFormat {
+        match self {
+            AnyAlignment::Standard(_) => AnyFormat::Standard,
+            AnyAlignment::Valve(_) => AnyFormat::Valve,
+            AnyAlignment::Quake2(_) => AnyFormat::Quake2,
+            AnyAlignment::Quake2Valve(_) => AnyFormat::Quake2Valve
+        }
+    }
+
+    fn standard(self) -> Option<standard::TextureAlignment> {
+        match self {
+            AnyAlignment::Standard(alignment) => Some(alignment),
+            _ => None
+        }
+    }
+
+    fn valve(self) -> Option<valve::TextureAlignment> {
+        match self {
+            AnyAlignment::Valve(alignment) => Some(alignment),
+            _ => None
+        }
+    }
+
+    /// Planes of Quake 2 maps may leave out the surface attributes,
+    /// so standard alignments are accepted with the default ones.
+    fn quake2(self) -> Option<quake2::standard::TextureAlignment> {
+        match self {
+            AnyAlignment::Quake2(alignment) => Some(alignment),
+            AnyAlignment::Standard(base) => Some(quake2::TextureAlignment { base, attributes: Default::default() }),
+            _ => None
+        }
+    }
+
+    fn quake2_valve(self) -> Option<quake2::valve::TextureAlignment> {
+        match self {
+            AnyAlignment::Quake2Valve(alignment) => Some(alignment),
+            AnyAlignment::Valve(base) => Some(quake2::TextureAlignment { base, attributes: Default::default() }),
+            _ => None
+        }
+    }
+}
+
+impl <'i, E> Parse<'i, E> for AnyAlignment
+where E: ParseError<Input<'i>> + Clone {
+    fn parse(input: Input<'i>) -> ParseResult<'i, Self, E> {
+        // the Quake 2 variants extend the others, so they have to be tried first
+        alt((
+            map(pair(parse, surface_attributes), |(base, attributes)|
+                AnyAlignment::Quake2Valve(quake2::TextureAlignment { base, attributes })
+            ),
+            map(parse, AnyAlignment::Valve),
+            map(pair(parse, surface_attributes), |(base, attributes)|
+                AnyAlignment::Quake2(quake2::TextureAlignment { base, attributes })
+            ),
+            map(parse, AnyAlignment::Standard)
+        ))(input)
+    }
+}
+
+/// Parses Quake 2 surface attributes only if at least one of them is present,
+/// since an alignment without them can't be told apart from the base format's.
+fn surface_attributes<'i, E>(input: Input<'i>) -> ParseResult<'i, quake2::SurfaceAttributes, E>
+where E: ParseError<Input<'i>> + Clone {
+    preceded(peek(preceded(separator, integer::<i64, E>)), parse)(input)
+}
+
+/// Returns the format of the first plane of the probed map. Planes of Quake 2 maps
+/// may leave out the surface attributes, so a map whose first plane is a standard
+/// or Valve one is a Quake 2 map if any other plane has surface attributes.
+fn probed_format(map: &Map<Probe>) -> Option<AnyFormat> {
+    let mut formats = map.entities
+        .iter()
+        .flat_map(|ent| ent.brushes.iter())
+        .flat_map(|brush| brush.planes.iter())
+        .map(|plane| plane.texture.alignment.format());
+
+    let first = formats.next()?;
+    let quake2 = match first {
+        AnyFormat::Standard => AnyFormat::Quake2,
+        AnyFormat::Valve => AnyFormat::Quake2Valve,
+        _ => return Some(first)
+    };
+
+    Some(if formats.any(|format| format == quake2) { quake2 } else { first })
+}
+
+/// Converts the probed map into one of the given format,
+/// failing at the first plane written in another format.
+fn narrow<'i, F, TA>(
+    probe: Map<Probe>,
+    expected: AnyFormat,
+    convert: fn(AnyAlignment) -> Option<TA>
+) -> Result<Map<F>, AnyError<'i>>
+where F: Format<Header = NoHeader, Entity = shared::Entity<shared::Brush<TA>>> {
+    let mut entities = Vec::with_capacity(probe.entities.len());
+
+    for (entity, ent) in probe.entities.into_iter().enumerate() {
+        let mut brushes = Vec::with_capacity(ent.brushes.len());
+
+        for (brush, planes) in ent.brushes.into_iter().enumerate() {
+            let planes = planes.planes
+                .into_iter()
+                .enumerate()
+                .map(|(plane, shared::Plane { points, texture })| {
+                    let found = texture.alignment.format();
+                    match convert(texture.alignment) {
+                        Some(alignment) => Ok(shared::Plane {
+                            points,
+                            texture: shared::Texture {
+                                name: texture.name,
+                                alignment
+                            }
+                        }),
+                        None => Err(AnyError::Mixed { expected, found, entity, brush, plane })
+                    }
+                })
+                .collect::<Result<_, _>>()?;
+
+            brushes.push(shared::Brush { planes })
+        }
+
+        entities.push(shared::Entity {
+            fields: ent.fields,
+            brushes
+        })
+    }
+
+    Ok(Map {
+        header: NoHeader,
+        entities
+    })
+}
+
+/// Reads the format from a TrenchBroom-style header comment like `// Format: Valve`.
+fn declared_format(input: Input) -> Option<AnyFormat> {
+    input
+        .lines()
+        .map(str::trim)
+        .take_while(|line| line.is_empty() || line.starts_with("//"))
+        .filter_map(|line| line.trim_start_matches('/').trim().strip_prefix("Format:"))
+        .find_map(|format| match format.trim() {
+            "Standard" => Some(AnyFormat::Standard),
+            "Valve" => Some(AnyFormat::Valve),
+            "Quake2" => Some(AnyFormat::Quake2),
+            "Quake2 (Valve)" => Some(AnyFormat::Quake2Valve),
+            _ => None
+        })
+}
+
+fn mapversion(fields: &Fields) -> bool {
+    fields
+        .get("mapversion")
+        .is_some_and(|version| version.trim() == "220")
+}
+
+fn skip_separator(input: Input) -> Input {
+    opt(separator::<Error>)(input)
+        .map_or(input, |(rest, _)| rest)
+}
+
+/// Returns the input following the first opening brace at the given depth,
+/// skipping over comments and quoted strings.
+fn open_brace(input: Input, depth: usize) -> Option<Input> {
+    let mut current = 0;
+    let mut chars = input.char_indices().peekable();
+
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '/' if matches!(chars.peek(), Some((_, '/'))) => {
+                while !matches!(chars.next(), Some((_, '\n')) | None) {}
+            },
+            '"' => {
+                let mut escaped = false;
+                for (_, c) in chars.by_ref() {
+                    match c {
+                        '"' if !escaped => break,
+                        '\\' => escaped = !escaped,
+                        _ => escaped = false
+                    }
+                }
+            },
+            '{' => {
+                current += 1;
+                if current == depth {
+                    return Some(&input[index + 1..])
+                }
+            },
+            '}' => current = current.saturating_sub(1),
+            _ => ()
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const STANDARD: &str = r#"{
+"classname" "worldspawn"
+{
+( 0 0 0 ) ( 0 64 0 ) ( 64 0 0 ) floor 0 0 0 1 1
+( 0 0 64 ) ( 64 0 64 ) ( 0 64 64 ) ceiling 0 0 0 1 1
+}
+}
+{
+"classname" "info_player_start"
+"origin" "32 32 24"
+}"#;
+
+    const VALVE: &str = r#"// Game: Half-Life
+// Format: Valve
+{
+"classname" "worldspawn"
+"mapversion" "220"
+{
+( 0 0 0 ) ( 0 64 0 ) ( 64 0 0 ) floor [ 1 0 0 0 ] [ 0 -1 0 0 ] 0 1 1
+}
+}"#;
+
+    const QUAKE2: &str = r#"{
+"classname" "worldspawn"
+{
+( 0 0 0 ) ( 0 64 0 ) ( 64 0 0 ) e1u1/floor 0 0 0 1 1 0 1 300
+}
+}"#;
+
+    #[test]
+    fn detect() {
+        assert_eq!(AnyFormat::detect(STANDARD), Some(AnyFormat::Standard));
+        assert_eq!(AnyFormat::detect(VALVE), Some(AnyFormat::Valve));
+        assert_eq!(AnyFormat::detect(QUAKE2), Some(AnyFormat::Quake2));
+        assert_eq!(
+            AnyFormat::detect(r#"{ "classname" "worldspawn" { brushDef { } } }"#),
+            Some(AnyFormat::Quake3)
+        );
+        assert_eq!(
+            AnyFormat::detect("// doom\nVersion 2\n{ }"),
+            Some(AnyFormat::Doom3)
+        );
+        assert_eq!(
+            AnyFormat::detect(r#"{ "classname" "worldspawn" "mapversion" "220" }"#),
+            Some(AnyFormat::Valve)
+        );
+        assert_eq!(AnyFormat::detect(r#"{ "classname" "worldspawn" }"#), None);
+    }
+
+    #[test]
+    fn parse() {
+        let map = AnyMap::parse(include_str!("../../../examples/example.map")).unwrap();
+
+        assert_eq!(map.format(), AnyFormat::Standard);
+        assert_eq!(map.len(), 5);
+
+        let worldspawn = map.entity(0).unwrap();
+        assert_eq!(worldspawn.fields()["classname"], "worldspawn");
+        assert_eq!(worldspawn.brushes().count(), 8);
+        assert_eq!(worldspawn.brushes().next().unwrap().planes[0].texture, "sfloor4_6");
+    }
+
+    #[test]
+    fn doom3_brushes() {
+        let map = AnyMap::parse(r#"Version 2
+{
+"classname" "worldspawn"
+{
+brushDef3
+{
+( 0 0 -1 -64 ) ( ( 0.0078125 0 0 ) ( 0 0.0078125 0 ) ) "textures/common/caulk" 0 0 0
+( 0 0 0 -64 ) ( ( 0.0078125 0 0 ) ( 0 0.0078125 0 ) ) "textures/common/caulk" 0 0 0
+}
+}
+}"#).unwrap();
+        let brush = map.entity(0).unwrap().brushes().next().unwrap();
+
+        assert!(brush.planes[0].points.is_some());
+        assert_eq!(brush.planes[1].points, None)
+    }
+
+    #[test]
+    fn parse_escaping() {
+        let input = r#"{ "classname" "worldspawn" "wad" "c:\wads\" }"#;
+        let map = AnyMap::parse_with(input, Escaping::Verbatim).unwrap();
+
+        assert_eq!(map.entity(0).unwrap().fields()["wad"], r"c:\wads\");
+        assert!(AnyMap::parse(input).is_err())
+    }
+
+    #[test]
+    fn parse_formats() {
+        assert_eq!(AnyMap::parse(VALVE).unwrap().format(), AnyFormat::Valve);
+        assert_eq!(AnyMap::parse(QUAKE2).unwrap().format(), AnyFormat::Quake2);
+        assert_eq!(
+            AnyMap::parse(r#"{ "classname" "worldspawn" }"#).unwrap().format(),
+            AnyFormat::Standard
+        );
+    }
+
+    #[test]
+    fn quake2_without_attributes() {
+        let input = r#"{
+"classname" "worldspawn"
+{
+( 0 0 0 ) ( 0 64 0 ) ( 64 0 0 ) e1u1/floor 0 0 0 1 1 0 1 300
+( 0 0 64 ) ( 64 0 64 ) ( 0 64 64 ) e1u1/ceil 0 0 0 1 1
+}
+}"#;
+
+        match AnyMap::parse(input) {
+            Ok(AnyMap::Quake2(map)) => assert_eq!(
+                map.entities[0].brushes[0].planes[1].texture.alignment.attributes,
+                quake2::SurfaceAttributes::default()
+            ),
+            other => panic!("expected a Quake 2 map, got {:?}", other)
+        }
+        assert_eq!(AnyFormat::detect(STANDARD), Some(AnyFormat::Standard))
+    }
+
+    #[test]
+    fn quake2_without_attributes_first() {
+        let input = r#"{
+"classname" "worldspawn"
+{
+( 0 0 64 ) ( 64 0 64 ) ( 0 64 64 ) e1u1/ceil 0 0 0 1 1
+( 0 0 0 ) ( 0 64 0 ) ( 64 0 0 ) e1u1/floor 0 0 0 1 1 0 1 300
+}
+}"#;
+
+        assert_eq!(AnyFormat::detect(input), Some(AnyFormat::Standard));
+        match AnyMap::parse(input) {
+            Ok(AnyMap::Quake2(map)) => {
+                let planes = &map.entities[0].brushes[0].planes;
+                assert_eq!(planes[0].texture.alignment.attributes, quake2::SurfaceAttributes::default());
+                assert_eq!(planes[1].texture.alignment.attributes.value, 300)
+            },
+            other => panic!("expected a Quake 2 map, got {:?}", other)
+        }
+
+        let valve = input
+            .replace("0 0 0 1 1", "[ 1 0 0 0 ] [ 0 -1 0 0 ] 0 1 1");
+        assert_eq!(AnyMap::parse(&valve).map(|map| map.format()), Ok(AnyFormat::Quake2Valve))
+    }
+
+    #[test]
+    fn mixed() {
+        let input = r#"{
+"classname" "worldspawn"
+{
+( 0 0 0 ) ( 0 64 0 ) ( 64 0 0 ) floor 0 0 0 1 1
+}
+{
+( 0 0 0 ) ( 0 64 0 ) ( 64 0 0 ) floor 0 0 0 1 1
+( 0 0 64 ) ( 64 0 64 ) ( 0 64 64 ) ceiling [ 1 0 0 0 ] [ 0 -1 0 0 ] 0 1 1
+}
+}"#;
+
+        assert_eq!(
+            AnyMap::parse(input),
+            Err(AnyError::Mixed {
+                expected: AnyFormat::Standard,
+                found: AnyFormat::Valve,
+                entity: 0,
+                brush: 1,
+                plane: 1
+            })
+        )
+    }
+
+    #[test]
+    fn mismatch() {
+        assert_eq!(
+            AnyMap::parse(&format!("// Format: Valve\n{}", STANDARD)),
+            Err(AnyError::Mismatch {
+                declared: AnyFormat::Valve,
+                found: AnyFormat::Standard
+            })
+        );
+        assert_eq!(
+            AnyMap::parse(&STANDARD.replacen(r#""worldspawn""#, r#""worldspawn" "mapversion" "220""#, 1)),
+            Err(AnyError::Mismatch {
+                declared: AnyFormat::Valve,
+                found: AnyFormat::Standard
+            })
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_roundtrip() {
+        let map = AnyMap::parse(STANDARD).unwrap();
+        let json = serde_json::to_string(&map).unwrap();
+
+        assert!(json.starts_with(r#"{"format":"Standard","map":{"header":null,"entities":["#));
+        assert_eq!(serde_json::from_str::<AnyMap>(&json).unwrap(), map)
+    }
+}
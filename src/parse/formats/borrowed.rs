@@ -0,0 +1,188 @@
+//! Variants of the [shared](super::shared) types that borrow their strings
+//! from the input instead of copying them into `String`s, which saves most of
+//! the allocations when the map is only read. They can be turned into
+//! the owned types with [IntoOwned](IntoOwned). Only the formats made up
+//! of [shared](super::shared) brushes have borrowed variants, so there are
+//! none for the [Quake3](super::Quake3) and [Doom3](super::Doom3) formats.
+
+use {
+    std::{
+        borrow::Cow,
+        marker::PhantomData
+    },
+    crate::parse::formats::{
+        Map,
+        Format,
+        NoHeader,
+        shared,
+        valve,
+        quake2,
+        standard
+    }
+};
+
+/// [Fields](shared::Fields) borrowing from the input.
+pub type Fields<'i> = shared::Fields<Cow<'i, str>>;
+
+/// An [Entity](shared::Entity) whose fields borrow from the input.
+pub type Entity<'i, B> = shared::Entity<B, Cow<'i, str>>;
+
+/// A [Brush](shared::Brush) whose texture names borrow from the input.
+pub type Brush<'i, TA> = shared::Brush<TA, Cow<'i, str>>;
+
+/// A [Plane](shared::Plane) whose texture name borrows from the input.
+pub type Plane<'i, TA> = shared::Plane<TA, Cow<'i, str>>;
+
+/// A [Texture](shared::Texture) whose name borrows from the input.
+pub type Texture<'i, TA> = shared::Texture<TA, Cow<'i, str>>;
+
+/// Trait for formats whose entities consist of [shared](shared) brushes
+/// made up of planes, which therefore have a [Borrowed](Borrowed) variant.
+/// It's implemented for the Standard, Valve and both Quake 2 formats.
+pub trait BrushFormat: Format<
+    Header = NoHeader,
+    Entity = shared::Entity<shared::Brush<<Self as BrushFormat>::TextureAlignment>>
+> {
+    type TextureAlignment;
+}
+
+impl BrushFormat for standard::Standard {
+    type TextureAlignment = standard::TextureAlignment;
+}
+
+impl BrushFormat for valve::Valve {
+    type TextureAlignment = valve::TextureAlignment;
+}
+
+impl BrushFormat for quake2::Quake2 {
+    type TextureAlignment = quake2::standard::TextureAlignment;
+}
+
+impl BrushFormat for quake2::Quake2Valve {
+    type TextureAlignment = quake2::valve::TextureAlignment;
+}
+
+/// The variant of the format `F` that borrows its strings from the input,
+/// for any [BrushFormat](BrushFormat). The Quake 3 and Doom 3 formats, whose
+/// entities contain brush primitives and patches, don't have one.
+/// ```
+/// use nomap::formats::{Standard, borrowed::{Borrowed, IntoOwned}};
+///
+/// let input = include_str!("../../../examples/example.map");
+/// let map = nomap::parse::<Borrowed<Standard>>(input).unwrap();
+/// assert_eq!(map.into_owned(), nomap::parse::<Standard>(input).unwrap());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Borrowed<'i, F>(PhantomData<(&'i str, F)>);
+
+impl <'i, F: BrushFormat> Format for Borrowed<'i, F> {
+    type Header = NoHeader;
+    type Entity = Entity<'i, Brush<'i, F::TextureAlignment>>;
+}
+
+/// Conversion of a borrowed type into its owned counterpart.
+pub trait IntoOwned {
+    type Owned;
+
+    fn into_owned(self) -> Self::Owned;
+}
+
+impl IntoOwned for Fields<'_> {
+    type Owned = shared::Fields;
+
+    fn into_owned(self) -> Self::Owned {
+        shared::Fields(
+            self.0
+                .into_iter()
+                .map(|(key, value)| (key.into_owned(), value.into_owned()))
+                .collect()
+        )
+    }
+}
+
+impl <TA> IntoOwned for Texture<'_, TA> {
+    type Owned = shared::Texture<TA>;
+
+    fn into_owned(self) -> Self::Owned {
+        shared::Texture {
+            name: self.name.into_owned(),
+            alignment: self.alignment
+        }
+    }
+}
+
+impl <TA> IntoOwned for Plane<'_, TA> {
+    type Owned = shared::Plane<TA>;
+
+    fn into_owned(self) -> Self::Owned {
+        shared::Plane {
+            points: self.points,
+            texture: self.texture.into_owned()
+        }
+    }
+}
+
+impl <TA> IntoOwned for Brush<'_, TA> {
+    type Owned = shared::Brush<TA>;
+
+    fn into_owned(self) -> Self::Owned {
+        shared::Brush {
+            planes: self.planes
+                .into_iter()
+                .map(IntoOwned::into_owned)
+                .collect()
+        }
+    }
+}
+
+impl <B: IntoOwned> IntoOwned for Entity<'_, B> {
+    type Owned = shared::Entity<B::Owned>;
+
+    fn into_owned(self) -> Self::Owned {
+        shared::Entity {
+            fields: self.fields.into_owned(),
+            brushes: self.brushes
+                .into_iter()
+                .map(IntoOwned::into_owned)
+                .collect()
+        }
+    }
+}
+
+impl <F: BrushFormat> IntoOwned for Map<Borrowed<'_, F>> {
+    type Owned = Map<F>;
+
+    fn into_owned(self) -> Self::Owned {
+        Map {
+            header: self.header,
+            entities: self.entities
+                .into_iter()
+                .map(IntoOwned::into_owned)
+                .collect()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        crate::parse::formats::Valve
+    };
+
+    #[test]
+    fn borrowed() {
+        let input = r#"{
+"classname" "worldspawn"
+{
+( 0 0 0 ) ( 0 64 0 ) ( 64 0 0 ) floor [ 1 0 0 0 ] [ 0 -1 0 0 ] 0 1 1
+}
+}"#;
+        let map = crate::parse::<Borrowed<Valve>>(input).unwrap();
+        let worldspawn = &map.entities[0];
+
+        assert!(matches!(worldspawn.fields["classname"], Cow::Borrowed("worldspawn")));
+        assert!(matches!(worldspawn.brushes[0].planes[0].texture.name, Cow::Borrowed("floor")));
+        assert_eq!(map.into_owned(), crate::parse::<Valve>(input).unwrap())
+    }
+}
@@ -0,0 +1,538 @@
+use {
+    std::ops::{Deref, DerefMut},
+    crate::{
+        geometry::PlaneEquation,
+        parse::{
+            common::{parse, integer, quoted_string},
+            formats::{
+                Format,
+                Header,
+                quake3::{self, control_points, has_size},
+                shared::{
+                    self,
+                    Vector3,
+                    braced,
+                    parenthesized,
+                    sep_terminated,
+                    maybe_sep_terminated
+                }
+            },
+            core::{
+                Parse,
+                Input,
+                ParseResult,
+                nom::{
+                    bytes::tag,
+                    branch::alt,
+                    multi::many0,
+                    number::float,
+                    error::ParseError,
+                    sequence::{pair, preceded, tuple},
+                    combinator::{map, verify}
+                }
+            }
+        }
+    }
+};
+
+/// The map format used by id Tech 4 games like Doom 3 and Quake 4.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Doom3;
+
+impl Format for Doom3 {
+    type Header = Version;
+    type Entity = Entity;
+}
+
+/// The Doom 3 format's Entity type. Its `brushes` contain
+/// both brushes and patches.
+pub type Entity = shared::Entity<Primitive>;
+
+/// The Doom 3 format's Texture type.
+pub type Texture = shared::Texture<TextureAlignment>;
+
+/// The Doom 3 format's texture alignment, which is the same as the
+/// [Quake 3 format's](quake3::TextureAlignment).
+pub type TextureAlignment = quake3::TextureAlignment;
+
+/// The header at the start of every Doom 3 map, which looks like this:
+/// ```plain
+/// Version 2
+/// ```
+/// Doom 3 uses version 2, while Quake 4 uses version 3.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Version(pub u32);
+
+impl Header for Version {
+    fn is_empty(&self) -> bool {
+        false
+    }
+}
+
+impl <'i, E> Parse<'i, E> for Version
+where E: ParseError<Input<'i>> + Clone {
+    fn parse(input: Input<'i>) -> ParseResult<'i, Self, E> {
+        map(
+            preceded(sep_terminated(tag("Version")), integer),
+            Version
+        )(input)
+    }
+}
+
+/// A primitive of a Doom 3 [Entity](Entity), which is either a brush or a patch.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Primitive {
+    Brush(Brush),
+    Patch(Patch)
+}
+
+impl <'i, E> Parse<'i, E> for Primitive
+where E: ParseError<Input<'i>> + Clone {
+    fn parse(input: Input<'i>) -> ParseResult<'i, Self, E> {
+        alt((
+            map(parse, Primitive::Brush),
+            map(parse, Primitive::Patch)
+        ))(input)
+    }
+}
+
+/// Representation of a Doom 3 brush, consisting of a list of [Plane](Plane)s
+/// in a `brushDef3` block. In a map file, it usually looks something like this:
+/// ```plain
+/// {
+/// brushDef3
+/// {
+/// ( 0 0 -1 -64 ) ( ( 0.0078125 0 0 ) ( 0 0.0078125 0 ) ) "textures/common/caulk" 0 0 0
+/// }
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Brush {
+    pub planes: Vec<Plane>
+}
+
+impl Brush {
+    /// Converts the brush into a [Brush](shared::Brush) with three-point
+    /// [Plane](shared::Plane)s. Returns `None` if any of the planes
+    /// can't be converted.
+    pub fn to_shared(&self) -> Option<shared::Brush<TextureAlignment>> {
+        self.planes
+            .iter()
+            .map(Plane::to_shared)
+            .collect::<Option<_>>()
+            .map(|planes| shared::Brush { planes })
+    }
+}
+
+impl <'i, E> Parse<'i, E> for Brush
+where E: ParseError<Input<'i>> + Clone {
+    fn parse(input: Input<'i>) -> ParseResult<'i, Self, E> {
+        map(
+            braced(
+                preceded(
+                    maybe_sep_terminated(tag("brushDef3")),
+                    braced(many0(maybe_sep_terminated(parse)))
+                )
+            ),
+            |planes| Brush { planes }
+        )(input)
+    }
+}
+
+/// Representation of a Doom 3 plane, which describes its half-space
+/// with a [plane equation](PlaneEquation) instead of three points.
+/// In a map file, the equation is written as `( a b c d )`, where
+/// `( a b c )` is the normal and `d` is the negated distance.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Plane {
+    pub equation: PlaneEquation,
+    pub texture: Texture
+}
+
+impl Plane {
+    /// Converts the plane into a three-point [Plane](shared::Plane)
+    /// describing the same half-space. Returns `None` if the plane's
+    /// normal has a length of zero.
+    pub fn to_shared(&self) -> Option<shared::Plane<TextureAlignment>> {
+        self.equation
+            .points()
+            .map(|points| shared::Plane {
+                points,
+                texture: self.texture.clone()
+            })
+    }
+}
+
+impl <'i, E> Parse<'i, E> for Plane
+where E: ParseError<Input<'i>> + Clone {
+    fn parse(input: Input<'i>) -> ParseResult<'i, Self, E> {
+        map(
+            pair(maybe_sep_terminated(equation), texture),
+            |(equation, texture)| Plane { equation, texture }
+        )(input)
+    }
+}
+
+/// Parses a plane equation written as `( a b c d )`.
+pub(crate) fn equation<'i, E>(input: Input<'i>) -> ParseResult<'i, PlaneEquation, E>
+where E: ParseError<Input<'i>> + Clone {
+    map(
+        parenthesized(
+            tuple((
+                sep_terminated(float),
+                sep_terminated(float),
+                sep_terminated(float),
+                float
+            ))
+        ),
+        |(x, y, z, d)| PlaneEquation {
+            normal: Vector3 { x, y, z },
+            distance: -d
+        }
+    )(input)
+}
+
+/// Parses the part of a plane following its equation, which
+/// is the texture matrix, the texture name and the flags.
+pub(crate) fn texture<'i, E>(input: Input<'i>) -> ParseResult<'i, Texture, E>
+where E: ParseError<Input<'i>> + Clone {
+    map(
+        tuple((
+            maybe_sep_terminated(parse),
+            maybe_sep_terminated(quoted_string),
+            sep_terminated(integer),
+            sep_terminated(integer),
+            integer
+        )),
+        |(matrix, name, content_flags, surface_flags, value)| Texture {
+            name: name.into(),
+            alignment: TextureAlignment {
+                matrix,
+                content_flags,
+                surface_flags,
+                value
+            }
+        }
+    )(input)
+}
+
+/// The Doom 3 format's Patch type, which extends a [Quake 3 patch](quake3::Patch)
+/// with optional explicit [Subdivisions](Subdivisions). Patches in `patchDef2`
+/// blocks don't have them, while those in `patchDef3` blocks do:
+/// ```plain
+/// {
+/// patchDef3
+/// {
+/// "textures/base_wall/lfwall13f3"
+/// ( 3 3 4 4 0 0 0 )
+/// (
+/// ( ( -64 -64 0 0 0 ) ( -64 0 32 0 0.5 ) ( -64 64 0 0 1 ) )
+/// ( ( 0 -64 32 0.5 0 ) ( 0 0 64 0.5 0.5 ) ( 0 64 32 0.5 1 ) )
+/// ( ( 64 -64 0 1 0 ) ( 64 0 32 1 0.5 ) ( 64 64 0 1 1 ) )
+/// )
+/// }
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Patch {
+    pub patch: quake3::Patch,
+    pub subdivisions: Option<Subdivisions>
+}
+
+impl Deref for Patch {
+    type Target = quake3::Patch;
+
+    fn deref(&self) -> &Self::Target {
+        &self.patch
+    }
+}
+
+impl DerefMut for Patch {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.patch
+    }
+}
+
+impl <'i, E> Parse<'i, E> for Patch
+where E: ParseError<Input<'i>> + Clone {
+    fn parse(input: Input<'i>) -> ParseResult<'i, Self, E> {
+        map(patch, |(patch, _)| patch)(input)
+    }
+}
+
+/// Parses a patch along with the part of the input its texture name was
+/// parsed from, which doesn't include the quotes.
+pub(crate) fn patch<'i, E>(input: Input<'i>) -> ParseResult<'i, (Patch, Input<'i>), E>
+where E: ParseError<Input<'i>> + Clone {
+    map(
+        braced(
+            alt((
+                preceded(
+                    maybe_sep_terminated(tag("patchDef2")),
+                    braced(patch_body(
+                        map(
+                            tuple((
+                                sep_terminated(integer),
+                                sep_terminated(integer),
+                                sep_terminated(integer),
+                                sep_terminated(integer),
+                                integer
+                            )),
+                            |(width, height, content_flags, surface_flags, value)|
+                                (width, height, None, content_flags, surface_flags, value)
+                        )
+                    ))
+                ),
+                preceded(
+                    maybe_sep_terminated(tag("patchDef3")),
+                    braced(patch_body(
+                        map(
+                            tuple((
+                                sep_terminated(integer),
+                                sep_terminated(integer),
+                                sep_terminated(integer),
+                                sep_terminated(integer),
+                                sep_terminated(integer),
+                                sep_terminated(integer),
+                                integer
+                            )),
+                            |(width, height, horizontal, vertical, content_flags, surface_flags, value)| (
+                                width,
+                                height,
+                                Some(Subdivisions { horizontal, vertical }),
+                                content_flags,
+                                surface_flags,
+                                value
+                            )
+                        )
+                    ))
+                )
+            ))
+        ),
+        |(texture, (_, _, subdivisions, content_flags, surface_flags, value), control_points)| (
+            Patch {
+                patch: quake3::Patch {
+                    texture: texture.into(),
+                    content_flags,
+                    surface_flags,
+                    value,
+                    control_points
+                },
+                subdivisions
+            },
+            texture
+        )
+    )(input)
+}
+
+/// The width, height, subdivisions and flags of a [Patch](Patch).
+type PatchInfo = (usize, usize, Option<Subdivisions>, u32, u32, i32);
+
+/// The texture, info and control points of a [Patch](Patch).
+type PatchBody<'i> = (Input<'i>, PatchInfo, Vec<Vec<quake3::ControlPoint>>);
+
+fn patch_body<'i, E, F>(info: F) -> impl Fn(Input<'i>) -> ParseResult<'i, PatchBody<'i>, E>
+where
+    E: ParseError<Input<'i>> + Clone,
+    F: Fn(Input<'i>) -> ParseResult<'i, PatchInfo, E>
+{
+    verify(
+        tuple((
+            maybe_sep_terminated(quoted_string),
+            maybe_sep_terminated(parenthesized(info)),
+            control_points
+        )),
+        |(_, (width, height, ..), control_points): &(_, PatchInfo, Vec<Vec<_>>)|
+            has_size(control_points, *width, *height)
+    )
+}
+
+/// The explicit number of subdivisions of a `patchDef3` [Patch](Patch)
+/// along its width and height.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Subdivisions {
+    pub horizontal: u32,
+    pub vertical: u32
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        crate::parse::common::test::expected
+    };
+
+    #[test]
+    fn version() {
+        assert_eq!(
+            parse(r"Version 2"),
+            expected(Version(2))
+        )
+    }
+
+    #[test]
+    fn version_weird() {
+        assert_eq!(
+            parse(r"Version // quake 4
+            3"),
+            expected(Version(3))
+        )
+    }
+
+    #[test]
+    fn plane() {
+        assert_eq!(
+            parse(r#"( 0 0 -1 -64 ) ( ( 0.0078125 0 0 ) ( 0 0.0078125 0 ) ) "textures/common/caulk" 0 0 0"#),
+            expected(Plane {
+                equation: PlaneEquation {
+                    normal: Vector3 { x: 0., y: 0., z: -1. },
+                    distance: 64.
+                },
+                texture: Texture {
+                    name: "textures/common/caulk".into(),
+                    alignment: TextureAlignment {
+                        matrix: quake3::TextureMatrix {
+                            u: Vector3 { x: 0.0078125, y: 0., z: 0. },
+                            v: Vector3 { x: 0., y: 0.0078125, z: 0. }
+                        },
+                        ..<_>::default()
+                    }
+                }
+            })
+        )
+    }
+
+    #[test]
+    fn brush() {
+        let brush = parse::<Brush, crate::parse::core::Error>(
+            r#"{
+            brushDef3
+            {
+            ( 0 0 -1 -64 ) ( ( 0.0078125 0 0 ) ( 0 0.0078125 0 ) ) "textures/common/caulk" 0 0 0
+            ( 0 0 1 -64 ) ( ( 0.0078125 0 0 ) ( 0 0.0078125 0 ) ) "textures/common/caulk" 0 0 0
+            }
+            }"#
+        ).unwrap().1;
+
+        assert_eq!(brush.planes.len(), 2);
+        assert_eq!(brush.planes[1].equation.distance, 64.);
+    }
+
+    #[test]
+    fn patch() {
+        let patch = parse::<Patch, crate::parse::core::Error>(
+            r#"{
+            patchDef3
+            {
+            "textures/base_wall/lfwall13f3"
+            ( 3 3 4 4 0 0 0 )
+            (
+            ( ( -64 -64 0 0 0 ) ( -64 0 32 0 0.5 ) ( -64 64 0 0 1 ) )
+            ( ( 0 -64 32 0.5 0 ) ( 0 0 64 0.5 0.5 ) ( 0 64 32 0.5 1 ) )
+            ( ( 64 -64 0 1 0 ) ( 64 0 32 1 0.5 ) ( 64 64 0 1 1 ) )
+            )
+            }
+            }"#
+        ).unwrap().1;
+
+        assert_eq!(patch.texture, "textures/base_wall/lfwall13f3");
+        assert_eq!(patch.subdivisions, Some(Subdivisions { horizontal: 4, vertical: 4 }));
+        assert_eq!((patch.width(), patch.height()), (3, 3));
+    }
+
+    #[test]
+    fn map() {
+        let map = crate::parse::<Doom3>(
+            r#"Version 2
+// entity 0
+{
+"classname" "worldspawn"
+// primitive 0
+{
+ brushDef3
+ {
+  ( 0 0 -1 0 ) ( ( 0.0078125 0 0 ) ( 0 0.0078125 0 ) ) "textures/common/caulk" 0 0 0
+ }
+}
+// primitive 1
+{
+ patchDef2
+ {
+  "textures/base_wall/lfwall13f3"
+  ( 3 3 0 0 0 )
+  (
+   ( ( -64 -64 0 0 0 ) ( -64 0 32 0 0.5 ) ( -64 64 0 0 1 ) )
+   ( ( 0 -64 32 0.5 0 ) ( 0 0 64 0.5 0.5 ) ( 0 64 32 0.5 1 ) )
+   ( ( 64 -64 0 1 0 ) ( 64 0 32 1 0.5 ) ( 64 64 0 1 1 ) )
+  )
+ }
+}
+}
+"#
+        ).unwrap();
+
+        assert_eq!(map.header, Version(2));
+        assert!(matches!(map.entities[0].brushes[0], Primitive::Brush(_)));
+        assert!(matches!(map.entities[0].brushes[1], Primitive::Patch(Patch { subdivisions: None, .. })));
+    }
+
+    #[test]
+    fn to_shared() {
+        let brush = Brush {
+            planes: vec![Plane {
+                equation: PlaneEquation {
+                    normal: Vector3 { x: 1., y: 0., z: 0. },
+                    distance: 32.
+                },
+                ..<_>::default()
+            }]
+        };
+        let shared = brush.to_shared().unwrap();
+
+        assert!(shared.planes[0].points.iter().all(|point| point.x == 32.));
+    }
+
+    #[cfg(feature = "display")]
+    #[test]
+    fn roundtrip() {
+        let map = crate::Map::<Doom3> {
+            header: Version(2),
+            entities: vec![Entity {
+                fields: crate::formats::shared::Fields(std::iter::once(("k".into(), "v".into())).collect()),
+                brushes: vec![
+                    Primitive::Brush(Brush {
+                        planes: vec![Plane {
+                            equation: PlaneEquation {
+                                normal: Vector3 { x: 0., y: 0., z: 1. },
+                                distance: 16.
+                            },
+                            texture: Texture {
+                                name: "textures/common/caulk".into(),
+                                ..<_>::default()
+                            }
+                        }]
+                    }),
+                    Primitive::Patch(Patch {
+                        patch: quake3::Patch {
+                            texture: "textures/base_wall/lfwall13f3".into(),
+                            control_points: vec![vec![<_>::default(); 3]; 3],
+                            ..<_>::default()
+                        },
+                        subdivisions: Some(Subdivisions { horizontal: 2, vertical: 6 })
+                    })
+                ]
+            }]
+        };
+        let string = map.to_string();
+        assert_eq!(
+            expected(map),
+            parse(&string)
+        )
+    }
+}
@@ -0,0 +1,172 @@
+use {
+    std::{
+        iter::FusedIterator,
+        marker::PhantomData
+    },
+    crate::parse::{
+        common::parse,
+        formats::{
+            Format,
+            ParseEscaped,
+            shared::{Escaping, separator, maybe_sep_terminated}
+        },
+        core::{
+            Parse,
+            Input,
+            Error,
+            nom::{
+                self,
+                error::ParseError,
+                sequence::preceded,
+                combinator::opt
+            }
+        }
+    }
+};
+
+/// An iterator that parses the entities of a map one at a time, for when
+/// a map only needs to be scanned and building the whole [Map](super::Map)
+/// would be wasteful. The header is parsed when the iterator is created.
+/// After an error, no more entities are returned.
+/// ```
+/// use nomap::formats::{EntityIter, Standard};
+///
+/// let input = include_str!("../../../examples/example.map");
+/// // stops parsing as soon as a monster is found
+/// let monster = EntityIter::<Standard>::new(input)
+///     .unwrap()
+///     .filter_map(Result::ok)
+///     .find(|ent| ent.fields["classname"].starts_with("monster_"));
+/// assert!(monster.is_some());
+/// ```
+pub struct EntityIter<'i, F: Format, E = Error<'i>> {
+    header: F::Header,
+    input: Input<'i>,
+    escaping: Escaping,
+    done: bool,
+    error: PhantomData<E>
+}
+
+impl <'i, F, E> EntityIter<'i, F, E>
+where
+    F: Format,
+    F::Header: Parse<'i, E>,
+    E: ParseError<Input<'i>> + Clone
+{
+    /// Parses the header of the map and returns an iterator over the entities following it.
+    pub fn new(input: Input<'i>) -> Result<Self, nom::Err<E>> {
+        EntityIter::with_escaping(input, Escaping::Raw)
+    }
+
+    /// Like [new](EntityIter::new), but the strings of entity fields
+    /// are decoded with the given [Escaping](Escaping).
+    pub fn with_escaping(input: Input<'i>, escaping: Escaping) -> Result<Self, nom::Err<E>> {
+        preceded(opt(separator), maybe_sep_terminated(parse))(input)
+            .map(|(input, header)| EntityIter {
+                header,
+                input,
+                escaping,
+                done: false,
+                error: PhantomData
+            })
+    }
+}
+
+impl <'i, F: Format, E> EntityIter<'i, F, E> {
+    /// Returns the header of the map.
+    pub fn header(&self) -> &F::Header {
+        &self.header
+    }
+
+    /// Returns the input that hasn't been parsed yet.
+    pub fn remaining(&self) -> Input<'i> {
+        self.input
+    }
+}
+
+impl <'i, F, E> Iterator for EntityIter<'i, F, E>
+where
+    F: Format,
+    F::Entity: ParseEscaped<'i, E>,
+    E: ParseError<Input<'i>> + Clone
+{
+    type Item = Result<F::Entity, nom::Err<E>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done || self.input.is_empty() {
+            self.done = true;
+            return None
+        }
+
+        let escaping = self.escaping;
+        match maybe_sep_terminated(move |input| F::Entity::parse_escaped(escaping, input))(self.input) {
+            Ok((rest, entity)) => {
+                self.input = rest;
+                Some(Ok(entity))
+            },
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+impl <'i, F, E> FusedIterator for EntityIter<'i, F, E>
+where
+    F: Format,
+    F::Entity: ParseEscaped<'i, E>,
+    E: ParseError<Input<'i>> + Clone
+{}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        crate::parse::formats::{Doom3, Standard, doom3::Version}
+    };
+
+    #[test]
+    fn entities() {
+        let input = include_str!("../../../examples/example.map");
+        let entities = EntityIter::<Standard>::new(input)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(entities, crate::parse::<Standard>(input).unwrap().entities)
+    }
+
+    #[test]
+    fn header() {
+        let iter = EntityIter::<Doom3>::new(r#"// header
+        Version 2
+        { "classname" "worldspawn" }"#).unwrap();
+
+        assert_eq!(iter.header(), &Version(2));
+        assert_eq!(iter.count(), 1)
+    }
+
+    #[test]
+    fn error() {
+        let mut iter = EntityIter::<Standard>::new(r#"{ "classname" "worldspawn" }
+        { "classname" "light" }
+        garbage
+        { "classname" "light" }"#).unwrap();
+
+        assert!(matches!(iter.next(), Some(Ok(_))));
+        assert!(matches!(iter.next(), Some(Ok(_))));
+        assert!(matches!(iter.next(), Some(Err(_))));
+        assert_eq!(iter.remaining().trim_start(), "garbage\n        { \"classname\" \"light\" }");
+        assert!(iter.next().is_none())
+    }
+
+    #[test]
+    fn escaping() {
+        let input = r#"{ "classname" "worldspawn" "wad" "c:\wads\" }"#;
+        let mut iter = EntityIter::<Standard>::with_escaping(input, Escaping::Verbatim).unwrap();
+
+        assert_eq!(iter.next().unwrap().unwrap().fields["wad"], r"c:\wads\");
+        assert!(EntityIter::<Standard>::new(input).unwrap().next().unwrap().is_err())
+    }
+}
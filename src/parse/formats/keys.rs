@@ -0,0 +1,568 @@
+//! Typed access to the values of common entity keys, which are stored
+//! as strings in [Fields](Fields).
+//! ```
+//! use nomap::formats::{Standard, keys::Orientation};
+//!
+//! let map = nomap::parse::<Standard>(r#"
+//! {
+//! "classname" "light"
+//! "origin" "-2704 1908 50"
+//! "angle" "-1"
+//! "_color" "1.00 0.93 0.70"
+//! }
+//! "#).unwrap();
+//!
+//! let light = &map.entities[0];
+//! assert_eq!(light.origin().unwrap().unwrap().z, 50.);
+//! assert_eq!(light.orientation().unwrap(), Some(Orientation::UP));
+//! assert!(light.value::<f32>("light").unwrap().is_none());
+//! ```
+
+use {
+    std::{
+        borrow::Borrow,
+        fmt::{self, Display, Formatter}
+    },
+    crate::parse::{
+        common::bitflags,
+        formats::shared::{Fields, Entity, Vector3}
+    }
+};
+
+/// Trait for types that can be stored as the value of an entity key.
+pub trait Value: Sized {
+    /// Parses the value, or returns a description of what was expected instead.
+    fn parse_value(value: &str) -> Result<Self, &'static str>;
+
+    fn format_value(&self) -> String;
+}
+
+/// An error that occurs when the value of a key doesn't have the expected form.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValueError {
+    pub key: String,
+    pub value: String,
+    /// A description of the expected form, like "three numbers".
+    pub expected: &'static str
+}
+
+impl Display for ValueError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            r#"invalid value "{}" for key "{}", expected {}"#,
+            self.value,
+            self.key,
+            self.expected
+        )
+    }
+}
+
+impl std::error::Error for ValueError {}
+
+/// Parses the whitespace separated numbers of `value`,
+/// which have to be exactly `N` finite ones.
+fn numbers<const N: usize>(value: &str) -> Option<[f32; N]> {
+    let mut numbers = [0.; N];
+    let mut parts = value.split_whitespace();
+
+    for number in numbers.iter_mut() {
+        *number = parts
+            .next()?
+            .parse::<f32>()
+            .ok()
+            .filter(|number| number.is_finite())?
+    }
+
+    match parts.next() {
+        Some(_) => None,
+        None => Some(numbers)
+    }
+}
+
+impl Value for f32 {
+    fn parse_value(value: &str) -> Result<Self, &'static str> {
+        numbers::<1>(value)
+            .map(|[number]| number)
+            .ok_or("a number")
+    }
+
+    fn format_value(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl Value for i32 {
+    fn parse_value(value: &str) -> Result<Self, &'static str> {
+        value
+            .trim()
+            .parse()
+            .map_err(|_| "an integer")
+    }
+
+    fn format_value(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl Value for Vector3 {
+    fn parse_value(value: &str) -> Result<Self, &'static str> {
+        numbers(value)
+            .map(|[x, y, z]| Vector3 { x, y, z })
+            .ok_or("three numbers")
+    }
+
+    fn format_value(&self) -> String {
+        format!("{} {} {}", self.x, self.y, self.z)
+    }
+}
+
+/// The color of a light or other entity, as stored in `_color`. Depending on
+/// the tool that wrote it, the components either range from 0 to 1 or from 0 to 255.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct Color {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32
+}
+
+impl Color {
+    /// Returns the color with its components ranging from 0 to 1,
+    /// assuming a range of 0 to 255 if any of them is greater than 1.
+    pub fn normalized(self) -> Self {
+        if self.r > 1. || self.g > 1. || self.b > 1. {
+            Color {
+                r: self.r / 255.,
+                g: self.g / 255.,
+                b: self.b / 255.
+            }
+        } else {
+            self
+        }
+    }
+}
+
+impl Value for Color {
+    fn parse_value(value: &str) -> Result<Self, &'static str> {
+        numbers(value)
+            .filter(|components| components.iter().all(|c| *c >= 0.))
+            .map(|[r, g, b]| Color { r, g, b })
+            .ok_or("three non-negative numbers")
+    }
+
+    fn format_value(&self) -> String {
+        format!("{} {} {}", self.r, self.g, self.b)
+    }
+}
+
+bitflags! {
+    /// The `spawnflags` of an entity. Most bits depend on the class of the entity,
+    /// but the higher ones are used by Quake to exclude entities from difficulties
+    /// and game modes.
+    pub struct SpawnFlags(u32) {
+        const NOT_EASY = 0x100;
+        const NOT_MEDIUM = 0x200;
+        const NOT_HARD = 0x400;
+        const NOT_DEATHMATCH = 0x800;
+    }
+}
+
+impl Value for SpawnFlags {
+    fn parse_value(value: &str) -> Result<Self, &'static str> {
+        value
+            .trim()
+            .parse()
+            .map(SpawnFlags)
+            .map_err(|_| "a non-negative integer")
+    }
+
+    fn format_value(&self) -> String {
+        self.bits().to_string()
+    }
+}
+
+/// The orientation of an entity in degrees, as stored in `angles` as "pitch yaw
+/// roll", or in `angle` as just the yaw. Like in Quake, a positive pitch points
+/// downwards. Lights store it in `mangle` as "yaw pitch roll" with the pitch
+/// pointing upwards instead, see [orientation](Fields::orientation).
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct Orientation {
+    pub pitch: f32,
+    pub yaw: f32,
+    pub roll: f32
+}
+
+impl Orientation {
+    /// Pointing straight up, which is written as an `angle` of -1.
+    pub const UP: Self = Orientation { pitch: -90., yaw: 0., roll: 0. };
+    /// Pointing straight down, which is written as an `angle` of -2.
+    pub const DOWN: Self = Orientation { pitch: 90., yaw: 0., roll: 0. };
+
+    /// Returns the orientation described by the value of an `angle` key,
+    /// taking the special values -1 and -2 into account.
+    pub fn from_angle(angle: f32) -> Self {
+        if angle == -1. {
+            Orientation::UP
+        } else if angle == -2. {
+            Orientation::DOWN
+        } else {
+            Orientation { yaw: angle, ..Orientation::default() }
+        }
+    }
+
+    /// Returns the value of an `angle` key describing the orientation,
+    /// or `None` if it can't be expressed by one.
+    pub fn to_angle(self) -> Option<f32> {
+        if self == Orientation::UP {
+            Some(-1.)
+        } else if self == Orientation::DOWN {
+            Some(-2.)
+        } else if self.pitch == 0. && self.roll == 0. && self.yaw != -1. && self.yaw != -2. {
+            Some(self.yaw)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the unit vector pointing in the direction of the orientation.
+    pub fn direction(self) -> Vector3 {
+        let (pitch_sin, pitch_cos) = self.pitch.to_radians().sin_cos();
+        let (yaw_sin, yaw_cos) = self.yaw.to_radians().sin_cos();
+
+        Vector3 {
+            x: pitch_cos * yaw_cos,
+            y: pitch_cos * yaw_sin,
+            z: -pitch_sin
+        }
+    }
+}
+
+impl Value for Orientation {
+    fn parse_value(value: &str) -> Result<Self, &'static str> {
+        numbers(value)
+            .map(|[pitch, yaw, roll]| Orientation { pitch, yaw, roll })
+            .ok_or("three numbers")
+    }
+
+    fn format_value(&self) -> String {
+        format!("{} {} {}", self.pitch, self.yaw, self.roll)
+    }
+}
+
+/// An [Orientation](Orientation) stored in the `mangle` of a light, which the
+/// light tools read as "yaw pitch roll" with a positive pitch pointing upwards.
+struct LightMangle(Orientation);
+
+/// Converts between the pitch of an [Orientation](Orientation) and of a light's `mangle`.
+fn flip_pitch(pitch: f32) -> f32 {
+    // subtracting from 0 turns -0 into 0, unlike negating, which would otherwise be written as "-0"
+    0. - pitch
+}
+
+impl Value for LightMangle {
+    fn parse_value(value: &str) -> Result<Self, &'static str> {
+        numbers(value)
+            .map(|[yaw, pitch, roll]| LightMangle(Orientation { pitch: flip_pitch(pitch), yaw, roll }))
+            .ok_or("three numbers")
+    }
+
+    fn format_value(&self) -> String {
+        let Orientation { pitch, yaw, roll } = self.0;
+        format!("{} {} {}", yaw, flip_pitch(pitch), roll)
+    }
+}
+
+/// The keys an [Orientation](Orientation) can be stored in, in the order they're looked up.
+const ORIENTATION_KEYS: [&str; 3] = ["angles", "mangle", "angle"];
+
+/// The only entity whose `mangle` is "pitch yaw roll" like `angles`.
+/// Everywhere else, `mangle` is used by lights, in the light tools' convention.
+const PITCH_YAW_ROLL_MANGLE: &str = "info_intermission";
+
+impl <S: Borrow<str>> Fields<S> {
+    /// Returns the last value of `key` parsed as `T`,
+    /// or `None` if `key` isn't present.
+    pub fn value<T: Value>(&self, key: &str) -> Result<Option<T>, ValueError> {
+        self.get(key)
+            .map(|value| T::parse_value(value.borrow())
+                .map_err(|expected| ValueError {
+                    key: key.into(),
+                    value: value.borrow().into(),
+                    expected
+                })
+            )
+            .transpose()
+    }
+
+    /// Returns the value of `origin`.
+    pub fn origin(&self) -> Result<Option<Vector3>, ValueError> {
+        self.value("origin")
+    }
+
+    /// Returns the value of `_color`.
+    pub fn color(&self) -> Result<Option<Color>, ValueError> {
+        self.value("_color")
+    }
+
+    /// Returns the value of `spawnflags`.
+    pub fn spawnflags(&self) -> Result<Option<SpawnFlags>, ValueError> {
+        self.value("spawnflags")
+    }
+
+    /// Returns the orientation stored in `angles`, `mangle` or `angle`,
+    /// whichever is present first in that order. The `mangle` of an
+    /// `info_intermission` is "pitch yaw roll" like `angles`, whereas
+    /// any other `mangle` is read like the light tools read spotlights,
+    /// as "yaw pitch roll" with a positive pitch pointing upwards.
+    pub fn orientation(&self) -> Result<Option<Orientation>, ValueError> {
+        match self.orientation_key() {
+            Some("angle") => self
+                .value("angle")
+                .map(|angle| angle.map(Orientation::from_angle)),
+            Some("mangle") if self.is_light_mangle() => self
+                .value("mangle")
+                .map(|mangle| mangle.map(|LightMangle(orientation)| orientation)),
+            Some(key) => self.value(key),
+            None => Ok(None)
+        }
+    }
+
+    fn is_light_mangle(&self) -> bool {
+        self.get("classname").map(Borrow::borrow) != Some(PITCH_YAW_ROLL_MANGLE)
+    }
+
+    fn orientation_key(&self) -> Option<&'static str> {
+        ORIENTATION_KEYS
+            .iter()
+            .copied()
+            .find(|key| self.contains_key(key))
+    }
+}
+
+impl <S: Borrow<str> + From<String>> Fields<S> {
+    /// Sets the value of `key` like [insert](Fields::insert).
+    pub fn set_value<T: Value>(&mut self, key: &str, value: &T) {
+        self.insert(S::from(key.into()), S::from(value.format_value()));
+    }
+
+    /// Sets the value of `origin`.
+    pub fn set_origin(&mut self, origin: Vector3) {
+        self.set_value("origin", &origin)
+    }
+
+    /// Sets the value of `_color`.
+    pub fn set_color(&mut self, color: Color) {
+        self.set_value("_color", &color)
+    }
+
+    /// Sets the value of `spawnflags`.
+    pub fn set_spawnflags(&mut self, spawnflags: SpawnFlags) {
+        self.set_value("spawnflags", &spawnflags)
+    }
+
+    /// Stores `orientation` in the key [orientation](Fields::orientation)
+    /// reads it from. If that's `angle` and the orientation can't be
+    /// expressed by one, or no such key is present, `angles` is used
+    /// instead, unless `angle` suffices.
+    pub fn set_orientation(&mut self, orientation: Orientation) {
+        let angle = orientation.to_angle();
+
+        match (self.orientation_key(), angle) {
+            (Some("angle"), Some(angle)) | (None, Some(angle)) => self.set_value("angle", &angle),
+            (Some("angle"), None) | (None, None) => {
+                self.remove("angle");
+                self.set_value("angles", &orientation)
+            },
+            (Some("mangle"), _) if self.is_light_mangle() =>
+                self.set_value("mangle", &LightMangle(orientation)),
+            (Some(key), _) => self.set_value(key, &orientation)
+        }
+    }
+}
+
+impl <B, S: Borrow<str>> Entity<B, S> {
+    /// Shorthand for [Fields::value](Fields::value).
+    pub fn value<T: Value>(&self, key: &str) -> Result<Option<T>, ValueError> {
+        self.fields.value(key)
+    }
+
+    /// Shorthand for [Fields::origin](Fields::origin).
+    pub fn origin(&self) -> Result<Option<Vector3>, ValueError> {
+        self.fields.origin()
+    }
+
+    /// Shorthand for [Fields::color](Fields::color).
+    pub fn color(&self) -> Result<Option<Color>, ValueError> {
+        self.fields.color()
+    }
+
+    /// Shorthand for [Fields::spawnflags](Fields::spawnflags).
+    pub fn spawnflags(&self) -> Result<Option<SpawnFlags>, ValueError> {
+        self.fields.spawnflags()
+    }
+
+    /// Shorthand for [Fields::orientation](Fields::orientation).
+    pub fn orientation(&self) -> Result<Option<Orientation>, ValueError> {
+        self.fields.orientation()
+    }
+}
+
+impl <B, S: Borrow<str> + From<String>> Entity<B, S> {
+    /// Shorthand for [Fields::set_value](Fields::set_value).
+    pub fn set_value<T: Value>(&mut self, key: &str, value: &T) {
+        self.fields.set_value(key, value)
+    }
+
+    /// Shorthand for [Fields::set_origin](Fields::set_origin).
+    pub fn set_origin(&mut self, origin: Vector3) {
+        self.fields.set_origin(origin)
+    }
+
+    /// Shorthand for [Fields::set_color](Fields::set_color).
+    pub fn set_color(&mut self, color: Color) {
+        self.fields.set_color(color)
+    }
+
+    /// Shorthand for [Fields::set_spawnflags](Fields::set_spawnflags).
+    pub fn set_spawnflags(&mut self, spawnflags: SpawnFlags) {
+        self.fields.set_spawnflags(spawnflags)
+    }
+
+    /// Shorthand for [Fields::set_orientation](Fields::set_orientation).
+    pub fn set_orientation(&mut self, orientation: Orientation) {
+        self.fields.set_orientation(orientation)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        std::borrow::Cow
+    };
+
+    fn fields(pairs: &[(&str, &str)]) -> Fields {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn vectors() {
+        let fields = fields(&[
+            ("origin", " -2704 1908\t50 "),
+            ("mins", "1 2"),
+            ("maxs", "1 2 3 4"),
+            ("size", "1 two 3")
+        ]);
+
+        assert_eq!(fields.origin(), Ok(Some(Vector3 { x: -2704., y: 1908., z: 50. })));
+        assert_eq!(fields.value::<Vector3>("nope"), Ok(None));
+        for key in ["mins", "maxs", "size"].iter() {
+            assert_eq!(fields.value::<Vector3>(key).unwrap_err().expected, "three numbers")
+        }
+    }
+
+    #[test]
+    fn error() {
+        let error = fields(&[("origin", "0 0 nan")]).origin().unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            r#"invalid value "0 0 nan" for key "origin", expected three numbers"#
+        )
+    }
+
+    #[test]
+    fn colors() {
+        let fields = fields(&[("_color", "255 128 0"), ("light", "1 -1 0")]);
+        let color = fields.color().unwrap().unwrap();
+
+        assert_eq!(color, Color { r: 255., g: 128., b: 0. });
+        assert_eq!(color.normalized(), Color { r: 1., g: 128. / 255., b: 0. });
+        assert_eq!(Color { r: 1., g: 0.93, b: 0.7 }.normalized().g, 0.93);
+        assert!(fields.value::<Color>("light").is_err())
+    }
+
+    #[test]
+    fn spawnflags() {
+        let mut fields = fields(&[("spawnflags", "1792"), ("broken", "-1")]);
+        let mut flags = fields.spawnflags().unwrap().unwrap();
+
+        assert!(flags.contains(SpawnFlags::NOT_EASY | SpawnFlags::NOT_MEDIUM | SpawnFlags::NOT_HARD));
+        assert!(!flags.contains(SpawnFlags::NOT_DEATHMATCH));
+        assert!(fields.value::<SpawnFlags>("broken").is_err());
+
+        flags.insert(SpawnFlags(1));
+        fields.set_spawnflags(flags);
+        assert_eq!(fields["spawnflags"], "1793")
+    }
+
+    #[test]
+    fn orientations() {
+        assert_eq!(fields(&[("angle", "-1")]).orientation(), Ok(Some(Orientation::UP)));
+        assert_eq!(fields(&[("angle", "-2")]).orientation(), Ok(Some(Orientation::DOWN)));
+        assert_eq!(
+            fields(&[("classname", "info_intermission"), ("angle", "315"), ("mangle", "10 20 0")]).orientation(),
+            Ok(Some(Orientation { pitch: 10., yaw: 20., roll: 0. }))
+        );
+        // a spotlight pointing west and slightly upwards
+        assert_eq!(
+            fields(&[("classname", "light"), ("mangle", "180 30 0")]).orientation(),
+            Ok(Some(Orientation { pitch: -30., yaw: 180., roll: 0. }))
+        );
+        assert_eq!(
+            fields(&[("angles", "0 90 5"), ("angle", "315")]).orientation(),
+            Ok(Some(Orientation { pitch: 0., yaw: 90., roll: 5. }))
+        );
+        assert_eq!(fields(&[]).orientation(), Ok(None));
+
+        let up = Orientation::UP.direction();
+        assert!(up.x.abs() < 1e-6 && up.y.abs() < 1e-6 && (up.z - 1.).abs() < 1e-6);
+    }
+
+    #[test]
+    fn set_orientation() {
+        let tilted = Orientation { pitch: 30., yaw: 90., roll: 0. };
+
+        let mut entity = fields(&[("classname", "info_null"), ("angle", "90")]);
+        entity.set_orientation(Orientation::DOWN);
+        assert_eq!(entity["angle"], "-2");
+        entity.set_orientation(tilted);
+        assert!(!entity.contains_key("angle"));
+        assert_eq!(entity["angles"], "30 90 0");
+        assert_eq!(entity.orientation(), Ok(Some(tilted)));
+
+        let mut empty = Fields::<String>::new();
+        empty.set_orientation(Orientation::from_angle(45.));
+        assert_eq!(empty["angle"], "45");
+
+        let mut light = fields(&[("classname", "light_fluoro"), ("mangle", "0 0 0")]);
+        light.set_orientation(Orientation::UP);
+        assert_eq!(light["mangle"], "0 90 0");
+        light.set_orientation(Orientation { pitch: 45., yaw: 270., roll: 0. });
+        assert_eq!(light["mangle"], "270 -45 0");
+        assert_eq!(light.orientation(), Ok(Some(Orientation { pitch: 45., yaw: 270., roll: 0. })));
+
+        let mut intermission = fields(&[("classname", "info_intermission"), ("mangle", "0 0 0")]);
+        intermission.set_orientation(Orientation::UP);
+        assert_eq!(intermission["mangle"], "-90 0 0");
+
+        assert_eq!(Orientation { yaw: -1., ..Orientation::default() }.to_angle(), None)
+    }
+
+    #[test]
+    fn entity() {
+        let mut entity = Entity::<(), Cow<str>> {
+            fields: Fields::new(),
+            brushes: Vec::new()
+        };
+        entity.set_origin(Vector3 { x: 1., y: -2.5, z: 0. });
+        entity.set_value("light", &300.);
+
+        assert_eq!(entity.fields["origin"], "1 -2.5 0");
+        assert_eq!(entity.value::<f32>("light"), Ok(Some(300.)));
+        assert_eq!(entity.value::<i32>("light"), Ok(Some(300)))
+    }
+}
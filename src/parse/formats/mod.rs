@@ -1,18 +1,27 @@
 pub mod shared;
 pub mod standard;
 pub mod valve;
+pub mod quake2;
+pub mod quake3;
+pub mod doom3;
+pub mod any;
+pub mod borrowed;
+pub mod keys;
+mod iter;
 
 use crate::parse::{
     common::parse,
-    formats::shared::{separator, maybe_sep_terminated},
+    formats::shared::{Escaping, separator, maybe_sep_terminated},
     core::{
         Parse,
         Input,
+        Error,
         ParseResult,
         nom::{
             multi::many1,
             error::ParseError,
             sequence::preceded,
+            sequence::pair,
             combinator::{map, opt}
         }
     }
@@ -20,18 +29,64 @@ use crate::parse::{
 
 pub use {
     valve::Valve,
-    standard::Standard
+    doom3::Doom3,
+    quake3::Quake3,
+    quake2::{Quake2, Quake2Valve},
+    standard::Standard,
+    any::{AnyFormat, AnyMap},
+    iter::EntityIter
 };
 
-/// Trait to define a map format by providing the entity type that it contains.
+/// Trait to define a map format by providing the entity type that it contains,
+/// as well as the type of the header preceding the entities.
 pub trait Format {
+    type Header: Header;
     type Entity;
 }
 
+/// Trait for entities whose fields can be decoded with a given [Escaping](Escaping),
+/// which is implemented for [Entity](shared::Entity). Their [Parse](Parse)
+/// implementation uses [Escaping::Raw](Escaping::Raw).
+pub trait ParseEscaped<'i, E = Error<'i>>: Parse<'i, E>
+where E: ParseError<Input<'i>> {
+    fn parse_escaped(escaping: Escaping, input: Input<'i>) -> ParseResult<'i, Self, E>;
+}
+
+/// The [Header](Format::Header) type of formats whose maps don't have a header.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NoHeader;
+
+/// Trait for the [Header](Format::Header)s of formats.
+pub trait Header {
+    /// Returns `true` if the header doesn't contain anything,
+    /// so that nothing has to be written for it.
+    fn is_empty(&self) -> bool;
+}
+
+impl Header for NoHeader {
+    fn is_empty(&self) -> bool {
+        true
+    }
+}
+
+impl <'i, E> Parse<'i, E> for NoHeader
+where E: ParseError<Input<'i>> {
+    fn parse(input: Input<'i>) -> ParseResult<'i, Self, E> {
+        Ok((input, NoHeader))
+    }
+}
+
 /// Representation of a Quake/Half-Life 1 map as a `Vec` of entities,
-/// where the entity type is defined by the format.
+/// where the entity and header types are defined by the format.
 #[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+    serialize = "F::Header: serde::Serialize, F::Entity: serde::Serialize",
+    deserialize = "F::Header: serde::Deserialize<'de>, F::Entity: serde::Deserialize<'de>"
+)))]
 pub struct Map<F: Format> {
+    pub header: F::Header,
     pub entities: Vec<F::Entity>,
 }
 
@@ -39,15 +94,43 @@ impl <'i, E, F> Parse<'i, E> for Map<F>
 where
     E: ParseError<Input<'i>> + Clone,
     F: Format,
+    F::Header: Parse<'i, E>,
     F::Entity: Parse<'i, E>
 {
-    fn parse(input: Input<'i>) -> ParseResult<Self, E> {
-        preceded(
-            opt(separator),
-            map(
-                many1(maybe_sep_terminated(parse)),
-                |entities| Map { entities }
-            )
-        )(input)
+    fn parse(input: Input<'i>) -> ParseResult<'i, Self, E> {
+        map_with(parse)(input)
     }
 }
+
+impl <F: Format> Map<F> {
+    /// Returns a parser for maps whose entity fields are decoded with the given
+    /// [Escaping](Escaping). The [Parse](Parse) implementation uses
+    /// [Escaping::Raw](Escaping::Raw).
+    pub fn parser<'i, E>(escaping: Escaping) -> impl Fn(Input<'i>) -> ParseResult<'i, Self, E>
+    where
+        E: ParseError<Input<'i>> + Clone,
+        F::Header: Parse<'i, E>,
+        F::Entity: ParseEscaped<'i, E>
+    {
+        map_with(move |input| F::Entity::parse_escaped(escaping, input))
+    }
+}
+
+fn map_with<'i, F, P, E>(entity: P) -> impl Fn(Input<'i>) -> ParseResult<'i, Map<F>, E>
+where
+    E: ParseError<Input<'i>> + Clone,
+    F: Format,
+    F::Header: Parse<'i, E>,
+    P: Fn(Input<'i>) -> ParseResult<'i, F::Entity, E>
+{
+    preceded(
+        opt(separator),
+        map(
+            pair(
+                maybe_sep_terminated(parse),
+                many1(maybe_sep_terminated(entity))
+            ),
+            |(header, entities)| Map { header, entities }
+        )
+    )
+}
@@ -0,0 +1,239 @@
+pub mod standard;
+pub mod valve;
+
+use {
+    std::ops::{Deref, DerefMut},
+    crate::parse::{
+        common::{bitflags, fields, parse, integer},
+        formats::shared::separator,
+        core::{
+            Parse,
+            Input,
+            ParseResult,
+            nom::{
+                error::ParseError,
+                sequence::preceded,
+                combinator::{map, opt}
+            }
+        }
+    }
+};
+
+pub use {
+    valve::Quake2Valve,
+    standard::Quake2
+};
+
+/// A Quake 2 texture alignment, which extends the alignment of the
+/// format it is based on with [SurfaceAttributes](SurfaceAttributes).
+/// It dereferences to the base alignment, so its fields can be
+/// accessed directly.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TextureAlignment<TA> {
+    pub base: TA,
+    pub attributes: SurfaceAttributes
+}
+
+impl <TA> Deref for TextureAlignment<TA> {
+    type Target = TA;
+
+    fn deref(&self) -> &Self::Target {
+        &self.base
+    }
+}
+
+impl <TA> DerefMut for TextureAlignment<TA> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.base
+    }
+}
+
+impl <'i, E, TA> Parse<'i, E> for TextureAlignment<TA>
+where
+    E: ParseError<Input<'i>> + Clone,
+    TA: Parse<'i, E>
+{
+    fn parse(input: Input<'i>) -> ParseResult<'i, Self, E> {
+        fields!(TextureAlignment:
+            base = parse,
+            attributes = parse
+        )(input)
+    }
+}
+
+/// The three integers following the texture alignment in
+/// a Quake 2 map, for example the `134217728 4 0` in:
+/// ```plain
+/// ( 0 0 0 ) ( 0 64 0 ) ( 64 0 0 ) e1u1/floor1_3 0 0 0 1 1 134217728 4 0
+/// ```
+/// Like the compilers, the parser treats missing integers as 0, since
+/// some editors leave them out, usually when all of them are 0.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SurfaceAttributes {
+    pub contents: Contents,
+    pub flags: SurfaceFlags,
+    pub value: i32
+}
+
+impl <'i, E> Parse<'i, E> for SurfaceAttributes
+where E: ParseError<Input<'i>> + Clone {
+    fn parse(input: Input<'i>) -> ParseResult<'i, Self, E> {
+        fields!(SurfaceAttributes:
+            contents = optional(parse),
+            flags = optional(parse),
+            value = optional(integer)
+        )(input)
+    }
+}
+
+/// Runs `parser` after a separator, returning the default value
+/// instead of failing if what follows doesn't match.
+fn optional<'i, P, O, E>(parser: P) -> impl Fn(Input<'i>) -> ParseResult<'i, O, E>
+where
+    P: Fn(Input<'i>) -> ParseResult<'i, O, E>,
+    O: Default,
+    E: ParseError<Input<'i>> + Clone
+{
+    map(opt(preceded(separator, parser)), Option::unwrap_or_default)
+}
+
+bitflags! {
+    /// The content flags of a Quake 2 brush face, which determine
+    /// what the brush is made of.
+    pub struct Contents(u32) {
+        const SOLID = 0x1;
+        const WINDOW = 0x2;
+        const AUX = 0x4;
+        const LAVA = 0x8;
+        const SLIME = 0x10;
+        const WATER = 0x20;
+        const MIST = 0x40;
+        const AREAPORTAL = 0x8000;
+        const PLAYERCLIP = 0x10000;
+        const MONSTERCLIP = 0x20000;
+        const CURRENT_0 = 0x40000;
+        const CURRENT_90 = 0x80000;
+        const CURRENT_180 = 0x100000;
+        const CURRENT_270 = 0x200000;
+        const CURRENT_UP = 0x400000;
+        const CURRENT_DOWN = 0x800000;
+        const ORIGIN = 0x1000000;
+        const MONSTER = 0x2000000;
+        const DEADMONSTER = 0x4000000;
+        const DETAIL = 0x8000000;
+        const TRANSLUCENT = 0x10000000;
+        const LADDER = 0x20000000;
+    }
+}
+
+impl <'i, E> Parse<'i, E> for Contents
+where E: ParseError<Input<'i>> {
+    fn parse(input: Input<'i>) -> ParseResult<'i, Self, E> {
+        map(integer, Contents)(input)
+    }
+}
+
+bitflags! {
+    /// The surface flags of a Quake 2 brush face, which determine
+    /// how the face is rendered and lit.
+    pub struct SurfaceFlags(u32) {
+        const LIGHT = 0x1;
+        const SLICK = 0x2;
+        const SKY = 0x4;
+        const WARP = 0x8;
+        const TRANS33 = 0x10;
+        const TRANS66 = 0x20;
+        const FLOWING = 0x40;
+        const NODRAW = 0x80;
+        const HINT = 0x100;
+        const SKIP = 0x200;
+    }
+}
+
+impl <'i, E> Parse<'i, E> for SurfaceFlags
+where E: ParseError<Input<'i>> {
+    fn parse(input: Input<'i>) -> ParseResult<'i, Self, E> {
+        map(integer, SurfaceFlags)(input)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        crate::parse::common::test::expected
+    };
+
+    #[test]
+    fn surface_attributes() {
+        assert_eq!(
+            parse(r"134217728 5 -10"),
+            expected(SurfaceAttributes {
+                contents: Contents::DETAIL,
+                flags: SurfaceFlags::LIGHT | SurfaceFlags::SKY,
+                value: -10
+            })
+        )
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn surface_attributes_serde() {
+        let attributes = SurfaceAttributes {
+            contents: Contents::DETAIL,
+            flags: SurfaceFlags::LIGHT | SurfaceFlags::SKY,
+            value: -10
+        };
+        let json = serde_json::to_string(&attributes).unwrap();
+
+        assert_eq!(json, r#"{"contents":134217728,"flags":5,"value":-10}"#);
+        assert_eq!(serde_json::from_str::<SurfaceAttributes>(&json).unwrap(), attributes)
+    }
+
+    #[test]
+    fn surface_attributes_missing() {
+        assert_eq!(
+            parse(r"8 1"),
+            expected(SurfaceAttributes {
+                contents: Contents::LAVA,
+                flags: SurfaceFlags::LIGHT,
+                value: 0
+            })
+        );
+        assert_eq!(
+            parse::<SurfaceAttributes, crate::parse::core::Error>("\n}"),
+            Ok(("\n}", SurfaceAttributes::default()))
+        )
+    }
+
+    #[test]
+    fn surface_attributes_weird() {
+        assert_eq!(
+            parse(r"134217728 // detail
+            5
+            // light and sky
+            -10"),
+            expected(SurfaceAttributes {
+                contents: Contents::DETAIL,
+                flags: SurfaceFlags::LIGHT | SurfaceFlags::SKY,
+                value: -10
+            })
+        )
+    }
+
+    #[test]
+    fn flags() {
+        let mut flags = SurfaceFlags::LIGHT | SurfaceFlags::WARP;
+
+        assert!(flags.contains(SurfaceFlags::WARP));
+        assert!(!flags.contains(SurfaceFlags::WARP | SurfaceFlags::SKY));
+        assert!(flags.intersects(SurfaceFlags::WARP | SurfaceFlags::SKY));
+
+        flags.remove(SurfaceFlags::LIGHT);
+        flags.set(SurfaceFlags::NODRAW, true);
+
+        assert_eq!(flags, SurfaceFlags(0x88));
+    }
+}
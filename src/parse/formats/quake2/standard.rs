@@ -0,0 +1,112 @@
+use crate::parse::formats::{
+    self,
+    Format,
+    NoHeader,
+    shared,
+    quake2
+};
+
+/// The map format of Quake 2, which is based on the [standard format](formats::Standard).
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Quake2;
+
+impl Format for Quake2 {
+    type Header = NoHeader;
+    type Entity = Entity;
+}
+
+/// The Quake 2 format's Entity type.
+pub type Entity = shared::Entity<Brush>;
+
+/// The Quake 2 format's Brush type.
+pub type Brush = shared::Brush<TextureAlignment>;
+
+/// The Quake 2 format's Plane type.
+pub type Plane = shared::Plane<TextureAlignment>;
+
+/// The Quake 2 format's Texture type.
+pub type Texture = shared::Texture<TextureAlignment>;
+
+/// The Quake 2 format's texture alignment.
+pub type TextureAlignment = quake2::TextureAlignment<formats::standard::TextureAlignment>;
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        crate::parse::{
+            common::{parse, test::expected},
+            formats::{
+                quake2::{Contents, SurfaceFlags, SurfaceAttributes},
+                standard::Vector2
+            }
+        }
+    };
+
+    #[test]
+    fn texture_alignment() {
+        assert_eq!(
+            parse(r"16 -8 90 0.5 1 134217728 1 300"),
+            expected(TextureAlignment {
+                base: formats::standard::TextureAlignment {
+                    offset: Vector2 { x: 16., y: -8. },
+                    rotation: 90.,
+                    scale: Vector2 { x: 0.5, y: 1. }
+                },
+                attributes: SurfaceAttributes {
+                    contents: Contents::DETAIL,
+                    flags: SurfaceFlags::LIGHT,
+                    value: 300
+                }
+            })
+        )
+    }
+
+    #[test]
+    fn standard_texture_alignment() {
+        let brush = parse::<Brush, crate::parse::core::Error>(
+            r"{
+( 0 0 0 ) ( 0 64 0 ) ( 64 0 0 ) e1u1/floor1_3 0 0 0 1 1
+( 0 0 64 ) ( 64 0 64 ) ( 0 64 64 ) e1u1/floor1_3 0 0 0 1 1 0 0 0
+}"
+        );
+
+        let (rest, brush) = brush.unwrap();
+        assert_eq!(rest, "");
+        assert_eq!(brush.planes.len(), 2);
+        assert_eq!(brush.planes[0].texture.alignment.attributes, SurfaceAttributes::default());
+        assert_eq!(brush.planes[0].texture.alignment, brush.planes[1].texture.alignment)
+    }
+
+    #[cfg(feature = "display")]
+    #[test]
+    fn roundtrip() {
+        let map = crate::Map::<Quake2> {
+            header: NoHeader,
+            entities: vec![Entity {
+                fields: crate::formats::shared::Fields(std::iter::once(("k".into(), "v".into())).collect()),
+                brushes: vec![Brush {
+                    planes: vec![Plane {
+                        texture: Texture {
+                            name: "e1u1/floor1_3".into(),
+                            alignment: TextureAlignment {
+                                attributes: SurfaceAttributes {
+                                    contents: Contents::WATER | Contents::TRANSLUCENT,
+                                    flags: SurfaceFlags::WARP | SurfaceFlags::TRANS33,
+                                    value: 0
+                                },
+                                ..<_>::default()
+                            }
+                        },
+                        ..<_>::default()
+                    }]
+                }]
+            }]
+        };
+        let string = map.to_string();
+        assert_eq!(
+            expected(map),
+            parse(&string)
+        )
+    }
+}
@@ -0,0 +1,137 @@
+use crate::parse::formats::{
+    self,
+    Format,
+    NoHeader,
+    shared,
+    quake2
+};
+
+/// The map format of Quake 2 with texture alignments in the [Valve format](formats::Valve),
+/// as written by editors like TrenchBroom.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Quake2Valve;
+
+impl Format for Quake2Valve {
+    type Header = NoHeader;
+    type Entity = Entity;
+}
+
+/// The Quake 2 Valve format's Entity type.
+pub type Entity = shared::Entity<Brush>;
+
+/// The Quake 2 Valve format's Brush type.
+pub type Brush = shared::Brush<TextureAlignment>;
+
+/// The Quake 2 Valve format's Plane type.
+pub type Plane = shared::Plane<TextureAlignment>;
+
+/// The Quake 2 Valve format's Texture type.
+pub type Texture = shared::Texture<TextureAlignment>;
+
+/// The Quake 2 Valve format's texture alignment.
+pub type TextureAlignment = quake2::TextureAlignment<formats::valve::TextureAlignment>;
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        crate::parse::{
+            common::{parse, test::expected},
+            formats::{
+                quake2::{Contents, SurfaceFlags, SurfaceAttributes},
+                shared::Vector3,
+                valve::{Axes, Axis, Scale}
+            }
+        }
+    };
+
+    #[test]
+    fn texture_alignment() {
+        assert_eq!(
+            parse(r"[ 1 0 0 16 ] [ 0 -1 0 -8 ] 0 0.5 1 0 4 0"),
+            expected(TextureAlignment {
+                base: formats::valve::TextureAlignment {
+                    axes: Axes {
+                        u: Axis {
+                            normal: Vector3 { x: 1., y: 0., z: 0. },
+                            offset: 16.
+                        },
+                        v: Axis {
+                            normal: Vector3 { x: 0., y: -1., z: 0. },
+                            offset: -8.
+                        }
+                    },
+                    rotation: 0.,
+                    scale: Scale { u: 0.5, v: 1. }
+                },
+                attributes: SurfaceAttributes {
+                    contents: Contents::empty(),
+                    flags: SurfaceFlags::SKY,
+                    value: 0
+                }
+            })
+        )
+    }
+
+    #[test]
+    fn texture_alignment_weird() {
+        assert_eq!(
+            parse(r"[1 0 0 16][0 -1 0 -8]0 0.5 1
+            // sky
+            0 4 0"),
+            expected(TextureAlignment {
+                base: formats::valve::TextureAlignment {
+                    axes: Axes {
+                        u: Axis {
+                            normal: Vector3 { x: 1., y: 0., z: 0. },
+                            offset: 16.
+                        },
+                        v: Axis {
+                            normal: Vector3 { x: 0., y: -1., z: 0. },
+                            offset: -8.
+                        }
+                    },
+                    rotation: 0.,
+                    scale: Scale { u: 0.5, v: 1. }
+                },
+                attributes: SurfaceAttributes {
+                    contents: Contents::empty(),
+                    flags: SurfaceFlags::SKY,
+                    value: 0
+                }
+            })
+        )
+    }
+
+    #[cfg(feature = "display")]
+    #[test]
+    fn roundtrip() {
+        let map = crate::Map::<Quake2Valve> {
+            header: NoHeader,
+            entities: vec![Entity {
+                fields: crate::formats::shared::Fields(std::iter::once(("k".into(), "v".into())).collect()),
+                brushes: vec![Brush {
+                    planes: vec![Plane {
+                        texture: Texture {
+                            name: "e1u1/sky1".into(),
+                            alignment: TextureAlignment {
+                                attributes: SurfaceAttributes {
+                                    contents: Contents::empty(),
+                                    flags: SurfaceFlags::SKY | SurfaceFlags::NODRAW,
+                                    value: 0
+                                },
+                                ..<_>::default()
+                            }
+                        },
+                        ..<_>::default()
+                    }]
+                }]
+            }]
+        };
+        let string = map.to_string();
+        assert_eq!(
+            expected(map),
+            parse(&string)
+        )
+    }
+}
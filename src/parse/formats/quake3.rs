@@ -0,0 +1,588 @@
+use {
+    std::ops::{Deref, DerefMut},
+    crate::parse::{
+        common::{fields, parse, integer},
+        formats::{
+            Format,
+            NoHeader,
+            standard::Vector2,
+            shared::{
+                self,
+                Vector3,
+                points,
+                braced,
+                texture_name,
+                parenthesized,
+                sep_terminated,
+                maybe_sep_terminated
+            }
+        },
+        core::{
+            Parse,
+            Input,
+            ParseResult,
+            nom::{
+                bytes::tag,
+                branch::alt,
+                multi::many0,
+                error::ParseError,
+                sequence::{pair, preceded, tuple},
+                combinator::{map, verify}
+            }
+        }
+    }
+};
+
+/// The brush primitives format used by id Tech 3 editors
+/// like GtkRadiant and NetRadiant.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Quake3;
+
+impl Format for Quake3 {
+    type Header = NoHeader;
+    type Entity = Entity;
+}
+
+/// The Quake 3 format's Entity type. Its `brushes` contain
+/// both brushes and patches.
+pub type Entity = shared::Entity<Primitive>;
+
+/// The Quake 3 format's Plane type.
+pub type Plane = shared::Plane<TextureAlignment>;
+
+/// The Quake 3 format's Texture type.
+pub type Texture = shared::Texture<TextureAlignment>;
+
+/// The Quake 3 format's Brush type, which wraps a [Brush](shared::Brush)
+/// in a `brushDef` block. In a map file, it usually looks something like this:
+/// ```plain
+/// {
+/// brushDef
+/// {
+/// ( 64 0 0 ) ( 64 64 0 ) ( 64 0 64 ) ( ( 0.015625 0 0 ) ( 0 0.015625 0 ) ) common/caulk 134217728 0 0
+/// }
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Brush(pub shared::Brush<TextureAlignment>);
+
+impl Brush {
+    pub fn into_inner(self) -> shared::Brush<TextureAlignment> {
+        self.0
+    }
+}
+
+impl Deref for Brush {
+    type Target = shared::Brush<TextureAlignment>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for Brush {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl <'i, E> Parse<'i, E> for Brush
+where E: ParseError<Input<'i>> + Clone {
+    fn parse(input: Input<'i>) -> ParseResult<'i, Self, E> {
+        map(
+            braced(
+                preceded(
+                    maybe_sep_terminated(tag("brushDef")),
+                    braced(many0(maybe_sep_terminated(plane)))
+                )
+            ),
+            |planes| Brush(shared::Brush { planes })
+        )(input)
+    }
+}
+
+fn plane<'i, E>(input: Input<'i>) -> ParseResult<'i, Plane, E>
+where E: ParseError<Input<'i>> + Clone {
+    map(
+        pair(maybe_sep_terminated(points), texture),
+        |(points, texture)| Plane { points, texture }
+    )(input)
+}
+
+/// Parses the part of a plane following its points, which
+/// is the texture matrix, the texture name and the flags.
+pub(crate) fn texture<'i, E>(input: Input<'i>) -> ParseResult<'i, Texture, E>
+where E: ParseError<Input<'i>> + Clone {
+    map(
+        tuple((
+            maybe_sep_terminated(parse),
+            sep_terminated(texture_name),
+            sep_terminated(integer),
+            sep_terminated(integer),
+            integer
+        )),
+        |(matrix, name, content_flags, surface_flags, value)| Texture {
+            name,
+            alignment: TextureAlignment {
+                matrix,
+                content_flags,
+                surface_flags,
+                value
+            }
+        }
+    )(input)
+}
+
+/// A primitive of a Quake 3 [Entity](Entity), which is either a brush or a patch.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Primitive {
+    Brush(Brush),
+    Patch(Patch)
+}
+
+impl <'i, E> Parse<'i, E> for Primitive
+where E: ParseError<Input<'i>> + Clone {
+    fn parse(input: Input<'i>) -> ParseResult<'i, Self, E> {
+        alt((
+            map(parse, Primitive::Brush),
+            map(parse, Primitive::Patch)
+        ))(input)
+    }
+}
+
+/// Representation of a curved surface described by a grid of control
+/// points that form quadratic Bézier patches. In a map file, it usually
+/// looks something like this:
+/// ```plain
+/// {
+/// patchDef2
+/// {
+/// base_wall/metal
+/// ( 3 3 0 0 0 )
+/// (
+/// ( ( -64 -64 0 0 0 ) ( -64 0 32 0 0.5 ) ( -64 64 0 0 1 ) )
+/// ( ( 0 -64 32 0.5 0 ) ( 0 0 64 0.5 0.5 ) ( 0 64 32 0.5 1 ) )
+/// ( ( 64 -64 0 1 0 ) ( 64 0 32 1 0.5 ) ( 64 64 0 1 1 ) )
+/// )
+/// }
+/// }
+/// ```
+/// The control points are stored like in the map file, as a list of
+/// `width` columns containing `height` points each.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Patch {
+    pub texture: String,
+    pub content_flags: u32,
+    pub surface_flags: u32,
+    pub value: i32,
+    pub control_points: Vec<Vec<ControlPoint>>
+}
+
+impl Patch {
+    /// The number of columns of control points.
+    pub fn width(&self) -> usize {
+        self.control_points.len()
+    }
+
+    /// The number of control points in each column.
+    pub fn height(&self) -> usize {
+        self.control_points
+            .first()
+            .map_or(0, Vec::len)
+    }
+}
+
+impl <'i, E> Parse<'i, E> for Patch
+where E: ParseError<Input<'i>> + Clone {
+    fn parse(input: Input<'i>) -> ParseResult<'i, Self, E> {
+        map(patch, |(patch, _)| patch)(input)
+    }
+}
+
+/// Parses a patch along with the part of the input its texture name was parsed from.
+pub(crate) fn patch<'i, E>(input: Input<'i>) -> ParseResult<'i, (Patch, Input<'i>), E>
+where E: ParseError<Input<'i>> + Clone {
+    map(
+        braced(
+            preceded(
+                maybe_sep_terminated(tag("patchDef2")),
+                braced(
+                    verify(
+                        tuple((
+                            sep_terminated(texture_name::<Input, _>),
+                            maybe_sep_terminated(
+                                parenthesized(
+                                    tuple((
+                                        sep_terminated(integer::<usize, _>),
+                                        sep_terminated(integer::<usize, _>),
+                                        sep_terminated(integer),
+                                        sep_terminated(integer),
+                                        integer
+                                    ))
+                                )
+                            ),
+                            control_points
+                        )),
+                        |(_, (width, height, ..), control_points): &(_, _, Vec<Vec<_>>)|
+                            has_size(control_points, *width, *height)
+                    )
+                )
+            )
+        ),
+        |(texture, (_, _, content_flags, surface_flags, value), control_points)| (
+            Patch {
+                texture: texture.into(),
+                content_flags,
+                surface_flags,
+                value,
+                control_points
+            },
+            texture
+        )
+    )(input)
+}
+
+pub(super) fn control_points<'i, E>(input: Input<'i>) -> ParseResult<'i, Vec<Vec<ControlPoint>>, E>
+where E: ParseError<Input<'i>> + Clone {
+    parenthesized(
+        many0(maybe_sep_terminated(
+            parenthesized(
+                many0(maybe_sep_terminated(
+                    parenthesized(parse)
+                ))
+            )
+        ))
+    )(input)
+}
+
+pub(super) fn has_size(control_points: &[Vec<ControlPoint>], width: usize, height: usize) -> bool {
+    control_points.len() == width
+        && control_points.iter().all(|column| column.len() == height)
+}
+
+/// A control point of a [Patch](Patch), consisting of
+/// its position and texture coordinates.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ControlPoint {
+    pub position: Vector3,
+    pub uv: Vector2
+}
+
+impl <'i, E> Parse<'i, E> for ControlPoint
+where E: ParseError<Input<'i>> + Clone {
+    fn parse(input: Input<'i>) -> ParseResult<'i, Self, E> {
+        fields!(ControlPoint:
+            position = sep_terminated(parse),
+            uv = parse
+        )(input)
+    }
+}
+
+/// Representation of the Quake 3 format's texture alignment. Unlike the
+/// other formats, the alignment precedes the texture name in a map file,
+/// while the flags follow it.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TextureAlignment {
+    pub matrix: TextureMatrix,
+    pub content_flags: u32,
+    pub surface_flags: u32,
+    pub value: i32
+}
+
+/// The 2x3 matrix that maps a point projected onto a plane
+/// to texture coordinates in the brush primitives format.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TextureMatrix {
+    pub u: Vector3,
+    pub v: Vector3
+}
+
+impl <'i, E> Parse<'i, E> for TextureMatrix
+where E: ParseError<Input<'i>> + Clone {
+    fn parse(input: Input<'i>) -> ParseResult<'i, Self, E> {
+        parenthesized(
+            fields!(TextureMatrix:
+                u = maybe_sep_terminated(parenthesized(parse)),
+                v = parenthesized(parse)
+            )
+        )(input)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        crate::parse::common::test::expected
+    };
+
+    #[test]
+    fn texture_matrix() {
+        assert_eq!(
+            parse(r"( ( 0.015625 0 -4 ) ( 0 0.03125 8.5 ) )"),
+            expected(TextureMatrix {
+                u: Vector3 { x: 0.015625, y: 0., z: -4. },
+                v: Vector3 { x: 0., y: 0.03125, z: 8.5 }
+            })
+        )
+    }
+
+    #[test]
+    fn texture_matrix_weird() {
+        assert_eq!(
+            parse(r"((0.015625 0 -4)
+            // rows
+            (0 0.03125
+            8.5))"),
+            expected(TextureMatrix {
+                u: Vector3 { x: 0.015625, y: 0., z: -4. },
+                v: Vector3 { x: 0., y: 0.03125, z: 8.5 }
+            })
+        )
+    }
+
+    #[test]
+    fn brush() {
+        assert_eq!(
+            parse(
+                r"{
+brushDef
+{
+( 64 0 0 ) ( 64 64 0 ) ( 64 0 64 ) ( ( 0.015625 0 0 ) ( 0 0.015625 0 ) ) common/caulk 134217728 0 0
+( 0 0 0 ) ( 0 0 64 ) ( 0 64 0 ) ( ( 0.03125 0 2 ) ( 0 0.03125 -1 ) ) base_wall/metal 0 4 -1
+}
+}"
+            ),
+            expected(Brush(shared::Brush {
+                planes: vec![
+                    Plane {
+                        points: [
+                            Vector3 { x: 64., y: 0., z: 0. },
+                            Vector3 { x: 64., y: 64., z: 0. },
+                            Vector3 { x: 64., y: 0., z: 64. }
+                        ],
+                        texture: Texture {
+                            name: "common/caulk".into(),
+                            alignment: TextureAlignment {
+                                matrix: TextureMatrix {
+                                    u: Vector3 { x: 0.015625, y: 0., z: 0. },
+                                    v: Vector3 { x: 0., y: 0.015625, z: 0. }
+                                },
+                                content_flags: 134217728,
+                                surface_flags: 0,
+                                value: 0
+                            }
+                        }
+                    },
+                    Plane {
+                        points: [
+                            Vector3 { x: 0., y: 0., z: 0. },
+                            Vector3 { x: 0., y: 0., z: 64. },
+                            Vector3 { x: 0., y: 64., z: 0. }
+                        ],
+                        texture: Texture {
+                            name: "base_wall/metal".into(),
+                            alignment: TextureAlignment {
+                                matrix: TextureMatrix {
+                                    u: Vector3 { x: 0.03125, y: 0., z: 2. },
+                                    v: Vector3 { x: 0., y: 0.03125, z: -1. }
+                                },
+                                content_flags: 0,
+                                surface_flags: 4,
+                                value: -1
+                            }
+                        }
+                    }
+                ]
+            }))
+        )
+    }
+
+    #[test]
+    fn brush_weird() {
+        assert_eq!(
+            parse(
+                r"{brushDef // primitives!
+                {(64 0 0)(64 64 0)(64 0 64)((0.015625 0 0)(0 0.015625 0))
+                common/caulk
+                134217728 0
+                // 🦀
+                0}}"
+            ),
+            expected(Brush(shared::Brush {
+                planes: vec![
+                    Plane {
+                        points: [
+                            Vector3 { x: 64., y: 0., z: 0. },
+                            Vector3 { x: 64., y: 64., z: 0. },
+                            Vector3 { x: 64., y: 0., z: 64. }
+                        ],
+                        texture: Texture {
+                            name: "common/caulk".into(),
+                            alignment: TextureAlignment {
+                                matrix: TextureMatrix {
+                                    u: Vector3 { x: 0.015625, y: 0., z: 0. },
+                                    v: Vector3 { x: 0., y: 0.015625, z: 0. }
+                                },
+                                content_flags: 134217728,
+                                surface_flags: 0,
+                                value: 0
+                            }
+                        }
+                    }
+                ]
+            }))
+        )
+    }
+
+    #[test]
+    fn brush_without_brush_def() {
+        assert!(
+            parse::<Brush, crate::parse::core::Error>(
+                r"{
+( 64 0 0 ) ( 64 64 0 ) ( 64 0 64 ) ( ( 0.015625 0 0 ) ( 0 0.015625 0 ) ) common/caulk 134217728 0 0
+}"
+            ).is_err()
+        )
+    }
+
+    fn control_point(x: f32, y: f32, z: f32, u: f32, v: f32) -> ControlPoint {
+        ControlPoint {
+            position: Vector3 { x, y, z },
+            uv: Vector2 { x: u, y: v }
+        }
+    }
+
+    #[test]
+    fn patch() {
+        assert_eq!(
+            parse(
+                r"{
+patchDef2
+{
+base_wall/metal
+( 3 3 0 0 0 )
+(
+( ( -64 -64 0 0 0 ) ( -64 0 32 0 0.5 ) ( -64 64 0 0 1 ) )
+( ( 0 -64 32 0.5 0 ) ( 0 0 64 0.5 0.5 ) ( 0 64 32 0.5 1 ) )
+( ( 64 -64 0 1 0 ) ( 64 0 32 1 0.5 ) ( 64 64 0 1 1 ) )
+)
+}
+}"
+            ),
+            expected(Patch {
+                texture: "base_wall/metal".into(),
+                control_points: vec![
+                    vec![
+                        control_point(-64., -64., 0., 0., 0.),
+                        control_point(-64., 0., 32., 0., 0.5),
+                        control_point(-64., 64., 0., 0., 1.)
+                    ],
+                    vec![
+                        control_point(0., -64., 32., 0.5, 0.),
+                        control_point(0., 0., 64., 0.5, 0.5),
+                        control_point(0., 64., 32., 0.5, 1.)
+                    ],
+                    vec![
+                        control_point(64., -64., 0., 1., 0.),
+                        control_point(64., 0., 32., 1., 0.5),
+                        control_point(64., 64., 0., 1., 1.)
+                    ]
+                ],
+                ..<_>::default()
+            })
+        )
+    }
+
+    #[test]
+    fn patch_size_mismatch() {
+        assert!(
+            parse::<Patch, crate::parse::core::Error>(
+                r"{
+patchDef2
+{
+base_wall/metal
+( 3 1 0 0 0 )
+(
+( ( -64 -64 0 0 0 ) )
+( ( 0 -64 32 0.5 0 ) )
+)
+}
+}"
+            ).is_err()
+        )
+    }
+
+    #[test]
+    fn entity() {
+        let entity = parse::<Entity, crate::parse::core::Error>(
+            r#"{
+"classname" "worldspawn"
+// brush 0
+{
+brushDef
+{
+( 64 0 0 ) ( 64 64 0 ) ( 64 0 64 ) ( ( 0.015625 0 0 ) ( 0 0.015625 0 ) ) common/caulk 134217728 0 0
+}
+}
+// patch 0
+{
+patchDef2
+{
+base_wall/metal
+( 3 3 0 0 0 )
+(
+( ( -64 -64 0 0 0 ) ( -64 0 32 0 0.5 ) ( -64 64 0 0 1 ) )
+( ( 0 -64 32 0.5 0 ) ( 0 0 64 0.5 0.5 ) ( 0 64 32 0.5 1 ) )
+( ( 64 -64 0 1 0 ) ( 64 0 32 1 0.5 ) ( 64 64 0 1 1 ) )
+)
+}
+}
+}"#
+        ).unwrap().1;
+
+        assert!(matches!(entity.brushes[0], Primitive::Brush(_)));
+        assert!(matches!(entity.brushes[1], Primitive::Patch(_)));
+    }
+
+    #[cfg(feature = "display")]
+    #[test]
+    fn roundtrip() {
+        let map = crate::Map::<Quake3> {
+            header: NoHeader,
+            entities: vec![Entity {
+                fields: crate::formats::shared::Fields(std::iter::once(("k".into(), "v".into())).collect()),
+                brushes: vec![
+                    Primitive::Brush(Brush(shared::Brush {
+                        planes: vec![Plane {
+                            texture: Texture {
+                                name: "common/caulk".into(),
+                                alignment: TextureAlignment {
+                                    content_flags: 134217728,
+                                    ..<_>::default()
+                                }
+                            },
+                            ..<_>::default()
+                        }]
+                    })),
+                    Primitive::Patch(Patch {
+                        texture: "base_wall/metal".into(),
+                        control_points: vec![vec![<_>::default(); 3]; 3],
+                        ..<_>::default()
+                    })
+                ]
+            }]
+        };
+        let string = map.to_string();
+        assert_eq!(
+            expected(map),
+            parse(&string)
+        )
+    }
+}
@@ -1,10 +1,12 @@
 use {
     std::{
-        collections::HashMap,
-        ops::{Deref, DerefMut}
+        borrow::{Borrow, Cow},
+        iter::FromIterator,
+        ops::Index
     },
     crate::parse::{
-        common::{fields, parse, quoted_string, many_fixed},
+        common::{fields, parse, quoted_string, verbatim_string, many_fixed},
+        formats::ParseEscaped,
         core::{
             Parse,
             Input,
@@ -14,8 +16,8 @@ use {
                 number::float,
                 error::ParseError,
                 bytes::{tag, take_till},
-                multi::{many0, fold_many0},
-                combinator::{map, opt, iterator, recognize},
+                multi::many0,
+                combinator::{cut, map, opt, iterator, recognize},
                 sequence::{pair, delimited, terminated, preceded},
                 character::{char, multispace1, line_ending, not_line_ending}
             }
@@ -23,7 +25,7 @@ use {
     }
 };
 
-pub(crate) fn separator<'i, E>(input: Input<'i>) -> ParseResult<Input<'i>, E>
+pub(crate) fn separator<'i, E>(input: Input<'i>) -> ParseResult<'i, Input<'i>, E>
 where E: ParseError<Input<'i>> + Clone {
     recognize(
         |input| {
@@ -40,25 +42,79 @@ where E: ParseError<Input<'i>> + Clone {
     )(input)
 }
 
-pub(crate) fn sep_terminated<'i, F, O, E>(parsed: F) -> impl Fn(Input<'i>) -> ParseResult<O, E>
+pub(crate) fn sep_terminated<'i, F, O, E>(parsed: F) -> impl Fn(Input<'i>) -> ParseResult<'i, O, E>
 where
-    F: Fn(Input<'i>) -> ParseResult<O, E>,
+    F: Fn(Input<'i>) -> ParseResult<'i, O, E>,
     E: ParseError<Input<'i>> + Clone
 {
     terminated(parsed, separator)
 }
 
-pub(crate) fn maybe_sep_terminated<'i, F, O, E>(parsed: F) -> impl Fn(Input<'i>) -> ParseResult<O, E>
+pub(crate) fn maybe_sep_terminated<'i, F, O, E>(parsed: F) -> impl Fn(Input<'i>) -> ParseResult<'i, O, E>
     where
-        F: Fn(Input<'i>) -> ParseResult<O, E>,
+        F: Fn(Input<'i>) -> ParseResult<'i, O, E>,
         E: ParseError<Input<'i>> + Clone
 {
     terminated(parsed, opt(separator))
 }
 
-/// A wrapper around a `HashMap<String, String>` representing
-/// an entity's key/value pairs. In a map file, they usually look
-/// something like this:
+pub(crate) fn delimited_by<'i, F, O, E>(open: char, parsed: F, close: char) -> impl Fn(Input<'i>) -> ParseResult<'i, O, E>
+where
+    F: Fn(Input<'i>) -> ParseResult<'i, O, E>,
+    E: ParseError<Input<'i>> + Clone
+{
+    delimited(
+        pair(char(open), opt(separator)),
+        parsed,
+        pair(opt(separator), char(close))
+    )
+}
+
+pub(crate) fn braced<'i, F, O, E>(parsed: F) -> impl Fn(Input<'i>) -> ParseResult<'i, O, E>
+where
+    F: Fn(Input<'i>) -> ParseResult<'i, O, E>,
+    E: ParseError<Input<'i>> + Clone
+{
+    delimited_by('{', parsed, '}')
+}
+
+/// Like [braced](braced), but fails without backtracking once the opening brace
+/// has been matched, so that errors point to the invalid contents of the block
+/// instead of its start.
+pub(crate) fn braced_cut<'i, F, O, E>(parsed: F) -> impl Fn(Input<'i>) -> ParseResult<'i, O, E>
+where
+    F: Fn(Input<'i>) -> ParseResult<'i, O, E>,
+    E: ParseError<Input<'i>> + Clone
+{
+    preceded(
+        pair(char('{'), opt(separator)),
+        cut(terminated(
+            parsed,
+            pair(opt(separator), char('}'))
+        ))
+    )
+}
+
+pub(crate) fn parenthesized<'i, F, O, E>(parsed: F) -> impl Fn(Input<'i>) -> ParseResult<'i, O, E>
+where
+    F: Fn(Input<'i>) -> ParseResult<'i, O, E>,
+    E: ParseError<Input<'i>> + Clone
+{
+    delimited_by('(', parsed, ')')
+}
+
+pub(crate) fn points<'i, E>(input: Input<'i>) -> ParseResult<'i, [Vector3; 3], E>
+where E: ParseError<Input<'i>> + Clone {
+    many_fixed(
+        maybe_sep_terminated(
+            parenthesized(parse)
+        )
+    )(input)
+}
+
+/// An entity's key/value pairs, kept in the order they appear in
+/// the map file, including duplicate keys. In a map file, they usually
+/// look something like this:
 /// ```plain
 /// "classname" "light"
 /// "wait" "2"
@@ -68,73 +124,303 @@ pub(crate) fn maybe_sep_terminated<'i, F, O, E>(parsed: F) -> impl Fn(Input<'i>)
 /// "origin" "-2704 1908 50"
 /// "_color" "1.00 0.93 0.70"
 /// ```
-#[derive(Debug, Clone, PartialEq, Default)]
-pub struct Fields(pub HashMap<String, String>);
+/// Like in the Quake engine, the last value of a duplicate key is the one
+/// that counts for lookups with [get](Fields::get) or indexing, but all of
+/// them are kept and written back out.
+///
+/// The strings are owned by default, but they can also
+/// [borrow](super::borrowed::Fields) from the input.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Fields<S = String>(pub Vec<(S, S)>);
+
+impl <S> Fields<S> {
+    /// Creates an empty set of fields.
+    pub fn new() -> Self {
+        Fields(Vec::new())
+    }
 
-impl Fields {
-    pub fn into_inner(self) -> HashMap<String, String> {
+    pub fn into_inner(self) -> Vec<(S, S)> {
         self.0
     }
+
+    /// Returns the number of key/value pairs, including duplicates.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns `true` if there are no key/value pairs.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns an iterator over the key/value pairs in order.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = (&S, &S)> {
+        self.0
+            .iter()
+            .map(|(key, value)| (key, value))
+    }
+
+    /// Returns an iterator over the keys in order, including duplicates.
+    pub fn keys(&self) -> impl Iterator<Item = &S> {
+        self.0
+            .iter()
+            .map(|(key, _)| key)
+    }
+
+    /// Appends a key/value pair, even if the key is already present.
+    pub fn push(&mut self, key: S, value: S) {
+        self.0.push((key, value))
+    }
 }
 
-impl Deref for Fields {
-    type Target = HashMap<String, String>;
+impl <S: Borrow<str>> Fields<S> {
+    /// Returns the last value of `key`.
+    pub fn get(&self, key: &str) -> Option<&S> {
+        self.0
+            .iter()
+            .rev()
+            .find(|(k, _)| k.borrow() == key)
+            .map(|(_, value)| value)
+    }
+
+    /// Returns a mutable reference to the last value of `key`.
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut S> {
+        self.0
+            .iter_mut()
+            .rev()
+            .find(|(k, _)| k.borrow() == key)
+            .map(|(_, value)| value)
+    }
+
+    /// Returns all values of `key` in order.
+    pub fn get_all<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a S> {
+        self.0
+            .iter()
+            .filter(move |(k, _)| k.borrow() == key)
+            .map(|(_, value)| value)
+    }
+
+    /// Returns `true` if `key` is present.
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Replaces the last value of `key`, keeping its position, and returns the old
+    /// value. If `key` isn't present yet, the pair is appended instead.
+    pub fn insert(&mut self, key: S, value: S) -> Option<S> {
+        match self.get_mut(key.borrow()) {
+            Some(existing) => Some(std::mem::replace(existing, value)),
+            None => {
+                self.push(key, value);
+                None
+            }
+        }
+    }
+
+    /// Removes every occurrence of `key` and returns its last value.
+    pub fn remove(&mut self, key: &str) -> Option<S> {
+        let mut removed = None;
+        let mut index = 0;
+
+        while index < self.0.len() {
+            if self.0[index].0.borrow() == key {
+                removed = Some(self.0.remove(index).1)
+            } else {
+                index += 1
+            }
+        }
 
-    fn deref(&self) -> &Self::Target {
-        &self.0
+        removed
     }
 }
 
-impl DerefMut for Fields {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+impl <S> Default for Fields<S> {
+    fn default() -> Self {
+        Fields::new()
     }
 }
 
-impl <'i, E> Parse<'i, E> for Fields
-where E: ParseError<Input<'i>> + Clone {
-    fn parse(input: Input<'i>) -> ParseResult<Self, E> {
-        map(
-            fold_many0(
+impl <S: Borrow<str>> Index<&str> for Fields<S> {
+    type Output = S;
+
+    /// Returns the last value of `key`.
+    ///
+    /// # Panics
+    /// Panics if `key` isn't present.
+    fn index(&self, key: &str) -> &S {
+        self.get(key)
+            .unwrap_or_else(|| panic!("no field with key `{}`", key))
+    }
+}
+
+impl <S> FromIterator<(S, S)> for Fields<S> {
+    fn from_iter<I: IntoIterator<Item = (S, S)>>(iter: I) -> Self {
+        Fields(iter.into_iter().collect())
+    }
+}
+
+impl <S> IntoIterator for Fields<S> {
+    type Item = (S, S);
+    type IntoIter = std::vec::IntoIter<(S, S)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl <S> Fields<S> {
+    /// Returns a parser for fields whose strings are decoded with the given [Escaping](Escaping).
+    /// The [Parse](Parse) implementation uses [Escaping::Raw](Escaping::Raw).
+    pub fn parser<'i, E>(escaping: Escaping) -> impl Fn(Input<'i>) -> ParseResult<'i, Self, E>
+    where
+        E: ParseError<Input<'i>> + Clone,
+        S: From<Cow<'i, str>>
+    {
+        move |input| map(
+            many0(
                 maybe_sep_terminated(
                     pair(
-                        maybe_sep_terminated(quoted_string),
-                        quoted_string
+                        maybe_sep_terminated(escaping.parser()),
+                        escaping.parser()
                     )
-                ),
-                HashMap::new(),
-                |mut map, (k, v)| {
-                    map.insert(k.into(), v.into());
-                    map
-                }
+                )
             ),
-            Fields
+            |pairs| pairs
+                .into_iter()
+                .map(|(k, v)| (S::from(k), S::from(v)))
+                .collect()
         )(input)
     }
 }
 
+impl <'i, E, S> Parse<'i, E> for Fields<S>
+where
+    E: ParseError<Input<'i>> + Clone,
+    S: From<Cow<'i, str>>
+{
+    fn parse(input: Input<'i>) -> ParseResult<'i, Self, E> {
+        Fields::parser(Escaping::Raw)(input)
+    }
+}
+
+/// How quotes and backslashes inside the quoted strings of [Fields](Fields) are treated,
+/// which differs between games and editors.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum Escaping {
+    /// Strings end at the next quote that isn't preceded by a backslash,
+    /// and are kept exactly as they're written, including the backslash
+    /// of `\"`. Strings are written back unchanged, so a string with a
+    /// quote that isn't preceded by a backslash can't be written.
+    #[default]
+    Raw,
+    /// Strings end at the next quote and backslashes have no special meaning,
+    /// like in the Quake and Half-Life engines and their compilers. Since
+    /// strings can't contain quotes this way, they're written as `'`.
+    Verbatim,
+    /// `\"` and `\\` stand for a quote and a backslash, like TrenchBroom
+    /// writes them. Any other backslash is kept as is, so paths like
+    /// `\half-life\valve\halflife.wad` are read and written unchanged.
+    Backslash
+}
+
+impl Escaping {
+    /// Returns a parser for a quoted string, which is decoded accordingly.
+    pub fn parser<'i, E>(self) -> impl Fn(Input<'i>) -> ParseResult<'i, Cow<'i, str>, E>
+    where E: ParseError<Input<'i>> + Clone {
+        move |input| match self {
+            Escaping::Raw => map(quoted_string, Cow::Borrowed)(input),
+            Escaping::Verbatim => map(verbatim_string, Cow::Borrowed)(input),
+            Escaping::Backslash => map(
+                quoted_string,
+                |raw| self.decode(raw)
+            )(input)
+        }
+    }
+
+    /// Decodes the contents of a quoted string.
+    pub fn decode(self, raw: &str) -> Cow<'_, str> {
+        if self != Escaping::Backslash || !(raw.contains("\\\"") || raw.contains("\\\\")) {
+            return Cow::Borrowed(raw)
+        }
+
+        let mut decoded = String::with_capacity(raw.len());
+        let mut chars = raw.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match (c, chars.peek()) {
+                ('\\', Some(&next)) if next == '"' || next == '\\' => {
+                    decoded.push(next);
+                    chars.next();
+                },
+                _ => decoded.push(c)
+            }
+        }
+
+        Cow::Owned(decoded)
+    }
+
+    /// Encodes a string so that it can be written between quotes
+    /// and [decoded](Escaping::decode) to the same string again.
+    /// This isn't possible for every string with [Raw](Escaping::Raw)
+    /// and [Verbatim](Escaping::Verbatim), as described there.
+    pub fn encode(self, value: &str) -> Cow<'_, str> {
+        match self {
+            Escaping::Verbatim if value.contains('"') => Cow::Owned(value.replace('"', "'")),
+            Escaping::Backslash if value.contains(['"', '\\']) => {
+                let mut encoded = String::with_capacity(value.len() + 2);
+                let mut chars = value.chars().peekable();
+
+                while let Some(c) = chars.next() {
+                    match (c, chars.peek()) {
+                        // backslashes are only ambiguous before quotes, other
+                        // backslashes and the closing quote
+                        ('\\', None) | ('\\', Some('"')) | ('\\', Some('\\')) => encoded.push_str("\\\\"),
+                        ('"', _) => encoded.push_str("\\\""),
+                        _ => encoded.push(c)
+                    }
+                }
+
+                Cow::Owned(encoded)
+            },
+            _ => Cow::Borrowed(value)
+        }
+    }
+}
+
 /// Representation of a map entity with [key/value pairs](Fields) and a list
 /// of [Brush](Brush)es, which may be empty if the entity in question is a
 /// point entity, like a light.
 #[derive(Debug, Clone, PartialEq, Default)]
-pub struct Entity<B> {
-    pub fields: Fields,
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Entity<B, S = String> {
+    pub fields: Fields<S>,
     pub brushes: Vec<B>
 }
 
-impl <'i, E, B> Parse<'i, E> for Entity<B>
+impl <'i, E, B, S> Parse<'i, E> for Entity<B, S>
+where
+    E: ParseError<Input<'i>> + Clone,
+    B: Parse<'i, E>,
+    S: From<Cow<'i, str>>
+{
+    fn parse(input: Input<'i>) -> ParseResult<'i, Self, E> {
+        Entity::parse_escaped(Escaping::Raw, input)
+    }
+}
+
+impl <'i, E, B, S> ParseEscaped<'i, E> for Entity<B, S>
 where
     E: ParseError<Input<'i>> + Clone,
-    B: Parse<'i, E>
+    B: Parse<'i, E>,
+    S: From<Cow<'i, str>>
 {
-    fn parse(input: Input<'i>) -> ParseResult<Self, E> {
-        delimited(
-            pair(char('{'), opt(separator)),
+    fn parse_escaped(escaping: Escaping, input: Input<'i>) -> ParseResult<'i, Self, E> {
+        braced_cut(
             fields!(Entity:
-                fields = maybe_sep_terminated(parse),
+                fields = maybe_sep_terminated(Fields::parser(escaping)),
                 brushes = many0(maybe_sep_terminated(parse))
-            ),
-            char('}')
+            )
         )(input)
     }
 }
@@ -150,27 +436,21 @@ where
 /// ( 816 -796 356 ) ( 816 -804 356 ) ( 808 -804 356 ) stone1_3 [ 0 -1 0 -20 ] [ 1 0 0 16 ] -0 1 1
 /// ```
 #[derive(Debug, Clone, PartialEq, Default)]
-pub struct Plane<TA> {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Plane<TA, S = String> {
     pub points: [Vector3; 3],
-    pub texture: Texture<TA>
+    pub texture: Texture<TA, S>
 }
 
-impl <'i, E, TA> Parse<'i, E> for Plane<TA>
+impl <'i, E, TA, S> Parse<'i, E> for Plane<TA, S>
 where
     E: ParseError<Input<'i>> + Clone,
-    TA: Parse<'i, E>
+    TA: Parse<'i, E>,
+    S: From<Input<'i>>
 {
-    fn parse(input: Input<'i>) -> ParseResult<Self, E> {
+    fn parse(input: Input<'i>) -> ParseResult<'i, Self, E> {
         fields!(Plane:
-            points = many_fixed(
-                maybe_sep_terminated(
-                    delimited(
-                        pair(char('('), opt(separator)),
-                        parse,
-                        pair(opt(separator), char(')'))
-                    ),
-                )
-            ),
+            points = points,
             texture = parse
         )(input)
     }
@@ -178,6 +458,7 @@ where
 
 /// A simple three-dimensional vector using `f32`s.
 #[derive(Debug, Copy, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Vector3 {
     pub x: f32,
     pub y: f32,
@@ -186,7 +467,7 @@ pub struct Vector3 {
 
 impl <'i, E> Parse<'i, E> for Vector3
 where E: ParseError<Input<'i>> + Clone {
-    fn parse(input: Input<'i>) -> ParseResult<Self, E> {
+    fn parse(input: Input<'i>) -> ParseResult<'i, Self, E> {
         fields!(Vector3:
             x = sep_terminated(float),
             y = sep_terminated(float),
@@ -199,24 +480,21 @@ where E: ParseError<Input<'i>> + Clone {
 /// texture's name and alignment. The format of the
 /// latter differs between map formats.
 #[derive(Debug, Clone, PartialEq, Default)]
-pub struct Texture<TA> {
-    pub name: String,
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Texture<TA, S = String> {
+    pub name: S,
     pub alignment: TA,
 }
 
-impl <'i, E, TA> Parse<'i, E> for Texture<TA>
+impl <'i, E, TA, S> Parse<'i, E> for Texture<TA, S>
 where
     E: ParseError<Input<'i>> + Clone,
-    TA: Parse<'i, E>
+    TA: Parse<'i, E>,
+    S: From<Input<'i>>
 {
-    fn parse(input: Input<'i>) -> ParseResult<Self, E> {
+    fn parse(input: Input<'i>) -> ParseResult<'i, Self, E> {
         fields!(Texture:
-            name = map(
-                sep_terminated(
-                    take_till(char::is_whitespace)
-                ),
-                String::from
-            ),
+            name = sep_terminated(texture_name),
             alignment = parse
         )(input)
     }
@@ -225,28 +503,37 @@ where
 /// Representation of a map brush, consisting of a
 /// list of [Plane](Plane)s.
 #[derive(Debug, Clone, PartialEq, Default)]
-pub struct Brush<TA> {
-    pub planes: Vec<Plane<TA>>
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Brush<TA, S = String> {
+    pub planes: Vec<Plane<TA, S>>
 }
 
-impl <'i, E, TA> Parse<'i, E> for Brush<TA>
+impl <'i, E, TA, S> Parse<'i, E> for Brush<TA, S>
 where
     E: ParseError<Input<'i>> + Clone,
-    TA: Parse<'i, E>
+    TA: Parse<'i, E>,
+    S: From<Input<'i>>
 {
-    fn parse(input: Input<'i>) -> ParseResult<Self, E> {
+    fn parse(input: Input<'i>) -> ParseResult<'i, Self, E> {
         map(
-            delimited(
-                pair(char('{'), opt(separator)),
-                many0(maybe_sep_terminated(parse)),
-                pair(opt(separator), char('}'))
-            ),
+            braced_cut(many0(maybe_sep_terminated(parse))),
             |planes| Brush { planes }
         )(input)
     }
 }
 
-pub(crate) fn comment<'i, E>(input: Input<'i>) -> ParseResult<Input<'i>, E>
+pub(crate) fn texture_name<'i, S, E>(input: Input<'i>) -> ParseResult<'i, S, E>
+where
+    S: From<Input<'i>>,
+    E: ParseError<Input<'i>>
+{
+    map(
+        take_till(char::is_whitespace),
+        S::from
+    )(input)
+}
+
+pub(crate) fn comment<'i, E>(input: Input<'i>) -> ParseResult<'i, Input<'i>, E>
 where E: ParseError<Input<'i>> {
     preceded(tag("//"), not_line_ending)(input)
 }
@@ -255,30 +542,158 @@ where E: ParseError<Input<'i>> {
 mod test {
     use {
         super::*,
-        crate::parse::common::test::expected
+        crate::parse::{
+            core::Error,
+            common::test::expected
+        }
     };
 
     #[test]
     fn fields() {
-        let mut map = HashMap::new();
-        map.insert("classname".into(), "func_parser".into());
-        map.insert("good".into(), "yes".into());
+        let mut map = Fields::<String>::new();
+        map.push("classname".into(), "func_parser".into());
+        map.push("good".into(), "yes".into());
 
         assert_eq!(
             parse(
                 r#""classname" "func_parser"
                 "good" "yes""#
             ),
-            expected(Fields(map))
+            expected(map)
         )
     }
 
+    #[test]
+    fn fields_duplicates() {
+        let fields: Fields = parse::<_, Error>(r#""target" "a" "classname" "trigger_multiple" "target" "b""#)
+            .unwrap()
+            .1;
+
+        assert_eq!(
+            fields.keys().collect::<Vec<_>>(),
+            vec!["target", "classname", "target"]
+        );
+        assert_eq!(fields["target"], "b");
+        assert_eq!(fields.get_all("target").collect::<Vec<_>>(), vec!["a", "b"]);
+        assert_eq!(fields.get("message"), None);
+    }
+
+    #[test]
+    fn fields_edit() {
+        let mut fields = Fields::<String>::new();
+        fields.push("target".into(), "a".into());
+        fields.push("classname".into(), "trigger_multiple".into());
+        fields.push("target".into(), "b".into());
+
+        assert_eq!(fields.insert("classname".into(), "trigger_once".into()), Some("trigger_multiple".into()));
+        assert_eq!(fields.insert("wait".into(), "1".into()), None);
+        assert_eq!(fields.remove("target"), Some("b".into()));
+        assert_eq!(
+            fields.into_inner(),
+            vec![
+                ("classname".into(), "trigger_once".into()),
+                ("wait".into(), "1".into())
+            ]
+        )
+    }
+
+    #[test]
+    fn fields_verbatim() {
+        let fields = Fields::<String>::parser::<Error>(Escaping::Verbatim)(
+            r#""wad" "\half-life\valve\" "message" "say \"hi""#
+        );
+
+        assert_eq!(
+            fields,
+            expected(Fields(vec![
+                ("wad".into(), r#"\half-life\valve\"#.into()),
+                ("message".into(), r#"say \"#.into())
+            ]))
+            .map(|(_, fields)| (r#"hi""#, fields))
+        )
+    }
+
+    #[test]
+    fn escaping() {
+        let backslash = Escaping::Backslash;
+
+        assert_eq!(backslash.decode(r#"a \"b\" c:\dir\\"#), r#"a "b" c:\dir\"#);
+        assert_eq!(backslash.encode(r#"a "b" c:\dir\"#), r#"a \"b\" c:\dir\\"#);
+        assert!(matches!(backslash.decode(r"c:\dir"), Cow::Borrowed(_)));
+
+        for value in [r#"""#, r"\", r#"\""#, r"\\", r"a\b", r#"\"quoted\""#, ""].iter() {
+            assert_eq!(backslash.decode(&backslash.encode(value)), *value);
+            let quoted = format!(r#""{}""#, backslash.encode(value));
+            let decoded = backslash.parser::<Error>()(&quoted)
+                .map(|(rest, decoded)| (rest.is_empty(), decoded.into_owned()));
+            assert_eq!(decoded, Ok((true, value.to_string())))
+        }
+
+        assert_eq!(Escaping::Raw.decode(r#"a \"b\" c:\dir\\"#), r#"a \"b\" c:\dir\\"#);
+        assert_eq!(Escaping::Raw.encode(r#"a \"b\" c:\dir\\"#), r#"a \"b\" c:\dir\\"#);
+        assert_eq!(Escaping::Verbatim.decode(r#"a\"#), r#"a\"#);
+        assert_eq!(Escaping::Verbatim.encode(r#"say "hi""#), "say 'hi'");
+    }
+
+    #[cfg(feature = "display")]
+    #[test]
+    fn fields_roundtrip() {
+        let fields = Fields::<String>(vec![
+            ("message".into(), r#"say "hi" \"#.into()),
+            ("wad".into(), r"\half-life\valve\halflife.wad".into()),
+            (r#"odd "key""#.into(), r"\\".into())
+        ]);
+        let string = fields.display(Escaping::Backslash).to_string();
+
+        assert!(string.contains(r#""wad" "\half-life\valve\halflife.wad""#));
+        let parsed = Fields::parser::<Error>(Escaping::Backslash)(&string);
+        assert_eq!(parsed, Ok(("", fields)))
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_representation() {
+        let entity = Entity {
+            fields: Fields::<String>(vec![
+                ("classname".into(), "func_wall".into()),
+                ("target".into(), "a".into()),
+                ("target".into(), "b".into())
+            ]),
+            brushes: vec![Brush {
+                planes: vec![Plane {
+                    points: [
+                        Vector3 { x: 0., y: 0., z: 0. },
+                        Vector3 { x: 0., y: 64., z: 0. },
+                        Vector3 { x: 64., y: 0., z: 0. }
+                    ],
+                    texture: Texture {
+                        name: String::from("floor"),
+                        alignment: crate::parse::formats::standard::TextureAlignment::default()
+                    }
+                }]
+            }]
+        };
+        let json = serde_json::to_string(&entity).unwrap();
+
+        assert_eq!(
+            json,
+            concat!(
+                r#"{"fields":[["classname","func_wall"],["target","a"],["target","b"]],"#,
+                r#""brushes":[{"planes":[{"points":[{"x":0.0,"y":0.0,"z":0.0},"#,
+                r#"{"x":0.0,"y":64.0,"z":0.0},{"x":64.0,"y":0.0,"z":0.0}],"#,
+                r#""texture":{"name":"floor","alignment":{"offset":{"x":0.0,"y":0.0},"#,
+                r#""rotation":0.0,"scale":{"x":0.0,"y":0.0}}}}]}]}"#
+            )
+        );
+        assert_eq!(serde_json::from_str::<Entity<_>>(&json).unwrap(), entity)
+    }
+
     #[test]
     fn fields_weird() {
-        let mut map = HashMap::new();
-        map.insert("classname".into(), "func_parser".into());
-        map.insert("good".into(), "no".into());
-        map.insert("msg".into(), r#"evil message with \"quotes\" "#.into());
+        let mut map = Fields::<String>::new();
+        map.push("classname".into(), "func_parser".into());
+        map.push("good".into(), "no".into());
+        map.push("msg".into(), r#"evil message with \"quotes\" "#.into());
 
         assert_eq!(
             parse(
@@ -291,15 +706,15 @@ mod test {
                 "evil message with \"quotes\" " // UH OH WATCH OUT
                 "#
             ),
-            expected(Fields(map))
+            expected(map)
         )
     }
 
     #[test]
     fn vector3() {
         assert_eq!(
-            parse(r"105252 3.14 .04"),
-            expected(Vector3 { x: 105252., y: 3.14, z: 0.04 })
+            parse(r"105252 3.15 .04"),
+            expected(Vector3 { x: 105252., y: 3.15, z: 0.04 })
         )
     }
 
@@ -309,10 +724,10 @@ mod test {
             parse(r"105252
 
             //
-                    3.14
+                    3.15
                     // weeeeeeeeeeeeeeeeeeeeeee
                  .04"),
-            expected(Vector3 { x: 105252., y: 3.14, z: 0.04 })
+            expected(Vector3 { x: 105252., y: 3.15, z: 0.04 })
         )
     }
 
@@ -321,7 +736,7 @@ mod test {
         assert_eq!(
             parse(r"wizmet1_2 <texture alignment>"),
             expected(Texture {
-                name: "wizmet1_2".into(),
+                name: "wizmet1_2".to_string(),
                 alignment: DummyTextureAlignment
             })
         )
@@ -334,7 +749,7 @@ mod test {
             // <texture alignment>
             <texture alignment>"),
             expected(Texture {
-                name: "wizmet1_2".into(),
+                name: "wizmet1_2".to_string(),
                 alignment: DummyTextureAlignment
             })
         )
@@ -351,7 +766,7 @@ mod test {
                     Vector3 { x: -3088., y: 1744., z: 384. }
                 ],
                 texture: Texture {
-                    name: "kn_floorp2".into(),
+                    name: "kn_floorp2".to_string(),
                     alignment: DummyTextureAlignment
                 }
             })
@@ -376,7 +791,7 @@ mod test {
                     Vector3 { x: -3088., y: 1744., z: 384. }
                 ],
                 texture: Texture {
-                    name: "kn_floorp2".into(),
+                    name: "kn_floorp2".to_string(),
                     alignment: DummyTextureAlignment
                 }
             })
@@ -388,7 +803,7 @@ mod test {
 
     impl <'i, E> Parse<'i, E> for DummyTextureAlignment
     where E: ParseError<Input<'i>> + Clone {
-        fn parse(input: Input<'i>) -> ParseResult<Self, E> {
+        fn parse(input: Input<'i>) -> ParseResult<'i, Self, E> {
             map(
                 tag("<texture alignment>"),
                 |_| Self
@@ -414,7 +829,7 @@ mod test {
                             Vector3 { x: -60.5, y: 53.5, z: 10. }
                         ],
                         texture: Texture {
-                            name: "__TB_empty".into(),
+                            name: "__TB_empty".to_string(),
                             alignment: DummyTextureAlignment
                         }
                     },
@@ -425,7 +840,7 @@ mod test {
                             Vector3 { x: -60.5, y: 53.5, z: 10. }
                         ],
                         texture: Texture {
-                            name: "yeeeeeeeeee".into(),
+                            name: "yeeeeeeeeee".to_string(),
                             alignment: DummyTextureAlignment
                         }
                     }
@@ -455,7 +870,7 @@ mod test {
                             Vector3 { x: -60.5, y: 53.5, z: 10. }
                         ],
                         texture: Texture {
-                            name: "__TB_empty".into(),
+                            name: "__TB_empty".to_string(),
                             alignment: DummyTextureAlignment
                         }
                     },
@@ -466,7 +881,7 @@ mod test {
                             Vector3 { x: -60.5, y: 53.5, z: 10. }
                         ],
                         texture: Texture {
-                            name: "yeeeeeeeeee".into(),
+                            name: "yeeeeeeeeee".to_string(),
                             alignment: DummyTextureAlignment
                         }
                     }
@@ -480,7 +895,7 @@ mod test {
 
     impl <'i, E> Parse<'i, E> for DummyBrush
         where E: ParseError<Input<'i>> + Clone {
-        fn parse(input: Input<'i>) -> ParseResult<Self, E> {
+        fn parse(input: Input<'i>) -> ParseResult<'i, Self, E> {
             map(
                 char('B'),
                 |_| DummyBrush
@@ -490,9 +905,9 @@ mod test {
 
     #[test]
     fn entity() {
-        let mut map = HashMap::new();
-        map.insert("classname".into(), "func_parser".into());
-        map.insert("good".into(), "yes".into());
+        let mut map = Fields::<String>::new();
+        map.push("classname".into(), "func_parser".into());
+        map.push("good".into(), "yes".into());
 
         assert_eq!(
             parse(r#"{
@@ -501,7 +916,7 @@ mod test {
             BBB
             }"#),
             expected(Entity {
-                fields: Fields(map),
+                fields: map,
                 brushes: vec![DummyBrush; 3]
             })
         )
@@ -3,6 +3,7 @@ use {
         common::{fields, parse},
         formats::{
             Format,
+            NoHeader,
             shared::{self, sep_terminated}
         },
         core::{
@@ -22,6 +23,7 @@ use {
 pub struct Standard;
 
 impl Format for Standard {
+    type Header = NoHeader;
     type Entity = Entity;
 }
 
@@ -39,6 +41,7 @@ pub type Texture = shared::Texture<TextureAlignment>;
 
 /// Representation of the standard format's texture alignment.
 #[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TextureAlignment {
     pub offset: Vector2,
     pub rotation: f32,
@@ -47,7 +50,7 @@ pub struct TextureAlignment {
 
 impl <'i, E> Parse<'i, E> for TextureAlignment
 where E: ParseError<Input<'i>> + Clone {
-    fn parse(input: Input<'i>) -> ParseResult<Self, E> {
+    fn parse(input: Input<'i>) -> ParseResult<'i, Self, E> {
         fields!(TextureAlignment:
             offset = sep_terminated(parse),
             rotation = sep_terminated(float),
@@ -58,6 +61,7 @@ where E: ParseError<Input<'i>> + Clone {
 
 /// A simple two-dimensional vector using `f32`s.
 #[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Vector2 {
     pub x: f32,
     pub y: f32
@@ -65,7 +69,7 @@ pub struct Vector2 {
 
 impl <'i, E> Parse<'i, E> for Vector2
 where E: ParseError<Input<'i>> + Clone {
-    fn parse(input: Input<'i>) -> ParseResult<Self, E> {
+    fn parse(input: Input<'i>) -> ParseResult<'i, Self, E> {
         fields!(Vector2:
             x = sep_terminated(float),
             y = float
@@ -81,8 +85,8 @@ mod test {
     #[test]
     fn vector2() {
         assert_eq!(
-            parse(r"105252 3.14"),
-            expected(Vector2 { x: 105252., y: 3.14 })
+            parse(r"105252 3.15"),
+            expected(Vector2 { x: 105252., y: 3.15 })
         )
     }
 
@@ -92,8 +96,8 @@ mod test {
             parse(r"105252
             // why would you do this
 
-             3.14"),
-            expected(Vector2 { x: 105252., y: 3.14 })
+             3.15"),
+            expected(Vector2 { x: 105252., y: 3.15 })
         );
     }
 
@@ -133,6 +137,7 @@ mod test {
     #[test]
     fn roundtrip() {
         let map = crate::Map::<Standard> {
+            header: NoHeader,
             entities: vec![Entity {
                 fields: crate::formats::shared::Fields(std::iter::once(("k".into(), "v".into())).collect()),
                 brushes: vec![Brush {
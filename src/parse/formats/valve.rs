@@ -3,6 +3,7 @@ use {
         common::{fields, parse},
         formats::{
             Format,
+            NoHeader,
             standard::Vector2,
             shared::{self, Vector3, separator, sep_terminated, maybe_sep_terminated}
         },
@@ -26,6 +27,7 @@ use {
 pub struct Valve;
 
 impl Format for Valve {
+    type Header = NoHeader;
     type Entity = Entity;
 }
 
@@ -43,6 +45,7 @@ pub type Texture = shared::Texture<TextureAlignment>;
 
 /// Representation of the Valve format's texture alignment.
 #[derive(Debug, Copy, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TextureAlignment {
     pub axes: Axes,
     pub rotation: f32,
@@ -51,7 +54,7 @@ pub struct TextureAlignment {
 
 impl <'i, E> Parse<'i, E> for TextureAlignment
 where E: ParseError<Input<'i>> + Clone {
-    fn parse(input: Input<'i>) -> ParseResult<Self, E> {
+    fn parse(input: Input<'i>) -> ParseResult<'i, Self, E> {
         fields!(TextureAlignment:
             axes = maybe_sep_terminated(parse),
             rotation = sep_terminated(float),
@@ -62,6 +65,7 @@ where E: ParseError<Input<'i>> + Clone {
 
 /// The u and v axes of the Valve format's texture alignment.
 #[derive(Debug, Copy, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Axes {
     pub u: Axis,
     pub v: Axis
@@ -69,7 +73,7 @@ pub struct Axes {
 
 impl <'i, E> Parse<'i, E> for Axes
 where E: ParseError<Input<'i>> + Clone {
-    fn parse(input: Input<'i>) -> ParseResult<Self, E> {
+    fn parse(input: Input<'i>) -> ParseResult<'i, Self, E> {
         fields!(Axes:
             u = maybe_sep_terminated(parse),
             v = parse
@@ -79,6 +83,7 @@ where E: ParseError<Input<'i>> + Clone {
 
 /// A [texture alignment](TextureAlignment) axis in Valve's map format.
 #[derive(Debug, Copy, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Axis {
     pub normal: Vector3,
     pub offset: f32
@@ -86,7 +91,7 @@ pub struct Axis {
 
 impl <'i, E> Parse<'i, E> for Axis
 where E: ParseError<Input<'i>> + Clone {
-    fn parse(input: Input<'i>) -> ParseResult<Self, E> {
+    fn parse(input: Input<'i>) -> ParseResult<'i, Self, E> {
         delimited(
             pair(char('['), opt(separator)),
             fields!(Axis:
@@ -100,6 +105,7 @@ where E: ParseError<Input<'i>> + Clone {
 
 /// The scale of a Valve format [Texture](super::shared::Texture).
 #[derive(Debug, Copy, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Scale {
     pub u: f32,
     pub v: f32
@@ -107,7 +113,7 @@ pub struct Scale {
 
 impl <'i, E> Parse<'i, E> for Scale
 where E: ParseError<Input<'i>> + Clone {
-    fn parse(input: Input<'i>) -> ParseResult<Self, E> {
+    fn parse(input: Input<'i>) -> ParseResult<'i, Self, E> {
         map(
             Vector2::parse,
             |vec| Scale {
@@ -236,6 +242,7 @@ mod test {
     #[test]
     fn roundtrip() {
         let map = crate::Map::<Valve> {
+            header: NoHeader,
             entities: vec![Entity {
                 fields: crate::formats::shared::Fields(std::iter::once(("k".into(), "v".into())).collect()),
                 brushes: vec![Brush {
@@ -1,5 +1,9 @@
 pub mod core;
 mod common;
 pub mod formats;
+pub mod diagnostics;
+pub mod recover;
+pub mod spans;
+pub mod cst;
 
 use self::core::nom;
\ No newline at end of file
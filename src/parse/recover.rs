@@ -0,0 +1,352 @@
+//! A parser that recovers from errors instead of failing at the first one,
+//! so that all problems of a map can be reported at once while keeping
+//! everything that could be parsed.
+
+use {
+    std::borrow::Cow,
+    crate::parse::{
+        common::parse as parse_part,
+        formats::{
+            Map,
+            Format,
+            shared::{self, Escaping, Fields, separator, maybe_sep_terminated}
+        },
+        core::{
+            Parse,
+            Input,
+            Error,
+            nom::{
+                self,
+                error::ErrorKind,
+                sequence::pair,
+                combinator::opt
+            }
+        }
+    }
+};
+
+/// Parses a map, recovering from errors by skipping to the next `{` or `}`
+/// of the entity or brush they occurred in. Returns the entities and brushes
+/// that could be parsed, along with every error encountered. If the header
+/// is invalid, its default value is used instead.
+/// ```
+/// use nomap::formats::Standard;
+///
+/// let (map, errors) = nomap::recover::parse::<Standard, _, _>(r#"
+/// { "classname" "worldspawn" { broken } }
+/// { "classname" "info_player_start" }
+/// "#);
+/// assert_eq!(map.entities.len(), 2);
+/// assert_eq!(errors.len(), 1);
+/// ```
+pub fn parse<'i, F, B, S>(input: Input<'i>) -> (Map<F>, Vec<Error<'i>>)
+where
+    F: Format<Entity = shared::Entity<B, S>>,
+    F::Header: Parse<'i, Error<'i>> + Default,
+    B: Parse<'i, Error<'i>>,
+    S: From<Cow<'i, str>>
+{
+    parse_with(input, Escaping::Raw)
+}
+
+/// Like [parse](parse), but decodes the strings of entity
+/// fields with the given [Escaping](Escaping).
+pub fn parse_with<'i, F, B, S>(input: Input<'i>, escaping: Escaping) -> (Map<F>, Vec<Error<'i>>)
+where
+    F: Format<Entity = shared::Entity<B, S>>,
+    F::Header: Parse<'i, Error<'i>> + Default,
+    B: Parse<'i, Error<'i>>,
+    S: From<Cow<'i, str>>
+{
+    let mut errors = Vec::new();
+    let mut rest = skip_separator(input);
+
+    let header = match parse_part(rest) {
+        Ok((remaining, header)) => {
+            rest = remaining;
+            header
+        },
+        Err(e) => {
+            errors.push(into_error(e, rest));
+            F::Header::default()
+        }
+    };
+
+    let mut entities = Vec::new();
+
+    loop {
+        rest = skip_separator(rest);
+
+        if rest.is_empty() {
+            break
+        } else if rest.starts_with('{') {
+            let (remaining, entity) = entity(&rest[1..], escaping, &mut errors);
+            rest = remaining;
+            entities.push(entity)
+        } else {
+            errors.push(error(rest, "expected an entity"));
+            rest = next_boundary(rest);
+            if rest.starts_with('}') {
+                rest = &rest[1..]
+            }
+        }
+    }
+
+    (Map { header, entities }, errors)
+}
+
+/// Parses the contents of an entity following its opening brace. The fields
+/// are parsed one at a time, so that the fields before an unclosed string
+/// are kept.
+fn entity<'i, B, S>(input: Input<'i>, escaping: Escaping, errors: &mut Vec<Error<'i>>) -> (Input<'i>, shared::Entity<B, S>)
+where
+    B: Parse<'i, Error<'i>>,
+    S: From<Cow<'i, str>>
+{
+    let mut rest = skip_separator(input);
+    let mut fields = Fields::new();
+
+    while rest.starts_with('"') {
+        match pair(maybe_sep_terminated(escaping.parser()), escaping.parser())(rest) {
+            Ok((remaining, (key, value))) => {
+                fields.push(S::from(key), S::from(value));
+                rest = skip_separator(remaining)
+            },
+            // anything else is reported as an unexpected brush below
+            Err(nom::Err::Error(_)) => break,
+            Err(e) => {
+                errors.push(into_error(e, rest));
+                rest = next_boundary(rest);
+                break
+            }
+        }
+    }
+
+    let mut brushes = Vec::new();
+
+    loop {
+        rest = skip_separator(rest);
+
+        if rest.is_empty() {
+            errors.push(error(rest, "expected a closing brace"));
+            break
+        } else if rest.starts_with('}') {
+            rest = &rest[1..];
+            break
+        } else if rest.starts_with('{') {
+            match parse_part(rest) {
+                Ok((remaining, brush)) => {
+                    rest = remaining;
+                    brushes.push(brush)
+                },
+                Err(e) => {
+                    errors.push(into_error(e, rest));
+                    rest = skip_block(rest)
+                }
+            }
+        } else {
+            errors.push(error(rest, "expected a brush or a closing brace"));
+            rest = next_boundary(rest)
+        }
+    }
+
+    (rest, shared::Entity { fields, brushes })
+}
+
+fn error<'i>(input: Input<'i>, context: &'static str) -> Error<'i> {
+    Error {
+        input,
+        kind: ErrorKind::Char,
+        context
+    }
+}
+
+fn into_error<'i>(error: nom::Err<Error<'i>>, input: Input<'i>) -> Error<'i> {
+    match error {
+        nom::Err::Error(e) | nom::Err::Failure(e) => e,
+        nom::Err::Incomplete(_) => Error {
+            input,
+            kind: ErrorKind::Complete,
+            context: ""
+        }
+    }
+}
+
+fn skip_separator(input: Input) -> Input {
+    opt(separator::<Error>)(input)
+        .map_or(input, |(rest, _)| rest)
+}
+
+/// Returns the input starting at the next brace, or an empty input if there's none.
+fn next_boundary(input: Input) -> Input {
+    braces(input)
+        .next()
+        .map_or(&input[input.len()..], |(index, _)| &input[index..])
+}
+
+/// Returns the input following the end of the block starting
+/// at `input`, or an empty input if the block isn't closed.
+fn skip_block(input: Input) -> Input {
+    let mut depth = 0usize;
+
+    for (index, brace) in braces(input) {
+        if brace == '{' {
+            depth += 1
+        } else {
+            depth = depth.saturating_sub(1);
+            if depth == 0 {
+                return &input[index + 1..]
+            }
+        }
+    }
+
+    &input[input.len()..]
+}
+
+/// Returns the positions of all braces in `input` outside of comments and
+/// strings. Strings end at a line break so that an unclosed quote doesn't
+/// swallow the rest of the map.
+fn braces(input: Input<'_>) -> impl Iterator<Item = (usize, char)> + '_ {
+    let mut chars = input.char_indices().peekable();
+
+    std::iter::from_fn(move || {
+        while let Some((index, c)) = chars.next() {
+            match c {
+                '{' | '}' => return Some((index, c)),
+                '/' if matches!(chars.peek(), Some((_, '/'))) => {
+                    while !matches!(chars.peek(), Some((_, '\n')) | None) {
+                        chars.next();
+                    }
+                },
+                '"' => {
+                    let mut escaped = false;
+                    while let Some(&(_, c)) = chars.peek() {
+                        if c == '\n' {
+                            break
+                        }
+                        chars.next();
+                        match c {
+                            '"' if !escaped => break,
+                            '\\' => escaped = !escaped,
+                            _ => escaped = false
+                        }
+                    }
+                },
+                _ => ()
+            }
+        }
+        None
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        crate::parse::formats::{Standard, Doom3, doom3::Version}
+    };
+
+    #[test]
+    fn valid() {
+        let input = include_str!("../../examples/example.map");
+        let (map, errors) = parse::<Standard, _, _>(input);
+
+        assert!(errors.is_empty());
+        assert_eq!(map, crate::parse::<Standard>(input).unwrap())
+    }
+
+    #[test]
+    fn broken_brush() {
+        let input = r#"{
+"classname" "worldspawn"
+{
+( 0 0 0 ) ( 0 64 0 ) ( 64 0 0 ) floor 0 0 0 1 1
+}
+{
+( 0 0 0 ) ( 0 64 0 ) ( 64 0 0 ) floor 0 0 zero 1 1
+}
+{
+( 0 0 64 ) ( 64 0 64 ) ( 0 64 64 ) ceiling 0 0 0 1 1
+}
+}
+{
+"classname" "info_player_start"
+}"#;
+        let (map, errors) = parse::<Standard, _, _>(input);
+
+        assert_eq!(map.entities.len(), 2);
+        assert_eq!(map.entities[0].brushes.len(), 2);
+        assert_eq!(map.entities[0].brushes[1].planes[0].texture.name, "ceiling");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].locate(input).unwrap().line, 7)
+    }
+
+    #[test]
+    fn broken_entities() {
+        let input = r#"
+garbage
+{
+"classname" "worldspawn"
+"message" unquoted
+{
+( 0 0 0 ) ( 0 64 0 ) ( 64 0 0 ) floor 0 0 0 1 1
+}
+}
+}
+{
+"classname" "info_player_start"
+"#;
+        let (map, errors) = parse::<Standard, _, _>(input);
+
+        assert_eq!(map.entities.len(), 2);
+        assert_eq!(map.entities[0].fields["classname"], "worldspawn");
+        assert_eq!(map.entities[0].brushes.len(), 1);
+        assert_eq!(map.entities[1].fields["classname"], "info_player_start");
+        assert_eq!(
+            errors
+                .iter()
+                .map(|e| (e.locate(input).unwrap().line, e.context))
+                .collect::<Vec<_>>(),
+            vec![
+                (2, "expected an entity"),
+                (5, "expected a brush or a closing brace"),
+                (10, "expected an entity"),
+                (13, "expected a closing brace")
+            ]
+        )
+    }
+
+    #[test]
+    fn unclosed_string() {
+        let input = r#"{ "classname" "worldspawn" }
+{
+"classname" "light"
+"message" "unclosed
+}"#;
+        let (map, errors) = parse::<Standard, _, _>(input);
+
+        assert_eq!(map.entities.len(), 2);
+        assert_eq!(map.entities[1].fields["classname"], "light");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].context, "no closing quote");
+        assert_eq!(errors[0].locate(input).unwrap().column, 11)
+    }
+
+    #[test]
+    fn escaping() {
+        let input = r#"{ "classname" "worldspawn" "wad" "c:\wads\" }"#;
+        let (map, errors) = parse_with::<Standard, _, _>(input, Escaping::Verbatim);
+
+        assert!(errors.is_empty());
+        assert_eq!(map.entities[0].fields["wad"], r"c:\wads\")
+    }
+
+    #[test]
+    fn broken_header() {
+        let (map, errors) = parse::<Doom3, _, _>(r#"Version two { "classname" "worldspawn" }"#);
+
+        assert_eq!(map.header, Version::default());
+        assert_eq!(errors.len(), 2);
+        assert_eq!(map.entities.len(), 1)
+    }
+}
@@ -0,0 +1,564 @@
+//! Optional tracking of where the parts of a map are located in its source.
+//! The spans are returned in a tree of their own that mirrors the parsed map,
+//! so the parsed types stay the same whether spans are tracked or not. The
+//! brushes of the Quake 3 and Doom 3 formats are tracked down to their planes
+//! like those of the other formats, while only the texture name of patches is
+//! tracked besides their whole span.
+//! ```
+//! use nomap::formats::Standard;
+//!
+//! let input = include_str!("../../examples/example.map");
+//! let (map, spans) = nomap::spans::parse::<Standard>(input).unwrap();
+//!
+//! let plane = &spans.entities[0].brushes[0].planes[0];
+//! assert_eq!(plane.span.line_column(input), (9, 1));
+//! assert!(plane.texture.slice(input).starts_with("sfloor4_6"));
+//! ```
+
+use {
+    std::borrow::Cow,
+    crate::parse::{
+        common::parse as parse_part,
+        formats::{
+            Map,
+            Format,
+            doom3,
+            quake3,
+            shared::{self, Escaping, points, separator, braced, braced_cut, maybe_sep_terminated}
+        },
+        core::{
+            Parse,
+            Input,
+            Error,
+            ParseResult,
+            nom::{
+                self,
+                bytes::tag,
+                branch::alt,
+                error::ParseError,
+                multi::many0,
+                sequence::{pair, preceded},
+                combinator::{map, opt, all_consuming}
+            }
+        }
+    }
+};
+
+/// A range of bytes in the source of a map.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize
+}
+
+impl Span {
+    /// Returns the part of `source` covered by the span.
+    pub fn slice(self, source: Input) -> Input {
+        &source[self.start..self.end]
+    }
+
+    /// Returns the line and column of the start of the span in `source`, starting at 1.
+    pub fn line_column(self, source: Input) -> (usize, usize) {
+        let before = &source[..self.start];
+        let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+
+        (
+            before.matches('\n').count() + 1,
+            before[line_start..].chars().count() + 1
+        )
+    }
+}
+
+/// The spans of a [Map](Map).
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct MapSpans<ES = EntitySpans> {
+    pub header: Span,
+    pub entities: Vec<ES>
+}
+
+/// The spans of an [Entity](shared::Entity).
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct EntitySpans<BS = BrushSpans> {
+    pub span: Span,
+    pub fields: Vec<FieldSpans>,
+    pub brushes: Vec<BS>
+}
+
+/// The spans of a key/value pair of [Fields](shared::Fields),
+/// including the quotes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct FieldSpans {
+    pub key: Span,
+    pub value: Span
+}
+
+/// The spans of a [Brush](shared::Brush).
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct BrushSpans {
+    pub span: Span,
+    pub planes: Vec<PlaneSpans>
+}
+
+/// The spans of a [Plane](shared::Plane).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct PlaneSpans {
+    pub span: Span,
+    /// The span of the texture, including its alignment.
+    pub texture: Span
+}
+
+/// The spans of a brush or patch of the [Quake 3](quake3::Primitive)
+/// and [Doom 3](doom3::Primitive) formats. The spans of the planes of
+/// Doom 3 brushes cover the plane equation instead of three points.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PrimitiveSpans {
+    Brush(BrushSpans),
+    Patch(PatchSpans)
+}
+
+/// The spans of a [Patch](quake3::Patch).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct PatchSpans {
+    pub span: Span,
+    /// The span of the texture name, without quotes.
+    pub texture: Span
+}
+
+/// Trait for parsing a type along with its spans, which are offsets into `base`.
+/// The input passed to [parse_spanned](ParseSpanned::parse_spanned) has to be
+/// a suffix of `base`. The strings of entity fields are decoded with `escaping`.
+pub trait ParseSpanned<'i, E = Error<'i>>
+where
+    E: ParseError<Input<'i>>,
+    Self: Sized
+{
+    type Spans;
+
+    fn parse_spanned(base: Input<'i>, escaping: Escaping, input: Input<'i>) -> ParseResult<'i, (Self, Self::Spans), E>;
+}
+
+/// A map along with its spans, as returned by [parse](parse).
+pub type SpannedMap<'i, F> = (Map<F>, <Map<F> as ParseSpanned<'i>>::Spans);
+
+/// Parses a map from a string like [parse](function@crate::parse),
+/// additionally returning the spans of its parts.
+pub fn parse<'i, F>(input: Input<'i>) -> Result<SpannedMap<'i, F>, nom::Err<Error<'i>>>
+where
+    F: Format,
+    Map<F>: ParseSpanned<'i>
+{
+    parse_with(input, Escaping::Raw)
+}
+
+/// Like [parse](parse), but decodes the strings of entity
+/// fields with the given [Escaping](Escaping).
+pub fn parse_with<'i, F>(input: Input<'i>, escaping: Escaping) -> Result<SpannedMap<'i, F>, nom::Err<Error<'i>>>
+where
+    F: Format,
+    Map<F>: ParseSpanned<'i>
+{
+    all_consuming(|i| Map::parse_spanned(input, escaping, i))(input)
+        .map(|(_rest, parsed)| parsed)
+}
+
+/// Runs `parser`, additionally returning the span of what it consumed.
+fn spanned<'i, P, O, E>(base: Input<'i>, parser: P) -> impl Fn(Input<'i>) -> ParseResult<'i, (O, Span), E>
+where P: Fn(Input<'i>) -> ParseResult<'i, O, E> {
+    move |input| {
+        let (rest, parsed) = parser(input)?;
+        let span = Span {
+            start: base.len() - input.len(),
+            end: base.len() - rest.len()
+        };
+        Ok((rest, (parsed, span)))
+    }
+}
+
+impl <'i, E, F> ParseSpanned<'i, E> for Map<F>
+where
+    E: ParseError<Input<'i>> + Clone,
+    F: Format,
+    F::Header: Parse<'i, E>,
+    F::Entity: ParseSpanned<'i, E>
+{
+    type Spans = MapSpans<<F::Entity as ParseSpanned<'i, E>>::Spans>;
+
+    fn parse_spanned(base: Input<'i>, escaping: Escaping, input: Input<'i>) -> ParseResult<'i, (Self, Self::Spans), E> {
+        let (rest, ((header, header_span), parsed)) = preceded(
+            opt(separator),
+            pair(
+                maybe_sep_terminated(spanned(base, parse_part)),
+                nom::multi::many1(maybe_sep_terminated(|i| F::Entity::parse_spanned(base, escaping, i)))
+            )
+        )(input)?;
+
+        let (entities, spans) = parsed.into_iter().unzip();
+
+        Ok((rest, (
+            Map { header, entities },
+            MapSpans { header: header_span, entities: spans }
+        )))
+    }
+}
+
+impl <'i, E, B, S> ParseSpanned<'i, E> for shared::Entity<B, S>
+where
+    E: ParseError<Input<'i>> + Clone,
+    B: ParseSpanned<'i, E>,
+    S: From<Cow<'i, str>>
+{
+    type Spans = EntitySpans<B::Spans>;
+
+    fn parse_spanned(base: Input<'i>, escaping: Escaping, input: Input<'i>) -> ParseResult<'i, (Self, Self::Spans), E> {
+        let (rest, (((fields, field_spans), parsed), span)) = spanned(
+            base,
+            braced_cut(pair(
+                maybe_sep_terminated(|i| shared::Fields::parse_spanned(base, escaping, i)),
+                many0(maybe_sep_terminated(|i| B::parse_spanned(base, escaping, i)))
+            ))
+        )(input)?;
+
+        let (brushes, brush_spans) = parsed.into_iter().unzip();
+
+        Ok((rest, (
+            shared::Entity { fields, brushes },
+            EntitySpans { span, fields: field_spans, brushes: brush_spans }
+        )))
+    }
+}
+
+impl <'i, E, S> ParseSpanned<'i, E> for shared::Fields<S>
+where
+    E: ParseError<Input<'i>> + Clone,
+    S: From<Cow<'i, str>>
+{
+    type Spans = Vec<FieldSpans>;
+
+    fn parse_spanned(base: Input<'i>, escaping: Escaping, input: Input<'i>) -> ParseResult<'i, (Self, Self::Spans), E> {
+        let quoted = |i| spanned(base, escaping.parser())(i);
+
+        let (rest, pairs) = many0(
+            maybe_sep_terminated(
+                pair(
+                    maybe_sep_terminated(quoted),
+                    quoted
+                )
+            )
+        )(input)?;
+
+        let mut fields = shared::Fields::new();
+        let mut spans = Vec::with_capacity(pairs.len());
+
+        for ((key, key_span), (value, value_span)) in pairs {
+            fields.push(S::from(key), S::from(value));
+            spans.push(FieldSpans { key: key_span, value: value_span })
+        }
+
+        Ok((rest, (fields, spans)))
+    }
+}
+
+impl <'i, E, TA, S> ParseSpanned<'i, E> for shared::Brush<TA, S>
+where
+    E: ParseError<Input<'i>> + Clone,
+    TA: Parse<'i, E>,
+    S: From<Input<'i>>
+{
+    type Spans = BrushSpans;
+
+    fn parse_spanned(base: Input<'i>, escaping: Escaping, input: Input<'i>) -> ParseResult<'i, (Self, Self::Spans), E> {
+        let (rest, (parsed, span)) = spanned(
+            base,
+            braced_cut(many0(maybe_sep_terminated(|i| shared::Plane::parse_spanned(base, escaping, i))))
+        )(input)?;
+
+        let (planes, plane_spans) = parsed.into_iter().unzip();
+
+        Ok((rest, (
+            shared::Brush { planes },
+            BrushSpans { span, planes: plane_spans }
+        )))
+    }
+}
+
+impl <'i, E, TA, S> ParseSpanned<'i, E> for shared::Plane<TA, S>
+where
+    E: ParseError<Input<'i>> + Clone,
+    TA: Parse<'i, E>,
+    S: From<Input<'i>>
+{
+    type Spans = PlaneSpans;
+
+    fn parse_spanned(base: Input<'i>, _escaping: Escaping, input: Input<'i>) -> ParseResult<'i, (Self, Self::Spans), E> {
+        let (rest, ((points, (texture, texture_span)), span)) = spanned(
+            base,
+            pair(points, spanned(base, parse_part))
+        )(input)?;
+
+        Ok((rest, (
+            shared::Plane { points, texture },
+            PlaneSpans { span, texture: texture_span }
+        )))
+    }
+}
+
+impl <'i, E> ParseSpanned<'i, E> for quake3::Primitive
+where E: ParseError<Input<'i>> + Clone {
+    type Spans = PrimitiveSpans;
+
+    fn parse_spanned(base: Input<'i>, _escaping: Escaping, input: Input<'i>) -> ParseResult<'i, (Self, Self::Spans), E> {
+        alt((
+            map(
+                primitive_brush(base, "brushDef", points, quake3::texture),
+                |(planes, spans)| (
+                    quake3::Primitive::Brush(quake3::Brush(shared::Brush {
+                        planes: planes
+                            .into_iter()
+                            .map(|(points, texture)| shared::Plane { points, texture })
+                            .collect()
+                    })),
+                    PrimitiveSpans::Brush(spans)
+                )
+            ),
+            map(
+                spanned(base, quake3::patch),
+                |((patch, texture), span)| (
+                    quake3::Primitive::Patch(patch),
+                    PrimitiveSpans::Patch(PatchSpans { span, texture: slice_span(base, texture) })
+                )
+            )
+        ))(input)
+    }
+}
+
+impl <'i, E> ParseSpanned<'i, E> for doom3::Primitive
+where E: ParseError<Input<'i>> + Clone {
+    type Spans = PrimitiveSpans;
+
+    fn parse_spanned(base: Input<'i>, _escaping: Escaping, input: Input<'i>) -> ParseResult<'i, (Self, Self::Spans), E> {
+        alt((
+            map(
+                primitive_brush(base, "brushDef3", doom3::equation, doom3::texture),
+                |(planes, spans)| (
+                    doom3::Primitive::Brush(doom3::Brush {
+                        planes: planes
+                            .into_iter()
+                            .map(|(equation, texture)| doom3::Plane { equation, texture })
+                            .collect()
+                    }),
+                    PrimitiveSpans::Brush(spans)
+                )
+            ),
+            map(
+                spanned(base, doom3::patch),
+                |((patch, texture), span)| (
+                    doom3::Primitive::Patch(patch),
+                    PrimitiveSpans::Patch(PatchSpans { span, texture: slice_span(base, texture) })
+                )
+            )
+        ))(input)
+    }
+}
+
+/// The planes of a brush as pairs of their half-space and
+/// texture, along with the spans of the brush.
+type SpannedPlanes<GO, TO> = (Vec<(GO, TO)>, BrushSpans);
+
+/// Parses a brush whose planes are in a block introduced by `keyword`, like the
+/// `brushDef` of Quake 3. The planes are parsed as the part describing their
+/// half-space followed by their texture, whose spans are tracked.
+fn primitive_brush<'i, G, T, GO, TO, E>(
+    base: Input<'i>,
+    keyword: &'static str,
+    geometry: G,
+    texture: T
+) -> impl Fn(Input<'i>) -> ParseResult<'i, SpannedPlanes<GO, TO>, E>
+where
+    G: Fn(Input<'i>) -> ParseResult<'i, GO, E>,
+    T: Fn(Input<'i>) -> ParseResult<'i, TO, E>,
+    E: ParseError<Input<'i>> + Clone
+{
+    move |input| {
+        let plane = spanned(base, pair(maybe_sep_terminated(&geometry), spanned(base, &texture)));
+        let (rest, (parsed, span)) = spanned(
+            base,
+            braced(preceded(
+                maybe_sep_terminated(tag(keyword)),
+                braced(many0(maybe_sep_terminated(plane)))
+            ))
+        )(input)?;
+
+        let (planes, plane_spans) = parsed
+            .into_iter()
+            .map(|((geometry, (texture, texture_span)), span)| (
+                (geometry, texture),
+                PlaneSpans { span, texture: texture_span }
+            ))
+            .unzip();
+
+        Ok((rest, (planes, BrushSpans { span, planes: plane_spans })))
+    }
+}
+
+/// Returns the span of `slice`, which has to be a part of `base`.
+fn slice_span(base: Input, slice: Input) -> Span {
+    let start = slice.as_ptr() as usize - base.as_ptr() as usize;
+
+    Span {
+        start,
+        end: start + slice.len()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        crate::parse::formats::{Valve, Quake3, Doom3, borrowed::Borrowed}
+    };
+
+    const INPUT: &str = r#"// spans
+{
+"classname" "worldspawn"
+"wad" "base.wad"
+{
+( 0 0 0 ) ( 0 64 0 ) ( 64 0 0 ) floor [ 1 0 0 0 ] [ 0 -1 0 0 ] 0 1 1
+( 0 0 64 ) ( 64 0 64 ) ( 0 64 64 ) ceiling [ 1 0 0 0 ] [ 0 -1 0 0 ] 0 1 1
+}
+}
+{ "classname" "info_player_start" }"#;
+
+    #[test]
+    fn spans() {
+        let (map, spans) = parse::<Valve>(INPUT).unwrap();
+
+        assert_eq!(map, crate::parse::<Valve>(INPUT).unwrap());
+        assert_eq!(spans.entities.len(), 2);
+
+        let worldspawn = &spans.entities[0];
+        assert_eq!(worldspawn.span.line_column(INPUT), (2, 1));
+        assert!(worldspawn.span.slice(INPUT).starts_with('{'));
+        assert!(worldspawn.span.slice(INPUT).ends_with('}'));
+        assert_eq!(worldspawn.fields[1].key.slice(INPUT), r#""wad""#);
+        assert_eq!(worldspawn.fields[1].value.slice(INPUT), r#""base.wad""#);
+
+        let brush = &worldspawn.brushes[0];
+        assert_eq!(brush.span.line_column(INPUT), (5, 1));
+        assert_eq!(brush.planes[1].span.line_column(INPUT), (7, 1));
+        assert_eq!(
+            brush.planes[1].texture.slice(INPUT),
+            "ceiling [ 1 0 0 0 ] [ 0 -1 0 0 ] 0 1 1"
+        );
+
+        assert_eq!(
+            spans.entities[1].span.slice(INPUT),
+            r#"{ "classname" "info_player_start" }"#
+        )
+    }
+
+    #[test]
+    fn spans_borrowed() {
+        let (map, spans) = parse::<Borrowed<Valve>>(INPUT).unwrap();
+
+        assert_eq!(map.entities[0].brushes[0].planes[0].texture.name, "floor");
+        assert_eq!(spans.entities[0].brushes[0].planes.len(), 2)
+    }
+
+    #[test]
+    fn spans_escaping() {
+        let input = r#"{ "classname" "worldspawn" "wad" "c:\wads\" }"#;
+        let (map, spans) = parse_with::<Valve>(input, Escaping::Verbatim).unwrap();
+
+        assert_eq!(map.entities[0].fields["wad"], r"c:\wads\");
+        assert_eq!(spans.entities[0].fields[1].value.slice(input), r#""c:\wads\""#)
+    }
+
+    #[test]
+    fn spans_primitives() {
+        let input = r#"{
+"classname" "worldspawn"
+{
+brushDef
+{
+( 0 0 0 ) ( 0 64 0 ) ( 64 0 0 ) ( ( 0.03125 0 0 ) ( 0 0.03125 0 ) ) floor 0 0 0
+}
+}
+{
+patchDef2
+{
+base_wall/metal
+( 3 1 0 0 0 )
+(
+( ( -64 -64 0 0 0 ) )
+( ( 0 -64 32 0.5 0 ) )
+( ( 64 -64 0 1 0 ) )
+)
+}
+}
+}"#;
+        let (map, spans) = parse::<Quake3>(input).unwrap();
+        assert_eq!(map, crate::parse::<Quake3>(input).unwrap());
+
+        let brush = match &spans.entities[0].brushes[0] {
+            PrimitiveSpans::Brush(brush) => brush,
+            other => panic!("expected a brush, got {:?}", other)
+        };
+        assert_eq!(brush.span.line_column(input), (3, 1));
+        assert_eq!(brush.planes[0].span.line_column(input), (6, 1));
+        assert_eq!(brush.planes[0].texture.slice(input), "( ( 0.03125 0 0 ) ( 0 0.03125 0 ) ) floor 0 0 0");
+
+        let patch = match &spans.entities[0].brushes[1] {
+            PrimitiveSpans::Patch(patch) => patch,
+            other => panic!("expected a patch, got {:?}", other)
+        };
+        assert_eq!(patch.span.line_column(input), (9, 1));
+        assert_eq!(patch.texture.slice(input), "base_wall/metal")
+    }
+
+    #[test]
+    fn spans_doom3() {
+        let input = r#"Version 2
+{
+"classname" "worldspawn"
+{
+brushDef3
+{
+( 0 0 -1 0 ) ( ( 0.0078125 0 0 ) ( 0 0.0078125 0 ) ) "textures/common/caulk" 0 0 0
+( 0 0 1 -64 ) ( ( 0.0078125 0 0 ) ( 0 0.0078125 0 ) ) "textures/base_floor/a" 0 0 0
+}
+}
+{
+patchDef3
+{
+"textures/base_wall/lfwall13f3"
+( 3 1 4 4 0 0 0 )
+(
+( ( -64 -64 0 0 0 ) )
+( ( 0 -64 32 0.5 0 ) )
+( ( 64 -64 0 1 0 ) )
+)
+}
+}
+}"#;
+        let (map, spans) = parse::<Doom3>(input).unwrap();
+        assert_eq!(map, crate::parse::<Doom3>(input).unwrap());
+
+        match &spans.entities[0].brushes[..] {
+            [PrimitiveSpans::Brush(brush), PrimitiveSpans::Patch(patch)] => {
+                assert_eq!(brush.planes[1].span.line_column(input), (8, 1));
+                assert!(brush.planes[1].texture.slice(input).ends_with(r#""textures/base_floor/a" 0 0 0"#));
+                assert_eq!(patch.texture.slice(input), "textures/base_wall/lfwall13f3")
+            },
+            other => panic!("expected a brush and a patch, got {:?}", other)
+        }
+    }
+
+    #[test]
+    fn spans_header() {
+        let input = "Version 2\n{ }";
+        let (_, spans) = parse::<Doom3>(input).unwrap();
+
+        assert_eq!(spans.header.slice(input), "Version 2");
+        assert_eq!(spans.entities[0].span.slice(input), "{ }")
+    }
+}
@@ -0,0 +1,18 @@
+use {
+    std::io,
+    super::{WriteMap, MapWriter},
+    crate::parse::formats::any::*
+};
+
+impl WriteMap for AnyMap {
+    fn write_map<W: io::Write>(&self, writer: &mut MapWriter<W>) -> io::Result<()> {
+        match self {
+            AnyMap::Standard(map) => writer.write(map),
+            AnyMap::Valve(map) => writer.write(map),
+            AnyMap::Quake2(map) => writer.write(map),
+            AnyMap::Quake2Valve(map) => writer.write(map),
+            AnyMap::Quake3(map) => writer.write(map),
+            AnyMap::Doom3(map) => writer.write(map)
+        }
+    }
+}
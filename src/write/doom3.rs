@@ -0,0 +1,166 @@
+use {
+    std::io,
+    super::{WriteMap, MapWriter, quake3::{flags, control_points}},
+    crate::parse::formats::doom3::*
+};
+
+impl WriteMap for Version {
+    fn write_map<W: io::Write>(&self, writer: &mut MapWriter<W>) -> io::Result<()> {
+        writer.token("Version")?;
+        writer.token(self.0)?;
+        writer.end_line()
+    }
+}
+
+impl WriteMap for Plane {
+    fn write_map<W: io::Write>(&self, writer: &mut MapWriter<W>) -> io::Result<()> {
+        let normal = &self.equation.normal;
+        writer.token('(')?;
+        writer.number(normal.x)?;
+        writer.number(normal.y)?;
+        writer.number(normal.z)?;
+        writer.number(-self.equation.distance)?;
+        writer.token(')')?;
+        writer.write(&self.texture.alignment.matrix)?;
+        writer.string(&self.texture.name)?;
+        flags(writer, &self.texture.alignment)?;
+        writer.end_line()
+    }
+}
+
+impl WriteMap for Brush {
+    fn write_map<W: io::Write>(&self, writer: &mut MapWriter<W>) -> io::Result<()> {
+        writer.begin_brush()?;
+        writer.open()?;
+        writer.token("brushDef3")?;
+        writer.end_line()?;
+        writer.open()?;
+        for plane in self.planes.iter() {
+            writer.write(plane)?
+        }
+        writer.close()?;
+        writer.close()
+    }
+}
+
+impl WriteMap for Patch {
+    fn write_map<W: io::Write>(&self, writer: &mut MapWriter<W>) -> io::Result<()> {
+        writer.begin_patch()?;
+        writer.open()?;
+        match self.subdivisions {
+            Some(_) => writer.token("patchDef3")?,
+            None => writer.token("patchDef2")?
+        }
+        writer.end_line()?;
+        writer.open()?;
+        writer.string(&self.texture)?;
+        writer.end_line()?;
+        writer.token('(')?;
+        writer.token(self.width())?;
+        writer.token(self.height())?;
+        if let Some(subdivisions) = self.subdivisions {
+            writer.token(subdivisions.horizontal)?;
+            writer.token(subdivisions.vertical)?
+        }
+        writer.token(self.content_flags)?;
+        writer.token(self.surface_flags)?;
+        writer.token(self.value)?;
+        writer.token(')')?;
+        writer.end_line()?;
+        control_points(writer, self)?;
+        writer.close()?;
+        writer.close()
+    }
+}
+
+impl WriteMap for Primitive {
+    fn write_map<W: io::Write>(&self, writer: &mut MapWriter<W>) -> io::Result<()> {
+        match self {
+            Primitive::Brush(brush) => writer.write(brush),
+            Primitive::Patch(patch) => writer.write(patch)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        parse::formats::{
+            Doom3,
+            doom3::Primitive,
+            shared::Escaping
+        },
+        write::{MapWriter, WriteOptions}
+    };
+
+    #[test]
+    fn roundtrip() {
+        let input = r#"Version 2
+{
+"classname" "worldspawn"
+{
+brushDef3
+{
+( 0 0 -1 -64 ) ( ( 0.0078125 0 0 ) ( 0 0.0078125 0 ) ) "textures/common/caulk" 0 0 0
+( 0 0 1 -64 ) ( ( 0.0078125 0 0 ) ( 0 0.0078125 0 ) ) "textures/common/caulk" 0 0 0
+}
+}
+{
+patchDef3
+{
+"textures/base_wall/lfwall13f3"
+( 3 3 4 4 0 0 0 )
+(
+( ( -64 -64 0 0 0 ) ( -64 0 32 0 0.5 ) ( -64 64 0 0 1 ) )
+( ( 0 -64 32 0.5 0 ) ( 0 0 64 0.5 0.5 ) ( 0 64 32 0.5 1 ) )
+( ( 64 -64 0 1 0 ) ( 64 0 32 1 0.5 ) ( 64 64 0 1 1 ) )
+)
+}
+}
+}
+"#;
+        let map = crate::parse::<Doom3>(input).unwrap();
+
+        let mut writer = MapWriter::new(Vec::new());
+        writer.write(&map).unwrap();
+        assert_eq!(String::from_utf8(writer.into_inner()).unwrap(), input);
+
+        let options = WriteOptions {
+            indent: "  ".into(),
+            comments: true,
+            ..WriteOptions::default()
+        };
+        let mut writer = MapWriter::with_options(Vec::new(), options);
+        writer.write(&map).unwrap();
+        let written = String::from_utf8(writer.into_inner()).unwrap();
+
+        assert!(written.contains("\n  // brush 0\n  {\n    brushDef3\n"));
+        assert!(written.contains("\n  // patch 0\n"));
+        assert_eq!(crate::parse::<Doom3>(&written).unwrap(), map)
+    }
+
+    #[test]
+    fn escaped_texture() {
+        let input = r#"Version 2
+{
+"classname" "worldspawn"
+{
+brushDef3
+{
+( 0 0 -1 -64 ) ( ( 0.0078125 0 0 ) ( 0 0.0078125 0 ) ) "textures/common/caulk" 0 0 0
+}
+}
+}
+"#;
+        let mut map = crate::parse::<Doom3>(input).unwrap();
+        if let Primitive::Brush(brush) = &mut map.entities[0].brushes[0] {
+            brush.planes[0].texture.name = String::from(r#"textures/"odd""#)
+        }
+
+        let options = WriteOptions { escaping: Escaping::Backslash, ..WriteOptions::default() };
+        let mut writer = MapWriter::with_options(Vec::new(), options);
+        writer.write(&map).unwrap();
+
+        assert!(String::from_utf8(writer.into_inner()).unwrap().contains(r#" "textures/\"odd\"" 0 0 0"#))
+    }
+}
@@ -0,0 +1,377 @@
+//! Configurable serialisation of maps that streams directly to any
+//! [io::Write](std::io::Write), unlike the `Display` implementations
+//! provided by the "display" feature, which always use the same style.
+//! ```
+//! use nomap::{
+//!     formats::Standard,
+//!     write::{MapWriter, WriteOptions, KeyOrder}
+//! };
+//!
+//! let map = nomap::parse::<Standard>(include_str!("../../examples/example.map")).unwrap();
+//!
+//! let options = WriteOptions {
+//!     key_order: KeyOrder::ClassnameFirst,
+//!     precision: Some(2),
+//!     comments: true,
+//!     ..WriteOptions::default()
+//! };
+//!
+//! let mut out = Vec::new();
+//! MapWriter::with_options(&mut out, options).write(&map).unwrap();
+//!
+//! assert!(out.starts_with(b"// entity 0\n{\n\"classname\" \"worldspawn\"\n"));
+//! ```
+
+mod shared;
+mod standard;
+mod valve;
+mod quake2;
+mod quake3;
+mod doom3;
+mod any;
+
+use {
+    std::{
+        io,
+        fmt::{self, Display, Write as _}
+    },
+    crate::parse::formats::shared::Escaping
+};
+
+/// Trait for types that can be written with a [MapWriter](MapWriter).
+/// It's implemented for all types of the supported formats.
+pub trait WriteMap {
+    fn write_map<W: io::Write>(&self, writer: &mut MapWriter<W>) -> io::Result<()>;
+}
+
+/// The options of a [MapWriter](MapWriter). The default options produce
+/// the same output as the `Display` implementations, with entity fields
+/// in the order they're stored in.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct WriteOptions {
+    /// Written once per level of nesting in front of every line
+    /// inside an entity.
+    pub indent: String,
+    pub line_ending: LineEnding,
+    /// The maximum number of decimal places of numbers. Trailing zeros
+    /// are omitted. If `None`, numbers are written with as many decimal
+    /// places as needed to read them back exactly.
+    pub precision: Option<usize>,
+    /// Whether to round the points of planes to integers.
+    pub integer_coordinates: bool,
+    pub key_order: KeyOrder,
+    /// Whether to precede entities and brushes with TrenchBroom style
+    /// `// entity N` and `// brush N` comments. Patches are preceded
+    /// by `// patch N` comments and counted separately.
+    pub comments: bool,
+    /// How quotes and backslashes in entity fields are escaped. Strings
+    /// are written unchanged by default, like by the `Display`
+    /// implementations. Only [Escaping::Backslash](Escaping::Backslash)
+    /// can write any string: [Escaping::Verbatim](Escaping::Verbatim)
+    /// writes quotes as `'`, and [Escaping::Raw](Escaping::Raw) writes
+    /// quotes as they are, which ends the string early.
+    pub escaping: Escaping
+}
+
+/// The line ending written by a [MapWriter](MapWriter).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    CrLf
+}
+
+impl LineEnding {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n"
+        }
+    }
+}
+
+/// The order in which a [MapWriter](MapWriter) writes the fields of an entity.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum KeyOrder {
+    /// The order the fields are stored in, which is the order
+    /// they appeared in when parsed.
+    #[default]
+    Stored,
+    /// `classname` first, followed by the other fields in stored order.
+    ClassnameFirst
+}
+
+/// Writes maps and their parts to an [io::Write](std::io::Write) according
+/// to its [WriteOptions](WriteOptions). The output consists of tokens
+/// separated by single spaces, which are grouped into lines and indented
+/// by the level of nesting of the braces they're in.
+///
+/// The writer doesn't buffer its output, so wrapping `W` in an
+/// [io::BufWriter](std::io::BufWriter) is recommended when writing
+/// to a file.
+#[derive(Debug)]
+pub struct MapWriter<W> {
+    out: W,
+    options: WriteOptions,
+    depth: usize,
+    line_start: bool,
+    entities: usize,
+    brushes: usize,
+    patches: usize,
+    /// Reused for formatting numbers.
+    buffer: String
+}
+
+impl <W: io::Write> MapWriter<W> {
+    /// Creates a writer with the default options.
+    pub fn new(out: W) -> Self {
+        MapWriter::with_options(out, WriteOptions::default())
+    }
+
+    pub fn with_options(out: W, options: WriteOptions) -> Self {
+        MapWriter {
+            out,
+            options,
+            depth: 0,
+            line_start: true,
+            entities: 0,
+            brushes: 0,
+            patches: 0,
+            buffer: String::new()
+        }
+    }
+
+    pub fn options(&self) -> &WriteOptions {
+        &self.options
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.out
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+
+    /// Writes `value`, for example a whole [Map](crate::Map).
+    pub fn write<T: WriteMap + ?Sized>(&mut self, value: &T) -> io::Result<()> {
+        value.write_map(self)
+    }
+
+    /// Writes a single token, preceded by the indentation if it's the first
+    /// on its line and by a space otherwise.
+    pub fn token(&mut self, token: impl Display) -> io::Result<()> {
+        if self.line_start {
+            for _ in 0..self.depth {
+                self.out.write_all(self.options.indent.as_bytes())?
+            }
+            self.line_start = false
+        } else {
+            self.out.write_all(b" ")?
+        }
+        write!(self.out, "{}", token)
+    }
+
+    /// Ends the current line.
+    pub fn end_line(&mut self) -> io::Result<()> {
+        self.line_start = true;
+        self.out.write_all(self.options.line_ending.as_str().as_bytes())
+    }
+
+    /// Writes an opening brace on its own line and increases the indentation.
+    pub fn open(&mut self) -> io::Result<()> {
+        self.token('{')?;
+        self.end_line()?;
+        self.depth += 1;
+        Ok(())
+    }
+
+    /// Decreases the indentation and writes a closing brace on its own line.
+    pub fn close(&mut self) -> io::Result<()> {
+        self.depth = self.depth.saturating_sub(1);
+        self.token('}')?;
+        self.end_line()
+    }
+
+    /// Writes a number with the configured precision.
+    pub fn number(&mut self, value: f32) -> io::Result<()> {
+        let mut buffer = std::mem::take(&mut self.buffer);
+        buffer.clear();
+        format_number(&mut buffer, value, self.options.precision)
+            .expect("formatting into a String can't fail");
+        let result = self.token(&buffer);
+        self.buffer = buffer;
+        result
+    }
+
+    /// Writes a coordinate of a point, which is rounded to an integer
+    /// if [integer_coordinates](WriteOptions::integer_coordinates) is set.
+    pub fn coordinate(&mut self, value: f32) -> io::Result<()> {
+        if self.options.integer_coordinates {
+            self.number(value.round())
+        } else {
+            self.number(value)
+        }
+    }
+
+    /// Writes a quoted string, escaped according to the configured [Escaping](Escaping).
+    pub fn string(&mut self, value: &str) -> io::Result<()> {
+        let encoded = self.options.escaping.encode(value);
+        self.token(format_args!("\"{}\"", encoded))
+    }
+
+    /// Writes the `// entity N` comment if comments are enabled, and
+    /// restarts counting brushes and patches. Called before every entity.
+    pub fn begin_entity(&mut self) -> io::Result<()> {
+        let index = self.entities;
+        self.entities += 1;
+        self.brushes = 0;
+        self.patches = 0;
+        self.comment("entity", index)
+    }
+
+    /// Writes the `// brush N` comment if comments are enabled.
+    /// Called before every brush.
+    pub fn begin_brush(&mut self) -> io::Result<()> {
+        let index = self.brushes;
+        self.brushes += 1;
+        self.comment("brush", index)
+    }
+
+    /// Writes the `// patch N` comment if comments are enabled.
+    /// Called before every patch.
+    pub fn begin_patch(&mut self) -> io::Result<()> {
+        let index = self.patches;
+        self.patches += 1;
+        self.comment("patch", index)
+    }
+
+    fn comment(&mut self, kind: &str, index: usize) -> io::Result<()> {
+        if self.options.comments {
+            self.token(format_args!("// {} {}", kind, index))?;
+            self.end_line()
+        } else {
+            Ok(())
+        }
+    }
+}
+
+pub(crate) fn format_number(buffer: &mut String, value: f32, precision: Option<usize>) -> fmt::Result {
+    match precision {
+        Some(precision) => {
+            write!(buffer, "{:.*}", precision, value)?;
+            if buffer.contains('.') {
+                let trimmed = buffer.trim_end_matches('0').trim_end_matches('.').len();
+                buffer.truncate(trimmed)
+            }
+        },
+        None => write!(buffer, "{}", value)?
+    }
+
+    // negative zero, or a small negative number rounded to zero
+    if buffer == "-0" {
+        buffer.remove(0);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        crate::parse::formats::{Standard, Valve}
+    };
+
+    fn write<T: WriteMap>(value: &T, options: WriteOptions) -> String {
+        let mut writer = MapWriter::with_options(Vec::new(), options);
+        writer.write(value).unwrap();
+        String::from_utf8(writer.into_inner()).unwrap()
+    }
+
+    const INPUT: &str = r#"{
+"wad" "base.wad"
+"classname" "worldspawn"
+{
+( 0 0 0 ) ( 0 64.5 0 ) ( 64 0 0 ) floor [ 1 0 0 0.3333 ] [ 0 -1 0 0 ] 0 0.25 0.25
+}
+}
+"#;
+
+    #[test]
+    fn roundtrip() {
+        let input = include_str!("../../examples/example.map");
+        let map = crate::parse::<Standard>(input).unwrap();
+        let written = write(&map, WriteOptions::default());
+
+        assert_eq!(crate::parse::<Standard>(&written).unwrap(), map)
+    }
+
+    #[test]
+    fn default_style() {
+        let map = crate::parse::<Valve>(INPUT).unwrap();
+
+        assert_eq!(write(&map, WriteOptions::default()), INPUT)
+    }
+
+    #[test]
+    fn styled() {
+        let map = crate::parse::<Valve>(INPUT).unwrap();
+        let options = WriteOptions {
+            indent: "\t".into(),
+            line_ending: LineEnding::CrLf,
+            precision: Some(2),
+            integer_coordinates: true,
+            key_order: KeyOrder::ClassnameFirst,
+            comments: true,
+            escaping: Escaping::Backslash
+        };
+
+        assert_eq!(
+            write(&map, options),
+            "// entity 0\r\n\
+            {\r\n\
+            \t\"classname\" \"worldspawn\"\r\n\
+            \t\"wad\" \"base.wad\"\r\n\
+            \t// brush 0\r\n\
+            \t{\r\n\
+            \t\t( 0 0 0 ) ( 0 65 0 ) ( 64 0 0 ) floor [ 1 0 0 0.33 ] [ 0 -1 0 0 ] 0 0.25 0.25\r\n\
+            \t}\r\n\
+            }\r\n"
+        )
+    }
+
+    #[test]
+    fn verbatim() {
+        let input = r#"{
+"classname" "worldspawn"
+"wad" "c:\half-life\valve\halflife.wad;c:\half-life\valve\"
+"message" "it's a \trap"
+{
+( 0 0 0 ) ( 0 64 0 ) ( 64 0 0 ) floor [ 1 0 0 0 ] [ 0 -1 0 0 ] 0 1 1
+}
+}
+"#;
+        let map = crate::parse_with::<Valve>(input, Escaping::Verbatim).unwrap();
+        assert_eq!(map.entities[0].fields["wad"], r"c:\half-life\valve\halflife.wad;c:\half-life\valve\");
+        assert!(crate::parse::<Valve>(input).is_err());
+
+        let options = WriteOptions { escaping: Escaping::Verbatim, ..WriteOptions::default() };
+        assert_eq!(write(&map, options), input)
+    }
+
+    #[test]
+    fn numbers() {
+        let format = |value, precision| {
+            let mut buffer = String::new();
+            format_number(&mut buffer, value, precision).unwrap();
+            buffer
+        };
+
+        assert_eq!(format(0.1, None), "0.1");
+        assert_eq!(format(-0., None), "0");
+        assert_eq!(format(16., Some(3)), "16");
+        assert_eq!(format(1.23456, Some(3)), "1.235");
+        assert_eq!(format(-0.0001, Some(2)), "0");
+        assert_eq!(format(2.5, Some(0)), "2")
+    }
+}
@@ -0,0 +1,20 @@
+use {
+    std::io,
+    super::{WriteMap, MapWriter},
+    crate::parse::formats::quake2::*
+};
+
+impl WriteMap for SurfaceAttributes {
+    fn write_map<W: io::Write>(&self, writer: &mut MapWriter<W>) -> io::Result<()> {
+        writer.token(self.contents.bits())?;
+        writer.token(self.flags.bits())?;
+        writer.token(self.value)
+    }
+}
+
+impl <TA: WriteMap> WriteMap for TextureAlignment<TA> {
+    fn write_map<W: io::Write>(&self, writer: &mut MapWriter<W>) -> io::Result<()> {
+        writer.write(&self.base)?;
+        writer.write(&self.attributes)
+    }
+}
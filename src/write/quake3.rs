@@ -0,0 +1,124 @@
+use {
+    std::io,
+    super::{WriteMap, MapWriter},
+    crate::parse::formats::{
+        quake3::*,
+        shared::Vector3
+    }
+};
+
+impl WriteMap for TextureMatrix {
+    fn write_map<W: io::Write>(&self, writer: &mut MapWriter<W>) -> io::Result<()> {
+        writer.token('(')?;
+        row(writer, &self.u)?;
+        row(writer, &self.v)?;
+        writer.token(')')
+    }
+}
+
+fn row<W: io::Write>(writer: &mut MapWriter<W>, row: &Vector3) -> io::Result<()> {
+    writer.token('(')?;
+    writer.number(row.x)?;
+    writer.number(row.y)?;
+    writer.number(row.z)?;
+    writer.token(')')
+}
+
+/// Writes a texture, whose name is preceded by its matrix and followed by its flags.
+fn texture<W: io::Write>(
+    writer: &mut MapWriter<W>,
+    name: &str,
+    alignment: &TextureAlignment
+) -> io::Result<()> {
+    writer.write(&alignment.matrix)?;
+    writer.token(name)?;
+    flags(writer, alignment)
+}
+
+/// Writes the flags following the name of a texture.
+pub(super) fn flags<W: io::Write>(writer: &mut MapWriter<W>, alignment: &TextureAlignment) -> io::Result<()> {
+    writer.token(alignment.content_flags)?;
+    writer.token(alignment.surface_flags)?;
+    writer.token(alignment.value)
+}
+
+impl WriteMap for Brush {
+    fn write_map<W: io::Write>(&self, writer: &mut MapWriter<W>) -> io::Result<()> {
+        writer.begin_brush()?;
+        writer.open()?;
+        writer.token("brushDef")?;
+        writer.end_line()?;
+        writer.open()?;
+        for plane in self.planes.iter() {
+            for point in plane.points.iter() {
+                writer.token('(')?;
+                writer.write(point)?;
+                writer.token(')')?
+            }
+            texture(writer, &plane.texture.name, &plane.texture.alignment)?;
+            writer.end_line()?
+        }
+        writer.close()?;
+        writer.close()
+    }
+}
+
+impl WriteMap for ControlPoint {
+    fn write_map<W: io::Write>(&self, writer: &mut MapWriter<W>) -> io::Result<()> {
+        writer.token('(')?;
+        writer.number(self.position.x)?;
+        writer.number(self.position.y)?;
+        writer.number(self.position.z)?;
+        writer.number(self.uv.x)?;
+        writer.number(self.uv.y)?;
+        writer.token(')')
+    }
+}
+
+/// Writes the control points of a patch, which are the same for all patch types.
+pub(super) fn control_points<W: io::Write>(writer: &mut MapWriter<W>, patch: &Patch) -> io::Result<()> {
+    writer.token('(')?;
+    writer.end_line()?;
+    for column in patch.control_points.iter() {
+        writer.token('(')?;
+        for point in column.iter() {
+            writer.write(point)?
+        }
+        writer.token(')')?;
+        writer.end_line()?
+    }
+    writer.token(')')?;
+    writer.end_line()
+}
+
+impl WriteMap for Patch {
+    fn write_map<W: io::Write>(&self, writer: &mut MapWriter<W>) -> io::Result<()> {
+        writer.begin_patch()?;
+        writer.open()?;
+        writer.token("patchDef2")?;
+        writer.end_line()?;
+        writer.open()?;
+        writer.token(&self.texture)?;
+        writer.end_line()?;
+        writer.token('(')?;
+        writer.token(self.width())?;
+        writer.token(self.height())?;
+        writer.token(self.content_flags)?;
+        writer.token(self.surface_flags)?;
+        writer.token(self.value)?;
+        writer.token(')')?;
+        writer.end_line()?;
+        control_points(writer, self)?;
+        writer.close()?;
+        writer.close()
+    }
+}
+
+impl WriteMap for Primitive {
+    fn write_map<W: io::Write>(&self, writer: &mut MapWriter<W>) -> io::Result<()> {
+        match self {
+            Primitive::Brush(brush) => writer.write(brush),
+            Primitive::Patch(patch) => writer.write(patch)
+        }
+    }
+}
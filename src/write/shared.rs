@@ -0,0 +1,110 @@
+use {
+    std::io,
+    super::{WriteMap, MapWriter, KeyOrder},
+    crate::{
+        Map,
+        parse::formats::{
+            Format,
+            NoHeader,
+            shared::*
+        }
+    }
+};
+
+impl WriteMap for Vector3 {
+    fn write_map<W: io::Write>(&self, writer: &mut MapWriter<W>) -> io::Result<()> {
+        writer.coordinate(self.x)?;
+        writer.coordinate(self.y)?;
+        writer.coordinate(self.z)
+    }
+}
+
+impl <TA: WriteMap, S: AsRef<str>> WriteMap for Texture<TA, S> {
+    fn write_map<W: io::Write>(&self, writer: &mut MapWriter<W>) -> io::Result<()> {
+        writer.token(self.name.as_ref())?;
+        writer.write(&self.alignment)
+    }
+}
+
+impl <TA: WriteMap, S: AsRef<str>> WriteMap for Plane<TA, S> {
+    fn write_map<W: io::Write>(&self, writer: &mut MapWriter<W>) -> io::Result<()> {
+        for point in self.points.iter() {
+            writer.token('(')?;
+            writer.write(point)?;
+            writer.token(')')?
+        }
+        writer.write(&self.texture)?;
+        writer.end_line()
+    }
+}
+
+impl <TA: WriteMap, S: AsRef<str>> WriteMap for Brush<TA, S> {
+    fn write_map<W: io::Write>(&self, writer: &mut MapWriter<W>) -> io::Result<()> {
+        writer.begin_brush()?;
+        writer.open()?;
+        for plane in self.planes.iter() {
+            writer.write(plane)?
+        }
+        writer.close()
+    }
+}
+
+impl <S: AsRef<str>> WriteMap for Fields<S> {
+    fn write_map<W: io::Write>(&self, writer: &mut MapWriter<W>) -> io::Result<()> {
+        let is_classname = |key: &S| key.as_ref() == "classname";
+
+        match writer.options().key_order {
+            KeyOrder::Stored => {
+                for (key, value) in self.iter() {
+                    field(writer, key.as_ref(), value.as_ref())?
+                }
+            },
+            KeyOrder::ClassnameFirst => {
+                let (first, rest) = self.iter().partition::<Vec<_>, _>(|(key, _)| is_classname(key));
+                for (key, value) in first.into_iter().chain(rest) {
+                    field(writer, key.as_ref(), value.as_ref())?
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn field<W: io::Write>(writer: &mut MapWriter<W>, key: &str, value: &str) -> io::Result<()> {
+    writer.string(key)?;
+    writer.string(value)?;
+    writer.end_line()
+}
+
+impl <B: WriteMap, S: AsRef<str>> WriteMap for Entity<B, S> {
+    fn write_map<W: io::Write>(&self, writer: &mut MapWriter<W>) -> io::Result<()> {
+        writer.begin_entity()?;
+        writer.open()?;
+        writer.write(&self.fields)?;
+        for brush in self.brushes.iter() {
+            writer.write(brush)?
+        }
+        writer.close()
+    }
+}
+
+impl WriteMap for NoHeader {
+    fn write_map<W: io::Write>(&self, _writer: &mut MapWriter<W>) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl <F> WriteMap for Map<F>
+where
+    F: Format,
+    F::Header: WriteMap,
+    F::Entity: WriteMap
+{
+    fn write_map<W: io::Write>(&self, writer: &mut MapWriter<W>) -> io::Result<()> {
+        writer.write(&self.header)?;
+        for ent in self.entities.iter() {
+            writer.write(ent)?
+        }
+        Ok(())
+    }
+}
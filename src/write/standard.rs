@@ -0,0 +1,20 @@
+use {
+    std::io,
+    super::{WriteMap, MapWriter},
+    crate::parse::formats::standard::*
+};
+
+impl WriteMap for Vector2 {
+    fn write_map<W: io::Write>(&self, writer: &mut MapWriter<W>) -> io::Result<()> {
+        writer.number(self.x)?;
+        writer.number(self.y)
+    }
+}
+
+impl WriteMap for TextureAlignment {
+    fn write_map<W: io::Write>(&self, writer: &mut MapWriter<W>) -> io::Result<()> {
+        writer.write(&self.offset)?;
+        writer.number(self.rotation)?;
+        writer.write(&self.scale)
+    }
+}
@@ -0,0 +1,38 @@
+use {
+    std::io,
+    super::{WriteMap, MapWriter},
+    crate::parse::formats::valve::*
+};
+
+impl WriteMap for Scale {
+    fn write_map<W: io::Write>(&self, writer: &mut MapWriter<W>) -> io::Result<()> {
+        writer.number(self.u)?;
+        writer.number(self.v)
+    }
+}
+
+impl WriteMap for Axis {
+    fn write_map<W: io::Write>(&self, writer: &mut MapWriter<W>) -> io::Result<()> {
+        writer.token('[')?;
+        writer.number(self.normal.x)?;
+        writer.number(self.normal.y)?;
+        writer.number(self.normal.z)?;
+        writer.number(self.offset)?;
+        writer.token(']')
+    }
+}
+
+impl WriteMap for Axes {
+    fn write_map<W: io::Write>(&self, writer: &mut MapWriter<W>) -> io::Result<()> {
+        writer.write(&self.u)?;
+        writer.write(&self.v)
+    }
+}
+
+impl WriteMap for TextureAlignment {
+    fn write_map<W: io::Write>(&self, writer: &mut MapWriter<W>) -> io::Result<()> {
+        writer.write(&self.axes)?;
+        writer.number(self.rotation)?;
+        writer.write(&self.scale)
+    }
+}